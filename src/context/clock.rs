@@ -0,0 +1,70 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// A source of time for the [`Context`](crate::context::Context)'s loops and
+/// the delta timers ([`DeltaCycle`], [`DeltaAccumulator`]).
+///
+/// [`DeltaCycle`]: crate::context::DeltaCycle
+/// [`DeltaAccumulator`]: crate::context::DeltaAccumulator
+pub trait Clock: Clone + Send + Sync + 'static {
+    fn now(&self) -> Instant;
+}
+
+/// The real, monotonic system clock. See [`Instant::now`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline(always)]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves forward when explicitly told to.
+///
+/// Clones share the same time, so a clone may be handed to a
+/// [`Context`](crate::context::Context) or a delta timer while another is
+/// kept to [`advance`](ManualClock::advance) it by hand, e.g. in tests.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    origin: Instant,
+    elapsed_ns: Arc<AtomicU64>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            elapsed_ns: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed_ns
+            .fetch_add(duration.as_nanos() as u64, Ordering::AcqRel);
+    }
+
+    /// The total time the clock has been advanced by since its creation.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_ns.load(Ordering::Acquire))
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+}
@@ -0,0 +1,1166 @@
+pub mod clock;
+pub mod stats;
+pub mod time;
+
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "input")]
+use std::collections::HashMap;
+#[cfg(feature = "render")]
+use std::thread::JoinHandle;
+
+#[cfg(feature = "render")]
+use winit::dpi::PhysicalPosition;
+
+#[cfg(feature = "input")]
+use crate::input::{InputDispatcher, SECTION_COUNT, SLOT_COUNT};
+
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use stats::FrameStats;
+pub use time::TimeControl;
+
+/// A stateful context defines only initialization logic (which should also
+/// initialize the state) and loop logic.
+#[cfg(feature = "render")]
+pub type StatefulContext<Init, State> = Context<Init, State, EmptyRoutine>;
+
+/// A stateful context defines only initialization logic (which should also
+/// initialize the state) and loop logic.
+#[cfg(not(feature = "render"))]
+pub type StatefulContext<Init, State> = Context<Init, State>;
+
+/// A rendering context is not aware of logical state, it only cares about
+/// render logic and any state that may be related to it.
+#[cfg(feature = "render")]
+pub type RenderingContext<Render> = Context<EmptyRoutine, EmptyRoutine, Render>;
+
+/// A dumb context has no state and no logic of any kind. It's only function is
+/// that of creating an empty window.
+///
+/// This should only be used for testing or debugging.
+#[cfg(feature = "render")]
+pub type DumbContext = Context<EmptyRoutine, EmptyRoutine, EmptyRoutine>;
+
+/// A dumb context has no state and no logic of any kind.
+///
+/// This should only be used for testing or debugging.
+#[cfg(not(feature = "render"))]
+pub type DumbContext = Context<EmptyRoutine, EmptyRoutine>;
+
+// the input stream sizes are only meaningful along with the input dispatcher
+#[cfg(all(feature = "render", not(feature = "input")))]
+const SLOT_COUNT: usize = 0;
+#[cfg(all(feature = "render", not(feature = "input")))]
+const SECTION_COUNT: usize = 0;
+
+/// Stores glutin's context handles and implements winit's event handling.
+///
+/// This also makes use of 3 traits that define the application's routine:
+/// * [`Setup`] which runs after the creation of the context and collaborates
+///   to state initialisation.
+/// * [`Update`] which defines the state and handles the logic to run every
+///  'tick' in a continuous loop managed by the context.
+/// * [`Draw`] which defines render logic to run every frame before the window
+///   swaps buffers.
+///
+/// All of the context's timing, including the timestamps of the input, is
+/// driven by a [`Clock`], which is the real [`SystemClock`] unless specified
+/// otherwise.
+///
+/// With the `input` feature, `SLOTS` and `SECTIONS` are the sizes of the
+/// [`InputStream`](crate::input::stream::InputStream) shared with the
+/// [`InputState`](crate::input::InputState), as given to
+/// [`input::stream`](crate::input::stream()). A frame may hold up to
+/// `SLOTS * 2` packets before any further input is dropped, see
+/// [`SLOT_COUNT`](crate::input::SLOT_COUNT) for the cost of each event.
+#[cfg(feature = "render")]
+pub struct Context<
+    Init,
+    State,
+    Render,
+    C = SystemClock,
+    const SLOTS: usize = SLOT_COUNT,
+    const SECTIONS: usize = SECTION_COUNT,
+> where
+    Init: Setup<State, Render> + Sized,
+    State: Update + Default + Sized + Sync + Send,
+    Render: Draw + Default + Sized,
+    C: Clock,
+{
+    pub(crate) init: Option<Init>,
+    pub state_handle: StateHandle<State>,
+    pub renderer: Render,
+
+    #[cfg(feature = "input")]
+    pub(crate) input_dispatcher: InputDispatcher<SLOTS, SECTIONS>,
+    /// The cursors created from the custom images requested so far.
+    #[cfg(feature = "input")]
+    pub(crate) custom_cursors:
+        HashMap<crate::input::CustomCursorImage, winit::window::CustomCursor>,
+    pub(crate) render_delta: DeltaCycle<C>,
+    pub(crate) frame_stats: Arc<FrameStats>,
+    pub(crate) time_control: Arc<TimeControl>,
+    pub(crate) clock: C,
+
+    logic_thread: Option<JoinHandle<()>>,
+
+    pub(crate) parameters: crate::window::DisplayParameters,
+    pub(crate) display: Option<crate::window::DisplayHandle>,
+    pub(crate) gl_ctx: Option<glutin::context::PossiblyCurrentContext>,
+    pub(crate) gl_display: crate::window::GlDisplayState,
+}
+
+#[cfg(feature = "render")]
+impl<Init, State, Render, C, const SLOTS: usize, const SECTIONS: usize> Drop
+    for Context<Init, State, Render, C, SLOTS, SECTIONS>
+where
+    Init: Setup<State, Render> + Sized,
+    State: Update + Default + Sized + Sync + Send,
+    Render: Draw + Default + Sized,
+    C: Clock,
+{
+    fn drop(&mut self) {
+        if let Some(thread) = self.logic_thread.take() {
+            thread
+                .join()
+                .expect("logic thread has failed to join the main thread during context drop");
+        }
+    }
+}
+
+#[cfg(feature = "render")]
+pub enum StateHandle<State>
+where
+    State: Update + Default + Sized + Sync + Send,
+{
+    Acquired(JoinHandle<()>),
+    Preparing,
+    Uninitialised(State),
+}
+
+/// Pure logic context manager.
+///
+/// This also makes use of 2 traits that define the application's routine:
+/// * [`Setup`] which runs after the creation of the context and collaborates
+///   to state initialisation.
+/// * [`Update`] which defines the state and handles the logic to run every
+///  'tick' in a continuous loop managed by the context.
+#[cfg(not(feature = "render"))]
+pub struct Context<Init, State, C = SystemClock>
+where
+    Init: Setup<State> + Sized,
+    State: Update + Default + Sized,
+    C: Clock,
+{
+    init: Option<Init>,
+    pub state: State,
+
+    delta: DeltaCycle<C>,
+}
+
+#[cfg(not(feature = "render"))]
+impl<Init, State> Context<Init, State>
+where
+    Init: Setup<State>,
+    State: Update + Default,
+{
+    pub fn new(init: Init) -> Self {
+        Self::with_clock(init, SystemClock)
+    }
+}
+
+#[cfg(not(feature = "render"))]
+impl<Init, State, C> Context<Init, State, C>
+where
+    Init: Setup<State>,
+    State: Update + Default,
+    C: Clock,
+{
+    /// Same as [`Context::new`], but driven by the given `clock`.
+    pub fn with_clock(init: Init, clock: C) -> Self {
+        Self {
+            init: Some(init),
+            state: Default::default(),
+            delta: DeltaCycle::with_clock(clock),
+        }
+    }
+}
+
+#[cfg(all(feature = "render", feature = "input"))]
+impl<Init, State, Render, const SLOTS: usize, const SECTIONS: usize>
+    Context<Init, State, Render, SystemClock, SLOTS, SECTIONS>
+where
+    Init: Setup<State, Render>,
+    State: Update + Default + Sync + Send + 'static,
+    Render: Draw + Default,
+{
+    pub fn new(
+        init: Init,
+        input_dispatcher: InputDispatcher<SLOTS, SECTIONS>,
+        parameters: crate::window::DisplayParameters,
+    ) -> Self {
+        Self::with_clock(init, input_dispatcher, parameters, SystemClock)
+    }
+}
+
+#[cfg(all(feature = "render", not(feature = "input")))]
+impl<Init, State, Render> Context<Init, State, Render>
+where
+    Init: Setup<State, Render>,
+    State: Update + Default + Sync + Send + 'static,
+    Render: Draw + Default,
+{
+    pub fn new(init: Init, parameters: crate::window::DisplayParameters) -> Self {
+        Self::with_clock(init, parameters, SystemClock)
+    }
+}
+
+#[cfg(all(feature = "render", feature = "input"))]
+impl<Init, State, Render, C, const SLOTS: usize, const SECTIONS: usize>
+    Context<Init, State, Render, C, SLOTS, SECTIONS>
+where
+    Init: Setup<State, Render>,
+    State: Update + Default + Sync + Send + 'static,
+    Render: Draw + Default,
+    C: Clock,
+{
+    /// Same as [`Context::new`], but driven by the given `clock`, which also
+    /// stamps the input of the `input_dispatcher`.
+    pub fn with_clock(
+        init: Init,
+        mut input_dispatcher: InputDispatcher<SLOTS, SECTIONS>,
+        parameters: crate::window::DisplayParameters,
+        clock: C,
+    ) -> Self {
+        input_dispatcher.set_clock(clock.clone());
+        Self {
+            init: Some(init),
+            state_handle: StateHandle::Uninitialised(State::default()),
+            renderer: Default::default(),
+
+            input_dispatcher,
+            custom_cursors: HashMap::new(),
+            logic_thread: None,
+            render_delta: DeltaCycle::with_clock(clock.clone()),
+            frame_stats: Default::default(),
+            time_control: Default::default(),
+            clock,
+
+            parameters,
+            display: None,
+            gl_ctx: None,
+            gl_display: crate::window::GlDisplayState::Pending,
+        }
+    }
+}
+
+#[cfg(all(feature = "render", not(feature = "input")))]
+impl<Init, State, Render, C> Context<Init, State, Render, C>
+where
+    Init: Setup<State, Render>,
+    State: Update + Default + Sync + Send + 'static,
+    Render: Draw + Default,
+    C: Clock,
+{
+    /// Same as [`Context::new`], but driven by the given `clock`.
+    pub fn with_clock(init: Init, parameters: crate::window::DisplayParameters, clock: C) -> Self {
+        Self {
+            init: Some(init),
+            state_handle: StateHandle::Uninitialised(State::default()),
+            renderer: Default::default(),
+
+            logic_thread: None,
+            render_delta: DeltaCycle::with_clock(clock.clone()),
+            frame_stats: Default::default(),
+            time_control: Default::default(),
+            clock,
+
+            parameters,
+            display: None,
+            gl_ctx: None,
+            gl_display: crate::window::GlDisplayState::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "render")]
+impl<Init, State, Render, C, const SLOTS: usize, const SECTIONS: usize>
+    Context<Init, State, Render, C, SLOTS, SECTIONS>
+where
+    Init: Setup<State, Render>,
+    State: Update + Default + Sync + Send + 'static,
+    Render: Draw + Default,
+    C: Clock,
+{
+    /// The clock driving the timing of this [`Context`].
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Share the given frame timing `stats` with this [`Context`] instead of
+    /// the one it created on construction.
+    ///
+    /// This is useful to hand the statistics to the application state before
+    /// it is initialised.
+    pub fn with_frame_stats(mut self, stats: Arc<FrameStats>) -> Self {
+        self.frame_stats = stats;
+        self
+    }
+
+    /// The frame timing statistics of the render and logic loops.
+    ///
+    /// The returned [`Arc`] may be cloned and read from any thread.
+    pub fn frame_stats(&self) -> &Arc<FrameStats> {
+        &self.frame_stats
+    }
+
+    /// Share the given `control` with this [`Context`] instead of the one it
+    /// created on construction.
+    ///
+    /// This is useful to hand the time control to the application state
+    /// before it is initialised.
+    pub fn with_time_control(mut self, control: Arc<TimeControl>) -> Self {
+        self.time_control = control;
+        self
+    }
+
+    /// The control over the logic loop's simulation time.
+    ///
+    /// The returned [`Arc`] may be cloned and used from any thread.
+    pub fn time_control(&self) -> &Arc<TimeControl> {
+        &self.time_control
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn sync_cursor_options(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        use crate::input::GrabMode;
+
+        let Some(dh) = self.display.as_ref() else {
+            return;
+        };
+
+        let cursor = self.input_dispatcher.cursor_options();
+        if let Some(mode) = cursor.take_grab_change() {
+            match self.set_cursor_grab_mode(mode) {
+                Ok(applied) => cursor.report_grab(applied, false),
+                Err(error) => {
+                    tracing::event!(
+                        name: "cursor.grab.failed",
+                        tracing::Level::WARN,
+                        %error,
+                        ?mode,
+                        "Failed to grab the cursor"
+                    );
+                    cursor.report_grab(cursor.applied_grab_mode(), true);
+                }
+            }
+        }
+
+        if cursor.applied_grab_mode() == GrabMode::LockedRecentre && dh.window().has_focus() {
+            let size = dh.window().inner_size();
+            let center = PhysicalPosition::new(size.width / 2, size.height / 2);
+            let _ = dh.window().set_cursor_position(center);
+        }
+
+        if let Some(visible) = cursor.take_visibility_change() {
+            dh.window().set_cursor_visible(visible);
+        }
+
+        if cursor.take_appearance_change() {
+            use crate::input::CursorAppearance;
+
+            match cursor.appearance() {
+                CursorAppearance::Icon(icon) => dh.window().set_cursor(icon),
+                CursorAppearance::Custom(image) => {
+                    // created once per image, as creating a cursor is costly
+                    let custom = match self.custom_cursors.get(&image) {
+                        Some(custom) => custom.clone(),
+                        None => match image.source() {
+                            Ok(source) => {
+                                let custom = event_loop.create_custom_cursor(source);
+                                self.custom_cursors.insert(image, custom.clone());
+                                custom
+                            }
+                            Err(error) => {
+                                tracing::event!(
+                                    name: "cursor.custom.invalid",
+                                    tracing::Level::WARN,
+                                    %error,
+                                    "Failed to create custom cursor"
+                                );
+                                return;
+                            }
+                        },
+                    };
+                    dh.window().set_cursor(custom);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn sync_ime_options(&mut self) {
+        use std::sync::atomic::Ordering;
+        use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+        let Some(dh) = self.display.as_ref() else {
+            return;
+        };
+
+        let ime = self.input_dispatcher.ime_options();
+        if ime.dirty.swap(false, Ordering::AcqRel) {
+            let (x, y, width, height) = ime.cursor_area();
+            dh.window().set_ime_allowed(ime.allowed());
+            dh.window().set_ime_cursor_area(
+                PhysicalPosition::new(x, y),
+                PhysicalSize::new(width, height),
+            );
+        }
+    }
+
+    /// Force the cursor to change to a given `position`.
+    ///
+    /// If `positions` is [`None`], the cursor will be set to the center of
+    /// the window.
+    pub fn force_cursor_pos(&self, position: Option<PhysicalPosition<u32>>) {
+        let dh = self
+            .display
+            .as_ref()
+            .expect("DisplayHandle/Window must be initialised");
+
+        dh.window()
+            .set_cursor_position(position.unwrap_or_else(|| {
+                let size = dh.window().inner_size();
+                PhysicalPosition {
+                    x: size.width / 2,
+                    y: size.height / 2,
+                }
+            }))
+            .unwrap();
+    }
+
+    /// Set whether the cursor mode should be set to `grabbed`, locking it
+    /// where supported and confining it otherwise.
+    ///
+    /// This does not hide the cursor, which is done through the input's
+    /// `CursorOptions::set_visible` instead.
+    ///
+    /// # Panics
+    /// Will panic if the [`Context`]'s [`DisplayHandle`] has not yet
+    /// initialised; i.e. the window is still not finished.
+    pub fn set_cursor_grabbed(&self, grabbed: bool) -> Result<(), winit::error::ExternalError> {
+        use winit::window::CursorGrabMode;
+
+        let dh = self
+            .display
+            .as_ref()
+            .expect("DisplayHandle/Window must be initialised");
+
+        if grabbed {
+            lock_cursor(dh.window()).map(|_| ())
+        } else {
+            dh.window().set_cursor_grab(CursorGrabMode::None)
+        }
+    }
+
+    /// Grab the cursor as `mode`, falling back to [`GrabMode::Confined`] if
+    /// locking is not supported.
+    ///
+    /// # Returns
+    /// The applied mode, or an error if neither `mode` nor its fallback
+    /// could be applied, in which case the previous grab is left as is.
+    ///
+    /// # Panics
+    /// Will panic if the [`Context`]'s [`DisplayHandle`] has not yet
+    /// initialised; i.e. the window is still not finished.
+    ///
+    /// [`GrabMode::Confined`]: crate::input::GrabMode::Confined
+    #[cfg(feature = "input")]
+    pub fn set_cursor_grab_mode(
+        &self,
+        mode: crate::input::GrabMode,
+    ) -> Result<crate::input::GrabMode, winit::error::ExternalError> {
+        use crate::input::GrabMode;
+        use winit::window::CursorGrabMode;
+
+        let dh = self
+            .display
+            .as_ref()
+            .expect("DisplayHandle/Window must be initialised");
+        let window = dh.window();
+
+        match mode {
+            GrabMode::Free => window.set_cursor_grab(CursorGrabMode::None).map(|_| mode),
+            GrabMode::Confined => window
+                .set_cursor_grab(CursorGrabMode::Confined)
+                .map(|_| mode),
+            GrabMode::Locked => lock_cursor(window).map(|locked| {
+                if locked {
+                    GrabMode::Locked
+                } else {
+                    GrabMode::Confined
+                }
+            }),
+            GrabMode::LockedRecentre => lock_cursor(window).map(|locked| {
+                if locked {
+                    GrabMode::Locked
+                } else {
+                    GrabMode::LockedRecentre
+                }
+            }),
+        }
+    }
+
+    pub(crate) fn initialise_thread(&mut self) {
+        let state = std::mem::replace(&mut self.state_handle, StateHandle::Preparing);
+        if let StateHandle::Uninitialised(mut state) = state {
+            use tracing::{Level, event};
+
+            let stats = Arc::clone(&self.frame_stats);
+            let control = Arc::clone(&self.time_control);
+            let clock = self.clock.clone();
+            let handle = std::thread::spawn(move || {
+                let mut logic =
+                    LogicLoop::new(state.step_duration(), clock, stats).with_time_control(control);
+                loop {
+                    logic.frame(&mut state);
+                }
+            });
+            self.state_handle = StateHandle::Acquired(handle);
+            event!(
+                name: "context.state-thread.acquire",
+                Level::INFO,
+                "State/logic thread successfully acquired application state."
+            )
+        } else {
+            panic!(
+                "state/logic thread could not be initialised: application state is already acquired"
+            )
+        }
+    }
+
+    pub(crate) fn build_attributes(&self) -> winit::window::WindowAttributes {
+        use winit::{dpi::PhysicalSize, window::WindowAttributes};
+
+        use crate::window::DisplayWindowMode;
+
+        let attribs = WindowAttributes::default().with_title(self.parameters.title);
+        match self.parameters.mode {
+            DisplayWindowMode::Window => attribs.with_inner_size(PhysicalSize::new(
+                self.parameters.width,
+                self.parameters.height,
+            )),
+            DisplayWindowMode::FullScreen => {
+                use winit::window::Fullscreen;
+
+                attribs.with_fullscreen(Some(Fullscreen::Borderless(None)))
+            }
+        }
+    }
+}
+
+/// Lock the cursor, or confine it where locking is not supported.
+///
+/// # Returns
+/// Whether the cursor has been locked rather than confined.
+#[cfg(feature = "render")]
+fn lock_cursor(window: &winit::window::Window) -> Result<bool, winit::error::ExternalError> {
+    use winit::window::CursorGrabMode;
+
+    match window.set_cursor_grab(CursorGrabMode::Locked) {
+        Ok(()) => Ok(true),
+        Err(_) => {
+            #[cfg(debug_assertions)]
+            tracing::event!(
+                name: "cursor.grab.try_alternative",
+                tracing::Level::DEBUG,
+                "Failed to set cursor mode to 'Locked': will try 'Confined' instead"
+            );
+            window
+                .set_cursor_grab(CursorGrabMode::Confined)
+                .map(|_| false)
+        }
+    }
+}
+
+/// Drives the delta-accumulated logic loop of an [`Update`] state.
+///
+/// This is what the [`Context`]'s logic thread runs, one
+/// [`frame`](LogicLoop::frame) after the other.
+#[derive(Debug)]
+pub struct LogicLoop<C: Clock = SystemClock> {
+    delta: DeltaAccumulator<C>,
+    frame_delta: DeltaCycle<C>,
+    stats: Arc<FrameStats>,
+    control: Arc<TimeControl>,
+}
+
+impl<C: Clock> LogicLoop<C> {
+    pub fn new(step: Duration, clock: C, stats: Arc<FrameStats>) -> Self {
+        let now = clock.now();
+        Self {
+            delta: DeltaAccumulator::with_clock(step, now, clock.clone()),
+            frame_delta: DeltaCycle::with_clock_at(clock, now),
+            stats,
+            control: Default::default(),
+        }
+    }
+
+    pub fn with_time_control(mut self, control: Arc<TimeControl>) -> Self {
+        self.control = control;
+        self
+    }
+
+    pub fn accumulator(&self) -> &DeltaAccumulator<C> {
+        &self.delta
+    }
+
+    pub fn stats(&self) -> &Arc<FrameStats> {
+        &self.stats
+    }
+
+    pub fn time_control(&self) -> &Arc<TimeControl> {
+        &self.control
+    }
+
+    /// Run a single logic frame on the `state`.
+    ///
+    /// This will first wait until at least one whole step has been
+    /// accumulated, so with a [`ManualClock`] the clock must be advanced
+    /// beforehand or this will never return.
+    ///
+    /// While [`paused`](TimeControl::is_paused), this instead waits for one
+    /// step of unscaled time without accumulating it, then runs at most one
+    /// queued [`step`](TimeControl::step).
+    ///
+    /// # Returns
+    /// The amount of [`update`](Update::update) calls in this frame.
+    pub fn frame<State: Update>(&mut self, state: &mut State) -> u64 {
+        let stats = &self.stats;
+        let delta = &mut self.delta;
+        let clock = delta.cycle.clock.clone();
+
+        let _frame_span = stats.tracing_enabled().then(|| {
+            let frame = stats.logic().frames();
+            tracing::trace_span!("context.logic.frame", frame).entered()
+        });
+
+        state.new_frame(self.frame_delta.delta());
+
+        let paused = self.control.is_paused();
+        let wait_start = clock.now();
+        if paused {
+            while delta.step() > delta.delta_cycle().elapsed() {
+                std::thread::yield_now();
+            }
+            delta.skip();
+        } else {
+            delta.set_time_scale(self.control.scale());
+            while delta.step() > delta.accumulated() {
+                delta.accum();
+                std::thread::yield_now();
+            }
+        }
+        let wait_time = clock.now().duration_since(wait_start);
+
+        let mut iter = 0;
+        let tick = |state: &mut State, delta: &mut DeltaAccumulator<C>, iter: u64| {
+            if iter == 0 {
+                delta.set_step(state.step_duration());
+            }
+
+            let _tick_span = stats.tracing_enabled().then(|| {
+                let tick = stats.logic().ticks();
+                tracing::trace_span!("context.logic.tick", tick).entered()
+            });
+            let tick_start = clock.now();
+            state.update(delta.delta_step());
+            stats
+                .logic()
+                .record_tick(clock.now().duration_since(tick_start));
+        };
+
+        if paused {
+            if self.control.take_step() {
+                tick(state, delta, iter);
+                iter += 1;
+            }
+        } else {
+            while delta.overstep() {
+                tick(state, delta, iter);
+                iter += 1;
+            }
+        }
+        state.finish_frame();
+        self.frame_delta.sync();
+
+        stats
+            .logic()
+            .record_frame(self.frame_delta.delta_time(), wait_time, iter);
+        iter
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DeltaCycle<C: Clock = SystemClock> {
+    last: Instant,
+    delta: Duration,
+    clock: C,
+}
+
+#[derive(Clone, Debug)]
+pub struct DeltaAccumulator<C: Clock = SystemClock> {
+    step: Duration,
+    accumulated: Duration,
+    time_scale: f64,
+    cycle: DeltaCycle<C>,
+}
+
+impl<C: Clock + Default> Default for DeltaAccumulator<C> {
+    fn default() -> Self {
+        let clock = C::default();
+        Self::with_clock(Duration::ZERO, clock.now(), clock)
+    }
+}
+
+impl DeltaAccumulator {
+    pub fn new(step: Duration, start_time: Instant) -> Self {
+        Self::with_clock(step, start_time, SystemClock)
+    }
+}
+
+impl<C: Clock> DeltaAccumulator<C> {
+    pub fn with_clock(step: Duration, start_time: Instant, clock: C) -> Self {
+        Self {
+            step,
+            accumulated: Duration::ZERO,
+            time_scale: 1.0,
+            cycle: DeltaCycle::with_clock_at(clock, start_time),
+        }
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    pub fn set_step(&mut self, step: Duration) {
+        self.step = step;
+    }
+
+    pub fn delta_cycle(&self) -> &DeltaCycle<C> {
+        &self.cycle
+    }
+
+    pub fn accumulated(&self) -> Duration {
+        self.accumulated
+    }
+
+    /// Gets how "far ahead" the CPU is compared to the step speed.
+    pub fn time_ahead(&self) -> Duration {
+        self.step.saturating_sub(self.accumulated)
+    }
+
+    pub fn delta_step(&self) -> DeltaTime {
+        self.step.into()
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Set the multiplier of the time added on every [`accum`](Self::accum).
+    pub fn set_time_scale(&mut self, scale: f64) {
+        self.time_scale = scale;
+    }
+
+    pub fn accum(&mut self) {
+        self.cycle.sync();
+        self.accumulated += self.cycle.delta_time().mul_f64(self.time_scale);
+    }
+
+    /// Discard the time elapsed since the last [`accum`](Self::accum) without
+    /// accumulating it.
+    pub fn skip(&mut self) {
+        self.cycle.sync();
+    }
+
+    pub fn overstep(&mut self) -> bool {
+        let overstep = self.accumulated >= self.step;
+        if overstep {
+            self.accumulated -= self.step;
+        }
+
+        overstep
+    }
+}
+
+impl<C: Clock + Default> Default for DeltaCycle<C> {
+    fn default() -> Self {
+        Self::with_clock(C::default())
+    }
+}
+
+impl DeltaCycle {
+    pub fn new(start_time: Instant) -> Self {
+        Self::with_clock_at(SystemClock, start_time)
+    }
+}
+
+impl<C: Clock> DeltaCycle<C> {
+    /// Create a new [`DeltaCycle`] driven by `clock`, starting at its current
+    /// time.
+    pub fn with_clock(clock: C) -> Self {
+        let now = clock.now();
+        Self::with_clock_at(clock, now)
+    }
+
+    pub fn with_clock_at(clock: C, start_time: Instant) -> Self {
+        Self {
+            last: start_time,
+            delta: Duration::ZERO,
+            clock,
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn sync(&mut self) {
+        let now = self.clock.now();
+        self.delta = now.duration_since(self.last);
+        self.last = now;
+    }
+
+    pub const fn delta_time(&self) -> Duration {
+        self.delta
+    }
+
+    /// The time elapsed since the last [`sync`](Self::sync).
+    pub fn elapsed(&self) -> Duration {
+        self.clock.now().duration_since(self.last)
+    }
+
+    pub fn delta(&self) -> DeltaTime {
+        self.delta.into()
+    }
+}
+
+/// Between-frame time (commonly known as "delta time" or `dt`) representation
+/// in seconds in a 64-bits floating point which includes nanosecond detail.
+///
+/// Also see [`std::time::Duration::as_secs_f64`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeltaTime(f64);
+
+impl DeltaTime {
+    pub const fn as_millis_f64(&self) -> f64 {
+        self.0 * 1000.0
+    }
+
+    pub const fn as_millis(&self) -> f32 {
+        (self.0 * 1000.0) as f32
+    }
+
+    pub const fn as_macros_f64(&self) -> f64 {
+        self.as_millis_f64() * 1000.0
+    }
+
+    pub const fn as_macros(&self) -> f32 {
+        (self.as_millis() * 1000.0) as f32
+    }
+
+    pub const fn as_nanos_f64(&self) -> f64 {
+        self.as_macros_f64() * 1000.0
+    }
+
+    pub const fn as_nanos(&self) -> f32 {
+        (self.as_macros() * 1000.0) as f32
+    }
+
+    pub const fn as_f32(&self) -> f32 {
+        self.0 as f32
+    }
+
+    pub const fn as_f64(&self) -> f64 {
+        self.0
+    }
+}
+
+impl From<Duration> for DeltaTime {
+    fn from(value: Duration) -> Self {
+        DeltaTime(value.as_secs_f64())
+    }
+}
+
+impl Deref for DeltaTime {
+    type Target = f64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(not(feature = "render"))]
+pub trait Setup<State>
+where
+    State: Update + Default,
+{
+    fn init(self, state: &mut State) -> Result<(), &'static str>
+    where
+        Self: Sized;
+}
+
+#[cfg(feature = "render")]
+pub trait Setup<State, Render>
+where
+    State: Update + Default,
+    Render: Draw + Default,
+{
+    fn init(self, state: &mut State, renderer: &mut Render) -> Result<(), &'static str>
+    where
+        Self: Sized;
+}
+
+pub trait Update {
+    fn step_duration(&self) -> Duration;
+
+    fn update(&mut self, delta: DeltaTime);
+
+    /// Arbitrary logic to run when a new logic frame is started.
+    ///
+    /// This is guaranteed to run every frame, before the application's
+    /// [`update cycle`]. It is still called independently of whether the
+    /// [`update cycle`] runs or not due to [`delta accumulation`] conditions.
+    ///
+    /// Multiple iterations of the [`update cycle`] may occur in between
+    /// `new_frame` calls due to [`delta accumulation`] conditions.
+    ///
+    /// If using the [`input`] features, this is where you will want your
+    /// [`input frame sync`](crate::input::InputState::sync) to happen.
+    ///
+    /// [`update cycle`]: Update::update
+    /// [`delta accumulation`]: DeltaAccumulator
+    fn new_frame(&mut self, _frame_delta: DeltaTime);
+
+    /// Finishing logic for the frame.
+    ///
+    /// This is run after the application's delta-accumulated [`update cycle`]
+    /// has ended.
+    ///
+    /// [`update cycle`]: Update::update
+    fn finish_frame(&mut self);
+}
+
+#[cfg(feature = "render")]
+pub trait Draw {
+    fn set_resolution(&mut self, resolution: (f32, f32));
+
+    fn draw(&mut self, delta: DeltaTime);
+
+    /// Run on the render thread when a file is dragged over or dropped onto
+    /// the window, as the event is sent to the logic thread.
+    ///
+    /// This is where dropped images can be decoded into textures, see
+    /// [`FileEvent::load_texture`](crate::input::FileEvent).
+    #[cfg(feature = "input")]
+    fn file_event(&mut self, _event: &crate::input::FileEvent) {}
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EmptyRoutine;
+
+#[cfg(feature = "render")]
+impl<State, Render> Setup<State, Render> for EmptyRoutine
+where
+    State: Update + Default,
+    Render: Draw + Default,
+{
+    fn init(self, _: &mut State, _: &mut Render) -> Result<(), &'static str>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+impl Update for EmptyRoutine {
+    fn update(&mut self, _: DeltaTime) {}
+
+    fn step_duration(&self) -> Duration {
+        Duration::default()
+    }
+
+    fn new_frame(&mut self, _frame_delta: DeltaTime) {}
+
+    fn finish_frame(&mut self) {}
+}
+
+#[cfg(feature = "render")]
+impl Draw for EmptyRoutine {
+    fn draw(&mut self, _: DeltaTime) {}
+
+    fn set_resolution(&mut self, _: (f32, f32)) {}
+}
+
+#[cfg(feature = "render")]
+impl<State, Render, F> Setup<State, Render> for F
+where
+    State: Update + Default,
+    Render: Draw + Default,
+    F: FnOnce(&mut State, &mut Render) -> Result<(), &'static str>,
+{
+    fn init(self, state: &mut State, renderer: &mut Render) -> Result<(), &'static str>
+    where
+        Self: Sized,
+    {
+        self(state, renderer)
+    }
+}
+
+#[cfg(not(feature = "render"))]
+impl<State, F> Setup<State> for F
+where
+    State: Update + Default,
+    F: FnOnce(&mut State) -> Result<(), &'static str>,
+{
+    fn init(self, state: &mut State) -> Result<(), &'static str>
+    where
+        Self: Sized,
+    {
+        self(state)
+    }
+}
+
+#[cfg(not(feature = "render"))]
+impl<State> Setup<State> for EmptyRoutine
+where
+    State: Update + Default,
+{
+    fn init(self, _: &mut State) -> Result<(), &'static str>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: Duration = Duration::from_millis(10);
+
+    #[derive(Debug, Default)]
+    struct Counter {
+        updates: u32,
+        frames: u32,
+        finished: u32,
+        last_frame_delta: Duration,
+    }
+
+    impl Update for Counter {
+        fn step_duration(&self) -> Duration {
+            STEP
+        }
+
+        fn update(&mut self, delta: DeltaTime) {
+            assert_eq!(delta.as_f64(), STEP.as_secs_f64());
+            self.updates += 1;
+        }
+
+        fn new_frame(&mut self, frame_delta: DeltaTime) {
+            self.frames += 1;
+            self.last_frame_delta = Duration::from_secs_f64(frame_delta.as_f64());
+        }
+
+        fn finish_frame(&mut self) {
+            self.finished += 1;
+        }
+    }
+
+    #[test]
+    fn logic_loop_fixed_steps() {
+        let clock = ManualClock::new();
+        let mut logic = LogicLoop::new(STEP, clock.clone(), Default::default());
+        let mut state = Counter::default();
+
+        clock.advance(STEP * 3 + STEP / 2);
+        assert_eq!(logic.frame(&mut state), 3);
+        assert_eq!(state.updates, 3);
+        assert_eq!(logic.accumulator().accumulated(), STEP / 2);
+
+        clock.advance(STEP / 2);
+        assert_eq!(logic.frame(&mut state), 1);
+        assert_eq!(state.updates, 4);
+        assert_eq!(logic.accumulator().accumulated(), Duration::ZERO);
+
+        clock.advance(STEP);
+        assert_eq!(logic.frame(&mut state), 1);
+        assert_eq!(state.updates, 5);
+
+        assert_eq!(state.frames, 3);
+        assert_eq!(state.finished, 3);
+        assert_eq!(state.last_frame_delta, STEP / 2);
+
+        let stats = logic.stats().logic();
+        assert_eq!(stats.frames(), 3);
+        assert_eq!(stats.ticks(), 5);
+        assert_eq!(stats.steps().summary().max, 3);
+    }
+
+    #[test]
+    fn logic_loop_time_control() {
+        let clock = ManualClock::new();
+        let control = Arc::new(TimeControl::new());
+        let mut logic = LogicLoop::new(STEP, clock.clone(), Default::default())
+            .with_time_control(Arc::clone(&control));
+        let mut state = Counter::default();
+
+        control.set_scale(0.5);
+        clock.advance(STEP * 4);
+        assert_eq!(logic.frame(&mut state), 2);
+
+        control.pause();
+        control.step(2);
+        clock.advance(STEP * 10);
+        assert_eq!(logic.frame(&mut state), 1);
+        clock.advance(STEP);
+        assert_eq!(logic.frame(&mut state), 1);
+        clock.advance(STEP);
+        assert_eq!(logic.frame(&mut state), 0);
+        assert_eq!(control.pending_steps(), 0);
+
+        // time elapsed while paused is never accumulated
+        control.resume();
+        control.set_scale(1.0);
+        clock.advance(STEP);
+        assert_eq!(logic.frame(&mut state), 1);
+
+        // steps are only queued while paused
+        control.step(3);
+        assert_eq!(control.pending_steps(), 0);
+        clock.advance(STEP);
+        assert_eq!(logic.frame(&mut state), 1);
+
+        assert_eq!(state.updates, 6);
+        assert_eq!(state.frames, 6);
+        assert_eq!(state.finished, 6);
+    }
+
+    #[test]
+    fn manual_clock_shared() {
+        let clock = ManualClock::new();
+        let mut cycle = DeltaCycle::with_clock(clock.clone());
+
+        cycle.sync();
+        assert_eq!(cycle.delta_time(), Duration::ZERO);
+
+        clock.advance(Duration::from_millis(16));
+        cycle.sync();
+        assert_eq!(cycle.delta_time(), Duration::from_millis(16));
+        assert_eq!(cycle.clock().elapsed(), Duration::from_millis(16));
+    }
+}
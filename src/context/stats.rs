@@ -0,0 +1,317 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// The amount of samples retained by every [`SampleWindow`].
+pub const STATS_WINDOW: usize = 240;
+
+/// Frame timing statistics for both the render loop and the logic loop.
+///
+/// This is shared between the render thread and the logic thread through an
+/// [`Arc`](std::sync::Arc), see [`Context::frame_stats`]. Each loop is the
+/// sole writer of its own half of the statistics, while either thread (or any
+/// other) may read them at any time without blocking.
+///
+/// If [`tracing spans`](FrameStats::set_tracing) are enabled, every render
+/// frame, logic frame and logic tick will also be wrapped in a `TRACE` level
+/// span.
+///
+/// [`Context::frame_stats`]: crate::context::Context::frame_stats
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    render: RenderStats,
+    logic: LogicStats,
+    tracing: AtomicBool,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn render(&self) -> &RenderStats {
+        &self.render
+    }
+
+    pub fn logic(&self) -> &LogicStats {
+        &self.logic
+    }
+
+    /// Set whether render frames, logic frames and logic ticks should be
+    /// emitted as `tracing` spans.
+    pub fn set_tracing(&self, enabled: bool) {
+        self.tracing.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn tracing_enabled(&self) -> bool {
+        self.tracing.load(Ordering::Relaxed)
+    }
+}
+
+/// Statistics of the render loop, written by the render thread.
+#[derive(Debug, Default)]
+pub struct RenderStats {
+    frames: AtomicU64,
+    frame_time: SampleWindow,
+    swap_time: SampleWindow,
+}
+
+impl RenderStats {
+    /// The total amount of frames rendered.
+    pub fn frames(&self) -> u64 {
+        self.frames.load(Ordering::Relaxed)
+    }
+
+    /// The time between the start of two consecutive frames.
+    pub fn frame_time(&self) -> &SampleWindow {
+        &self.frame_time
+    }
+
+    /// The time spent swapping the window's buffers.
+    pub fn swap_time(&self) -> &SampleWindow {
+        &self.swap_time
+    }
+
+    pub(crate) fn record_frame(&self, frame_time: Duration, swap_time: Duration) {
+        self.frame_time.push_duration(frame_time);
+        self.swap_time.push_duration(swap_time);
+        self.frames.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Statistics of the logic loop, written by the logic thread.
+#[derive(Debug, Default)]
+pub struct LogicStats {
+    frames: AtomicU64,
+    ticks: AtomicU64,
+    frame_time: SampleWindow,
+    tick_time: SampleWindow,
+    wait_time: SampleWindow,
+    steps: SampleWindow,
+}
+
+impl LogicStats {
+    /// The total amount of logic frames run.
+    pub fn frames(&self) -> u64 {
+        self.frames.load(Ordering::Relaxed)
+    }
+
+    /// The total amount of [`update`](crate::context::Update::update) calls.
+    pub fn ticks(&self) -> u64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+
+    /// The time between the start of two consecutive logic frames.
+    pub fn frame_time(&self) -> &SampleWindow {
+        &self.frame_time
+    }
+
+    /// The time spent in a single [`update`](crate::context::Update::update)
+    /// call.
+    pub fn tick_time(&self) -> &SampleWindow {
+        &self.tick_time
+    }
+
+    /// The time spent waiting for the [`DeltaAccumulator`] to accumulate a
+    /// whole step.
+    ///
+    /// [`DeltaAccumulator`]: crate::context::DeltaAccumulator
+    pub fn wait_time(&self) -> &SampleWindow {
+        &self.wait_time
+    }
+
+    /// The amount of [`update`](crate::context::Update::update) calls in each
+    /// logic frame.
+    pub fn steps(&self) -> &SampleWindow {
+        &self.steps
+    }
+
+    pub(crate) fn record_tick(&self, tick_time: Duration) {
+        self.tick_time.push_duration(tick_time);
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_frame(&self, frame_time: Duration, wait_time: Duration, steps: u64) {
+        self.frame_time.push_duration(frame_time);
+        self.wait_time.push_duration(wait_time);
+        self.steps.push(steps);
+        self.frames.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A rolling window of the last [`STATS_WINDOW`] samples.
+///
+/// Durations are stored as nanoseconds.
+///
+/// This is meant to have a single writer, but may be read from any amount of
+/// threads. Reads are not synchronised with writes, so a summary may include a
+/// sample that has been pushed while it was being computed.
+#[derive(Debug)]
+pub struct SampleWindow {
+    samples: [AtomicU64; STATS_WINDOW],
+    written: AtomicU64,
+}
+
+impl Default for SampleWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SampleWindow {
+    pub fn new() -> Self {
+        Self {
+            samples: core::array::from_fn(|_| AtomicU64::new(0)),
+            written: AtomicU64::new(0),
+        }
+    }
+
+    pub fn push(&self, value: u64) {
+        let written = self.written.load(Ordering::Relaxed);
+        let i = written as usize % STATS_WINDOW;
+        self.samples[i].store(value, Ordering::Relaxed);
+        self.written.store(written + 1, Ordering::Release);
+    }
+
+    #[inline(always)]
+    pub fn push_duration(&self, duration: Duration) {
+        self.push(duration.as_nanos() as u64);
+    }
+
+    /// The amount of samples currently in the window.
+    pub fn len(&self) -> usize {
+        (self.written.load(Ordering::Acquire) as usize).min(STATS_WINDOW)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The most recently pushed sample, or `0` if the window is empty.
+    pub fn last(&self) -> u64 {
+        let written = self.written.load(Ordering::Acquire);
+        if written == 0 {
+            return 0;
+        }
+        let i = (written - 1) as usize % STATS_WINDOW;
+        self.samples[i].load(Ordering::Relaxed)
+    }
+
+    pub fn last_duration(&self) -> Duration {
+        Duration::from_nanos(self.last())
+    }
+
+    /// Compute the minimum, maximum, mean and percentiles of the samples
+    /// currently in the window.
+    pub fn summary(&self) -> WindowSummary<u64> {
+        let len = self.len();
+        let mut sorted: Vec<u64> = self.samples[..len]
+            .iter()
+            .map(|sample| sample.load(Ordering::Relaxed))
+            .collect();
+        sorted.sort_unstable();
+
+        if sorted.is_empty() {
+            return WindowSummary::default();
+        }
+
+        let sum: u128 = sorted.iter().map(|&sample| sample as u128).sum();
+        let percentile = |p: f64| {
+            let rank = (p * len as f64).ceil() as usize;
+            sorted[rank.clamp(1, len) - 1]
+        };
+
+        WindowSummary {
+            samples: len,
+            min: sorted[0],
+            max: sorted[len - 1],
+            mean: (sum / len as u128) as u64,
+            p50: percentile(0.50),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+        }
+    }
+
+    /// Same as [`summary`](SampleWindow::summary), interpreting the samples as
+    /// nanoseconds.
+    pub fn summary_duration(&self) -> WindowSummary<Duration> {
+        self.summary().map(Duration::from_nanos)
+    }
+}
+
+/// A summary of the samples of a [`SampleWindow`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WindowSummary<T> {
+    pub samples: usize,
+    pub min: T,
+    pub max: T,
+    pub mean: T,
+    pub p50: T,
+    pub p95: T,
+    pub p99: T,
+}
+
+impl<T> WindowSummary<T> {
+    pub fn map<U, F: Fn(T) -> U>(self, op: F) -> WindowSummary<U> {
+        WindowSummary {
+            samples: self.samples,
+            min: op(self.min),
+            max: op(self.max),
+            mean: op(self.mean),
+            p50: op(self.p50),
+            p95: op(self.p95),
+            p99: op(self.p99),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_summary() {
+        let window = SampleWindow::new();
+        assert_eq!(window.summary(), WindowSummary::default());
+
+        // pushed out of order, the summary sorts them
+        for sample in (1..=100).rev() {
+            window.push(sample);
+        }
+        assert_eq!(window.last(), 1);
+        assert_eq!(
+            window.summary(),
+            WindowSummary {
+                samples: 100,
+                min: 1,
+                max: 100,
+                mean: 50,
+                p50: 50,
+                p95: 95,
+                p99: 99,
+            }
+        );
+
+        // only the last samples are kept once the window has wrapped around
+        let window = SampleWindow::new();
+        for sample in 1..=300 {
+            window.push(sample);
+        }
+        assert_eq!(window.len(), STATS_WINDOW);
+        assert_eq!(window.last(), 300);
+        assert_eq!(
+            window.summary(),
+            WindowSummary {
+                samples: STATS_WINDOW,
+                min: 61,
+                max: 300,
+                mean: 180,
+                p50: 180,
+                p95: 288,
+                p99: 298,
+            }
+        );
+        assert_eq!(window.summary_duration().max, Duration::from_nanos(300));
+    }
+}
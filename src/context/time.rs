@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+/// Shared control over the flow of simulation time in the logic loop.
+///
+/// This is shared through an [`Arc`](std::sync::Arc) (see
+/// [`Context::time_control`]) and may be used from any thread, e.g. from a
+/// debug UI on the render thread or from the logic state itself.
+///
+/// * The time scale multiplies the time accumulated by the logic loop: a scale
+///   of `0.5` results in half the [`update`] calls in the same amount of time.
+///   The step passed to [`update`] is never scaled.
+/// * While paused, no time is accumulated at all. The logic loop still runs
+///   its frames ([`new_frame`] and [`finish_frame`] are still called) at the
+///   pace of one step per frame, but [`update`] is never called unless steps
+///   have been queued with [`TimeControl::step`].
+///
+/// [`Context::time_control`]: crate::context::Context::time_control
+/// [`update`]: crate::context::Update::update
+/// [`new_frame`]: crate::context::Update::new_frame
+/// [`finish_frame`]: crate::context::Update::finish_frame
+#[derive(Debug)]
+pub struct TimeControl {
+    scale: AtomicU64,
+    paused: AtomicBool,
+    steps: AtomicU32,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeControl {
+    pub fn new() -> Self {
+        Self {
+            scale: AtomicU64::new(1.0f64.to_bits()),
+            paused: AtomicBool::new(false),
+            steps: AtomicU32::new(0),
+        }
+    }
+
+    pub fn scale(&self) -> f64 {
+        f64::from_bits(self.scale.load(Ordering::Relaxed))
+    }
+
+    /// Set the multiplier of the time accumulated by the logic loop.
+    ///
+    /// A `scale` of `0` freezes time the same way [`pause`](Self::pause)
+    /// does.
+    ///
+    /// # Panics
+    /// If `scale` is negative or not finite.
+    pub fn set_scale(&self, scale: f64) {
+        assert!(
+            scale.is_finite() && scale >= 0.0,
+            "time scale must be finite and non-negative"
+        );
+        self.scale.store(scale.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Whether simulation time is frozen, either by [`pause`](Self::pause)
+    /// or by a time scale of `0`.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire) || self.scale() == 0.0
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Release);
+    }
+
+    /// Resume simulation time, discarding any steps still queued.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Release);
+        self.steps.store(0, Ordering::Release);
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        if paused {
+            self.pause();
+        } else {
+            self.resume();
+        }
+    }
+
+    /// Toggle between paused and resumed.
+    ///
+    /// # Returns
+    /// Whether it is now paused.
+    pub fn toggle_pause(&self) -> bool {
+        let paused = !self.paused.load(Ordering::Acquire);
+        self.set_paused(paused);
+        paused
+    }
+
+    /// Queue `count` [`update`](crate::context::Update::update) calls to run
+    /// while paused.
+    ///
+    /// Queued steps are run one per logic frame, so that every step may be
+    /// observed on its own.
+    /// They are ignored while not paused.
+    pub fn step(&self, count: u32) {
+        if self.is_paused() {
+            self.steps.fetch_add(count, Ordering::AcqRel);
+        }
+    }
+
+    /// The amount of queued steps that have yet to run.
+    pub fn pending_steps(&self) -> u32 {
+        self.steps.load(Ordering::Acquire)
+    }
+
+    /// Consume a single queued step, if any.
+    pub(crate) fn take_step(&self) -> bool {
+        self.steps
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |steps| {
+                steps.checked_sub(1)
+            })
+            .is_ok()
+    }
+}
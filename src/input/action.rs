@@ -0,0 +1,600 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    StringHash, StringMap, hash_string,
+    input::{
+        GamepadAxis, GamepadButton, InputSnapshot, KeyCode, Keys, MAX_HOLD_FRAMES, Modifiers,
+        MouseButton, RELEASE_SIGNAL,
+    },
+};
+
+/// A single input an action may be bound to.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    /// A physical key, regardless of the keyboard layout.
+    Key(KeyCode),
+    /// A key as interpreted by the keyboard layout.
+    /// See [`LogicalKeyCode`](crate::input::LogicalKeyCode).
+    Logical(char),
+    Mouse(MouseButton),
+    /// A button of any gamepad.
+    Gamepad(GamepadButton),
+}
+
+impl Trigger {
+    /// The held frames of the trigger, following the frame counting of
+    /// [`Keys`].
+    fn frames(self, keys: &Keys) -> u16 {
+        match self {
+            Trigger::Key(code) => keys.key_frames(code),
+            Trigger::Logical(c) => keys.logical_frames(c),
+            Trigger::Mouse(button) => keys.mouse_frames(button),
+            Trigger::Gamepad(button) => keys.gamepad_frames_any(button),
+        }
+    }
+}
+
+/// A [`Trigger`], optionally chorded with modifier keys, e.g. Ctrl+Z.
+///
+/// A chord is only active while all of its `modifiers` are held; other
+/// modifiers may be held as well, see [`Keys::modifiers_held`].
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub trigger: Trigger,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub modifiers: Modifiers,
+}
+
+impl Binding {
+    pub const fn new(trigger: Trigger) -> Self {
+        Self {
+            trigger,
+            modifiers: Modifiers::empty(),
+        }
+    }
+
+    pub const fn key(code: KeyCode) -> Self {
+        Self::new(Trigger::Key(code))
+    }
+
+    pub const fn logical(c: char) -> Self {
+        Self::new(Trigger::Logical(c))
+    }
+
+    pub const fn mouse(button: MouseButton) -> Self {
+        Self::new(Trigger::Mouse(button))
+    }
+
+    pub const fn gamepad(button: GamepadButton) -> Self {
+        Self::new(Trigger::Gamepad(button))
+    }
+
+    pub const fn with_modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// The held frames of the trigger, or `0` while the modifiers of the
+    /// chord are not held.
+    pub fn frames(&self, keys: &Keys) -> u16 {
+        if keys.modifiers_held(self.modifiers) {
+            self.trigger.frames(keys)
+        } else {
+            0
+        }
+    }
+
+    pub fn is_down(&self, keys: &Keys) -> bool {
+        let frames = self.frames(keys);
+        frames != 0 && frames != RELEASE_SIGNAL
+    }
+}
+
+impl From<Trigger> for Binding {
+    fn from(value: Trigger) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<KeyCode> for Binding {
+    fn from(value: KeyCode) -> Self {
+        Self::key(value)
+    }
+}
+
+impl From<char> for Binding {
+    fn from(value: char) -> Self {
+        Self::logical(value)
+    }
+}
+
+impl From<MouseButton> for Binding {
+    fn from(value: MouseButton) -> Self {
+        Self::mouse(value)
+    }
+}
+
+impl From<GamepadButton> for Binding {
+    fn from(value: GamepadButton) -> Self {
+        Self::gamepad(value)
+    }
+}
+
+/// A source of a single analog axis value, in the range `-1.0..=1.0`.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AxisBinding {
+    /// Composite of two digital bindings, e.g. `A` and `D` for a horizontal
+    /// axis.
+    Digital {
+        negative: Binding,
+        positive: Binding,
+    },
+    /// An axis of any gamepad, with deadzones applied. The value of the
+    /// gamepad with the largest magnitude is used.
+    Gamepad { axis: GamepadAxis, inverted: bool },
+}
+
+impl AxisBinding {
+    pub fn digital(negative: impl Into<Binding>, positive: impl Into<Binding>) -> Self {
+        Self::Digital {
+            negative: negative.into(),
+            positive: positive.into(),
+        }
+    }
+
+    pub const fn gamepad(axis: GamepadAxis) -> Self {
+        Self::Gamepad {
+            axis,
+            inverted: false,
+        }
+    }
+
+    fn value(&self, snapshot: &InputSnapshot) -> f32 {
+        match self {
+            AxisBinding::Digital { negative, positive } => {
+                let keys = snapshot.keys();
+                positive.is_down(keys) as i8 as f32 - negative.is_down(keys) as i8 as f32
+            }
+            AxisBinding::Gamepad { axis, inverted } => {
+                let gamepads = snapshot.gamepads();
+                let value = gamepads
+                    .connected()
+                    .map(|pad| gamepads.axis(pad, *axis))
+                    .fold(
+                        0.0f32,
+                        |max, value| {
+                            if value.abs() > max.abs() { value } else { max }
+                        },
+                    );
+                if *inverted { -value } else { value }
+            }
+        }
+    }
+}
+
+/// The serialisable bindings of an [`ActionMap`], keyed by action name.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionBindings {
+    pub actions: BTreeMap<String, Vec<Binding>>,
+    pub axes: BTreeMap<String, Vec<AxisBinding>>,
+    /// `x` and `y` bindings of every two-dimensional axis.
+    pub axes_2d: BTreeMap<String, [Vec<AxisBinding>; 2]>,
+}
+
+#[derive(Clone, Debug)]
+struct Action {
+    name: String,
+    bindings: Vec<Binding>,
+    frames: u16,
+}
+
+#[derive(Clone, Debug)]
+struct Axis {
+    name: String,
+    bindings: Vec<AxisBinding>,
+    value: f32,
+}
+
+impl Axis {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            bindings: Vec::new(),
+            value: 0.0,
+        }
+    }
+
+    fn update(&mut self, snapshot: &InputSnapshot) {
+        let value: f32 = self
+            .bindings
+            .iter()
+            .map(|binding| binding.value(snapshot))
+            .sum();
+        self.value = value.clamp(-1.0, 1.0);
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Axis2d {
+    x: Axis,
+    y: Axis,
+}
+
+/// Named actions and axes, keyed by the [`StringHash`] of their name and
+/// bound to any amount of inputs.
+///
+/// Must be [`updated`](ActionMap::update) once per frame, after the input has
+/// been polled with [`InputState::poll_key_events`].
+///
+/// Digital actions follow the same frame counting as [`Keys`]: an action is
+/// pressed on the first frame any of its bindings is down, held on the
+/// following frames and released on the first frame none of them are down.
+/// A binding pressed and released within a single frame releases the action,
+/// the same as the key itself.
+///
+/// [`InputState::poll_key_events`]: crate::input::InputState::poll_key_events
+#[derive(Clone, Debug, Default)]
+pub struct ActionMap {
+    actions: StringMap<Action>,
+    axes: StringMap<Axis>,
+    axes_2d: StringMap<Axis2d>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_bindings(bindings: &ActionBindings) -> Self {
+        let mut map = Self::new();
+        map.load_bindings(bindings);
+        map
+    }
+
+    /// Replace the bindings of every action and axis in `bindings`, keeping
+    /// those not present in it.
+    pub fn load_bindings(&mut self, bindings: &ActionBindings) {
+        for (name, action) in &bindings.actions {
+            self.rebind(name, action.iter().copied());
+        }
+        for (name, axis) in &bindings.axes {
+            self.rebind_axis(name, axis.iter().copied());
+        }
+        for (name, [x, y]) in &bindings.axes_2d {
+            self.rebind_axis_2d(name, x.iter().copied(), y.iter().copied());
+        }
+    }
+
+    pub fn to_bindings(&self) -> ActionBindings {
+        ActionBindings {
+            actions: self
+                .actions
+                .values()
+                .map(|action| (action.name.clone(), action.bindings.clone()))
+                .collect(),
+            axes: self
+                .axes
+                .values()
+                .map(|axis| (axis.name.clone(), axis.bindings.clone()))
+                .collect(),
+            axes_2d: self
+                .axes_2d
+                .values()
+                .map(|axis| {
+                    (
+                        axis.x.name.clone(),
+                        [axis.x.bindings.clone(), axis.y.bindings.clone()],
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn action_mut(&mut self, name: &str) -> &mut Action {
+        self.actions
+            .entry(hash_string(name))
+            .or_insert_with(|| Action {
+                name: name.to_string(),
+                bindings: Vec::new(),
+                frames: 0,
+            })
+    }
+
+    /// Add `binding` to the action `name`, creating it if needed.
+    pub fn bind(&mut self, name: &str, binding: impl Into<Binding>) {
+        self.action_mut(name).bindings.push(binding.into());
+    }
+
+    /// Replace all bindings of the action `name`, creating it if needed.
+    pub fn rebind(&mut self, name: &str, bindings: impl IntoIterator<Item = Binding>) {
+        let action = self.action_mut(name);
+        action.bindings.clear();
+        action.bindings.extend(bindings);
+    }
+
+    pub fn bind_axis(&mut self, name: &str, binding: AxisBinding) {
+        self.axes
+            .entry(hash_string(name))
+            .or_insert_with(|| Axis::new(name))
+            .bindings
+            .push(binding);
+    }
+
+    pub fn rebind_axis(&mut self, name: &str, bindings: impl IntoIterator<Item = AxisBinding>) {
+        let axis = self
+            .axes
+            .entry(hash_string(name))
+            .or_insert_with(|| Axis::new(name));
+        axis.bindings.clear();
+        axis.bindings.extend(bindings);
+    }
+
+    /// Add a pair of `x` and `y` bindings to the two-dimensional axis `name`,
+    /// e.g. `A`/`D` and `S`/`W` for WASD movement.
+    pub fn bind_axis_2d(&mut self, name: &str, x: AxisBinding, y: AxisBinding) {
+        let axis = self.axis_2d_mut(name);
+        axis.x.bindings.push(x);
+        axis.y.bindings.push(y);
+    }
+
+    pub fn rebind_axis_2d(
+        &mut self,
+        name: &str,
+        x: impl IntoIterator<Item = AxisBinding>,
+        y: impl IntoIterator<Item = AxisBinding>,
+    ) {
+        let axis = self.axis_2d_mut(name);
+        axis.x.bindings.clear();
+        axis.x.bindings.extend(x);
+        axis.y.bindings.clear();
+        axis.y.bindings.extend(y);
+    }
+
+    fn axis_2d_mut(&mut self, name: &str) -> &mut Axis2d {
+        self.axes_2d
+            .entry(hash_string(name))
+            .or_insert_with(|| Axis2d {
+                x: Axis::new(name),
+                y: Axis::new(name),
+            })
+    }
+
+    /// Remove the action or axis `name` along with all of its bindings.
+    pub fn remove(&mut self, name: StringHash) {
+        self.actions.remove(&name);
+        self.axes.remove(&name);
+        self.axes_2d.remove(&name);
+    }
+
+    pub fn bindings(&self, action: StringHash) -> &[Binding] {
+        self.actions
+            .get(&action)
+            .map(|action| action.bindings.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn update(&mut self, snapshot: &InputSnapshot) {
+        let keys = snapshot.keys();
+        for action in self.actions.values_mut() {
+            let mut down = false;
+            let mut released = false;
+            for binding in &action.bindings {
+                match binding.frames(keys) {
+                    0 => {}
+                    RELEASE_SIGNAL => released = true,
+                    _ => down = true,
+                }
+            }
+            action.frames = match (down, action.frames) {
+                (true, 0 | RELEASE_SIGNAL) => 1,
+                (true, MAX_HOLD_FRAMES) => MAX_HOLD_FRAMES,
+                (true, frames) => frames + 1,
+                // pressed and released within this frame
+                (false, _) if released => RELEASE_SIGNAL,
+                (false, 0 | RELEASE_SIGNAL) => 0,
+                (false, _) => RELEASE_SIGNAL,
+            };
+        }
+        for axis in self.axes.values_mut() {
+            axis.update(snapshot);
+        }
+        for axis in self.axes_2d.values_mut() {
+            axis.x.update(snapshot);
+            axis.y.update(snapshot);
+        }
+    }
+
+    #[inline(always)]
+    pub fn frames(&self, action: StringHash) -> u16 {
+        self.actions
+            .get(&action)
+            .map(|action| action.frames)
+            .unwrap_or(0)
+    }
+
+    #[inline(always)]
+    pub fn down(&self, action: StringHash) -> bool {
+        let frames = self.frames(action);
+        frames != 0 && frames != RELEASE_SIGNAL
+    }
+
+    #[inline(always)]
+    pub fn pressed(&self, action: StringHash) -> bool {
+        self.frames(action) == 1
+    }
+
+    #[inline(always)]
+    pub fn released(&self, action: StringHash) -> bool {
+        self.frames(action) == RELEASE_SIGNAL
+    }
+
+    #[inline(always)]
+    pub fn held(&self, action: StringHash) -> bool {
+        let frames = self.frames(action);
+        frames > 1 && frames != RELEASE_SIGNAL
+    }
+
+    /// The value of a one-dimensional axis, in the range `-1.0..=1.0`.
+    pub fn axis(&self, axis: StringHash) -> f32 {
+        self.axes.get(&axis).map(|axis| axis.value).unwrap_or(0.0)
+    }
+
+    /// The value of a two-dimensional axis, with a length of at most `1.0`,
+    /// so that diagonal movement is not faster.
+    pub fn axis_2d(&self, axis: StringHash) -> (f32, f32) {
+        let Some(axis) = self.axes_2d.get(&axis) else {
+            return (0.0, 0.0);
+        };
+        let (x, y) = (axis.x.value, axis.y.value);
+        let length = (x * x + y * y).sqrt();
+        if length > 1.0 {
+            (x / length, y / length)
+        } else {
+            (x, y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{DeltaPacket, KeyboardKeyCode};
+
+    fn press(snapshot: &mut InputSnapshot, code: KeyCode, down: bool) {
+        let code: KeyboardKeyCode = code.into();
+        snapshot
+            .keys_mut()
+            .press_change(DeltaPacket::Keyboard { code, down });
+    }
+
+    #[test]
+    fn actions_and_axes() {
+        const JUMP: StringHash = hash_string("jump");
+        const UNDO: StringHash = hash_string("undo");
+        const MOVE: StringHash = hash_string("move");
+
+        let mut map = ActionMap::new();
+        map.bind("jump", KeyCode::Space);
+        map.bind("jump", MouseButton::Right);
+        map.bind(
+            "undo",
+            Binding::key(KeyCode::KeyZ).with_modifiers(Modifiers::CONTROL),
+        );
+        map.bind_axis_2d(
+            "move",
+            AxisBinding::digital(KeyCode::KeyA, KeyCode::KeyD),
+            AxisBinding::digital(KeyCode::KeyS, KeyCode::KeyW),
+        );
+
+        let mut snapshot = InputSnapshot::default();
+        press(&mut snapshot, KeyCode::Space, true);
+        press(&mut snapshot, KeyCode::KeyW, true);
+        press(&mut snapshot, KeyCode::KeyD, true);
+        press(&mut snapshot, KeyCode::KeyZ, true);
+        map.update(&snapshot);
+
+        assert!(map.pressed(JUMP));
+        assert!(!map.down(UNDO));
+        let (x, y) = map.axis_2d(MOVE);
+        assert!((x - y).abs() < 1e-6 && (x * x + y * y - 1.0).abs() < 1e-6);
+
+        snapshot.keys_mut().update();
+        snapshot.keys_mut().press_change(DeltaPacket::Modifiers {
+            state: Modifiers::CONTROL,
+        });
+        press(&mut snapshot, KeyCode::KeyW, false);
+        map.update(&snapshot);
+
+        assert!(map.held(JUMP));
+        assert_eq!(map.frames(JUMP), 2);
+        assert!(map.pressed(UNDO));
+        assert_eq!(map.axis_2d(MOVE), (1.0, 0.0));
+
+        snapshot.keys_mut().update();
+        press(&mut snapshot, KeyCode::Space, false);
+        map.update(&snapshot);
+
+        assert!(map.released(JUMP));
+        assert!(map.held(UNDO));
+
+        let rebound = ActionMap::from_bindings(&map.to_bindings());
+        assert_eq!(rebound.to_bindings(), map.to_bindings());
+        assert_eq!(rebound.bindings(JUMP).len(), 2);
+    }
+
+    #[test]
+    fn tapped_within_a_frame() {
+        const JUMP: StringHash = hash_string("jump");
+        const UNDO: StringHash = hash_string("undo");
+
+        let mut map = ActionMap::new();
+        map.bind("jump", KeyCode::Space);
+        map.bind(
+            "undo",
+            Binding::key(KeyCode::KeyZ).with_modifiers(Modifiers::CONTROL),
+        );
+
+        let mut snapshot = InputSnapshot::default();
+        press(&mut snapshot, KeyCode::Space, true);
+        press(&mut snapshot, KeyCode::Space, false);
+        press(&mut snapshot, KeyCode::KeyZ, true);
+        press(&mut snapshot, KeyCode::KeyZ, false);
+        map.update(&snapshot);
+
+        assert!(map.released(JUMP));
+        assert_eq!(map.frames(UNDO), 0);
+
+        snapshot.keys_mut().update();
+        map.update(&snapshot);
+        assert_eq!(map.frames(JUMP), 0);
+
+        // other modifiers may be held along with those of the chord
+        snapshot.keys_mut().press_change(DeltaPacket::Modifiers {
+            state: Modifiers::CONTROL | Modifiers::SHIFT,
+        });
+        press(&mut snapshot, KeyCode::KeyZ, true);
+        map.update(&snapshot);
+        assert!(map.pressed(UNDO));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bindings_serde_round_trip() {
+        let mut map = ActionMap::new();
+        map.bind("jump", KeyCode::Space);
+        map.bind("jump", GamepadButton::South);
+        map.bind(
+            "undo",
+            Binding::logical('z').with_modifiers(Modifiers::CONTROL),
+        );
+        map.bind("fire", MouseButton::Left);
+        map.bind_axis(
+            "zoom",
+            AxisBinding::Gamepad {
+                axis: GamepadAxis::RightStickY,
+                inverted: true,
+            },
+        );
+        map.bind_axis_2d(
+            "move",
+            AxisBinding::digital(KeyCode::KeyA, KeyCode::KeyD),
+            AxisBinding::digital(KeyCode::KeyS, KeyCode::KeyW),
+        );
+
+        let bindings = map.to_bindings();
+        let json = serde_json::to_string(&bindings).unwrap();
+        let parsed: ActionBindings = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, bindings);
+
+        // missing fields fall back to their defaults
+        let parsed: ActionBindings =
+            serde_json::from_str(r#"{"actions":{"jump":[{"trigger":{"Logical":" "}}]}}"#).unwrap();
+        assert_eq!(parsed.actions["jump"], [Binding::logical(' ')]);
+        assert!(parsed.axes.is_empty() && parsed.axes_2d.is_empty());
+    }
+}
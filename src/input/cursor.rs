@@ -0,0 +1,239 @@
+#[cfg(feature = "textures")]
+use std::path::Path;
+use std::sync::Arc;
+
+pub use winit::window::CursorIcon;
+use winit::window::{BadImage, CustomCursor, CustomCursorSource};
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug)]
+pub enum CursorImageError {
+    #[cfg(feature = "textures")]
+    #[error("failed to load image: {0}")]
+    ImageLoadError(image::ImageError),
+
+    #[error("invalid cursor image: {0}")]
+    BadImage(#[from] BadImage),
+}
+
+/// How the cursor is grabbed by the window, see
+/// [`CursorOptions::set_grab_mode`](crate::input::CursorOptions::set_grab_mode).
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum GrabMode {
+    /// The cursor moves freely, in and out of the window.
+    #[default]
+    Free = 0,
+    /// The cursor can move, but cannot leave the window.
+    Confined = 1,
+    /// The cursor cannot move, falling back to [`GrabMode::Confined`] where
+    /// locking is not supported.
+    Locked = 2,
+    /// The same as [`GrabMode::Locked`], but falling back to
+    /// [`GrabMode::Confined`] with the cursor moved back to the center of the
+    /// window every frame.
+    ///
+    /// When applied, this mode is only reported if the fallback is in use.
+    LockedRecentre = 3,
+}
+
+impl GrabMode {
+    /// The mode in the lowest 2 `bits`.
+    pub(crate) const fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0 => Self::Free,
+            1 => Self::Confined,
+            2 => Self::Locked,
+            _ => Self::LockedRecentre,
+        }
+    }
+}
+
+/// The appearance of the cursor while it is over the window, see
+/// [`CursorOptions::set_appearance`](crate::input::CursorOptions::set_appearance).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CursorAppearance {
+    Icon(CursorIcon),
+    Custom(CustomCursorImage),
+}
+
+impl Default for CursorAppearance {
+    fn default() -> Self {
+        Self::Icon(CursorIcon::Default)
+    }
+}
+
+impl From<CursorIcon> for CursorAppearance {
+    fn from(value: CursorIcon) -> Self {
+        Self::Icon(value)
+    }
+}
+
+impl From<CustomCursorImage> for CursorAppearance {
+    fn from(value: CustomCursorImage) -> Self {
+        Self::Custom(value)
+    }
+}
+
+/// A validated cursor image, which may be created on any thread and is
+/// turned into a cursor by the window thread.
+///
+/// Cloning is cheap, the pixels are shared.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CustomCursorImage {
+    rgba: Arc<[u8]>,
+    width: u16,
+    height: u16,
+    hotspot: (u16, u16),
+}
+
+impl CustomCursorImage {
+    /// Create a cursor from `rgba` pixels, in rows from the top, with the
+    /// click point at `hotspot` pixels from the top left corner.
+    ///
+    /// # Returns
+    /// An error if the size of `rgba` does not match, if the image is too
+    /// large or if the hotspot is outside of it.
+    pub fn from_rgba(
+        rgba: impl Into<Vec<u8>>,
+        width: u16,
+        height: u16,
+        hotspot: (u16, u16),
+    ) -> Result<Self, CursorImageError> {
+        let rgba: Arc<[u8]> = rgba.into().into();
+        let image = Self {
+            rgba,
+            width,
+            height,
+            hotspot,
+        };
+        image.source()?;
+        Ok(image)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn hotspot(&self) -> (u16, u16) {
+        self.hotspot
+    }
+
+    /// Same as [`CustomCursorImage::from_rgba`], converting the `image` to
+    /// 8 bit RGBA first.
+    #[cfg(feature = "textures")]
+    pub fn from_image(
+        image: &image::DynamicImage,
+        hotspot: (u16, u16),
+    ) -> Result<Self, CursorImageError> {
+        let rgba = image.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let too_large = || BadImage::TooLarge {
+            width: width.min(u16::MAX as u32) as u16,
+            height: height.min(u16::MAX as u32) as u16,
+        };
+        let width = u16::try_from(width).map_err(|_| too_large())?;
+        let height = u16::try_from(height).map_err(|_| too_large())?;
+        Self::from_rgba(rgba.into_raw(), width, height, hotspot)
+    }
+
+    /// Same as [`CustomCursorImage::from_image`], decoding the image at
+    /// `path` as textures are.
+    #[cfg(feature = "textures")]
+    pub fn from_image_file(
+        path: impl AsRef<Path>,
+        hotspot: (u16, u16),
+    ) -> Result<Self, CursorImageError> {
+        let image = crate::texture::load_image(path).map_err(CursorImageError::ImageLoadError)?;
+        Self::from_image(&image, hotspot)
+    }
+
+    pub(crate) fn source(&self) -> Result<CustomCursorSource, BadImage> {
+        let (x, y) = self.hotspot;
+        CustomCursor::from_rgba(self.rgba.to_vec(), self.width, self.height, x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::CursorOptions;
+
+    #[test]
+    fn custom_cursor_validation() {
+        let image = CustomCursorImage::from_rgba(vec![255; 4 * 4 * 4], 4, 4, (1, 2)).unwrap();
+        assert_eq!((image.width(), image.height()), (4, 4));
+        assert_eq!(image.hotspot(), (1, 2));
+        assert!(image.source().is_ok());
+
+        assert!(matches!(
+            CustomCursorImage::from_rgba(vec![255; 15], 2, 2, (0, 0)),
+            Err(CursorImageError::BadImage(_))
+        ));
+        assert!(matches!(
+            CustomCursorImage::from_rgba(vec![255; 16], 2, 2, (2, 0)),
+            Err(CursorImageError::BadImage(_))
+        ));
+    }
+
+    #[test]
+    fn appearance_and_visibility() {
+        let options = CursorOptions::default();
+        assert!(options.visible());
+        assert_eq!(options.appearance(), CursorAppearance::default());
+
+        options.set_appearance(CursorIcon::Default);
+        assert!(!options.take_appearance_change());
+
+        let image = CustomCursorImage::from_rgba(vec![0; 4], 1, 1, (0, 0)).unwrap();
+        options.set_appearance(image.clone());
+        assert!(options.take_appearance_change());
+        assert_eq!(options.appearance(), CursorAppearance::Custom(image));
+
+        options.set_grabbed(true);
+        assert!(options.visible());
+        options.set_visible(false);
+        assert!(!options.visible());
+        assert!(!options.take_appearance_change());
+        assert_eq!(options.take_visibility_change(), Some(false));
+        assert_eq!(options.take_visibility_change(), None);
+    }
+
+    #[test]
+    fn grab_mode_requests_and_reports() {
+        let options = CursorOptions::default();
+        assert_eq!(options.grab_mode(), GrabMode::Free);
+        assert!(!options.check_dirty());
+
+        options.set_grab_mode(GrabMode::LockedRecentre);
+        options.set_visible(false);
+        assert!(options.check_grabbed());
+        assert!(options.check_dirty());
+        assert_eq!(options.take_grab_change(), Some(GrabMode::LockedRecentre));
+        assert_eq!(options.take_grab_change(), None);
+        assert!(!options.visible());
+
+        options.report_grab(GrabMode::LockedRecentre, false);
+        assert_eq!(options.applied_grab_mode(), GrabMode::LockedRecentre);
+        assert_eq!(options.grab_mode(), GrabMode::LockedRecentre);
+
+        // an unchanged request is not applied again
+        options.set_grabbed(true);
+        options.set_grabbed(true);
+        assert_eq!(options.take_grab_change(), Some(GrabMode::Locked));
+        options.set_grab_mode(GrabMode::Locked);
+        assert!(!options.check_dirty());
+
+        options.report_grab(GrabMode::LockedRecentre, true);
+        assert!(options.grab_failed());
+        options.report_grab(GrabMode::Locked, false);
+        assert!(!options.grab_failed());
+        assert_eq!(options.applied_grab_mode(), GrabMode::Locked);
+        assert!(!options.visible());
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+
+use crate::input::stream::SideQueue;
+
+/// A file dragged over or dropped onto the window, along with the cursor
+/// position at the time in physical pixels.
+///
+/// When several files are dragged at once, an event is sent for each of them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileEvent {
+    /// A file is being dragged over the window.
+    Hovered { path: PathBuf, position: (f64, f64) },
+    /// A file has been dropped onto the window.
+    Dropped { path: PathBuf, position: (f64, f64) },
+    /// The files dragged over the window have left it without being dropped.
+    HoverCancelled,
+}
+
+impl FileEvent {
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            FileEvent::Hovered { path, .. } | FileEvent::Dropped { path, .. } => Some(path),
+            FileEvent::HoverCancelled => None,
+        }
+    }
+
+    pub fn position(&self) -> Option<(f64, f64)> {
+        match self {
+            FileEvent::Hovered { position, .. } | FileEvent::Dropped { position, .. } => {
+                Some(*position)
+            }
+            FileEvent::HoverCancelled => None,
+        }
+    }
+
+    /// The path of the file, if it has been dropped.
+    pub fn dropped(&self) -> Option<&Path> {
+        match self {
+            FileEvent::Dropped { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Whether the file is an image which can be decoded, judging by its
+    /// extension.
+    #[cfg(feature = "textures")]
+    pub fn is_image(&self) -> bool {
+        self.path().is_some_and(|path| {
+            image::ImageFormat::from_path(path).is_ok_and(|format| format.reading_enabled())
+        })
+    }
+
+    /// Decode the dropped image into a texture, through
+    /// [`Texture::from_2d_image_file`](crate::texture::Texture::from_2d_image_file).
+    ///
+    /// This must be called from the render thread, e.g. from
+    /// [`Draw::file_event`](crate::context::Draw::file_event).
+    ///
+    /// # Returns
+    /// [`None`] if no file has been dropped or if it is not an
+    /// [`image`](FileEvent::is_image).
+    #[cfg(feature = "textures")]
+    pub fn load_texture(
+        &self,
+        mip_levels: crate::texture::MipLevels,
+    ) -> Option<Result<crate::texture::Texture, crate::texture::TextureError>> {
+        let path = self.dropped().filter(|_| self.is_image())?;
+        Some(crate::texture::Texture::from_2d_image_file(
+            path, mip_levels,
+        ))
+    }
+}
+
+/// Side channel for [`FileEvent`]s, placed in order by their
+/// [`DeltaPacket::File`](crate::input::DeltaPacket) markers.
+pub(crate) type FileQueue = SideQueue<FileEvent>;
+
+#[cfg(test)]
+mod tests {
+    use winit::event::WindowEvent;
+
+    use super::*;
+    use crate::input::{InputEvent, InputHarness, MouseButton};
+
+    #[test]
+    fn file_events_in_order() {
+        let mut input = InputHarness::new();
+        let dispatcher = input.dispatcher();
+
+        dispatcher.move_cursor((20.0, 30.0));
+        dispatcher.handle_file_events(&WindowEvent::HoveredFile("level.png".into()));
+        dispatcher.handle_file_events(&WindowEvent::HoveredFileCancelled);
+        dispatcher.click(MouseButton::Left);
+        dispatcher.move_cursor((25.0, 30.0));
+        let sent = dispatcher.handle_file_events(&WindowEvent::DroppedFile("level.png".into()));
+        assert_eq!(
+            sent.as_ref().and_then(FileEvent::position),
+            Some((25.0, 30.0))
+        );
+        assert!(
+            dispatcher
+                .handle_file_events(&WindowEvent::Focused(true))
+                .is_none()
+        );
+
+        let state = input.step();
+        let events: Vec<_> = state.events().iter().map(|timed| &timed.event).collect();
+        assert_eq!(events.len(), 5);
+        assert_eq!(
+            events[0],
+            &InputEvent::File(FileEvent::Hovered {
+                path: "level.png".into(),
+                position: (20.0, 30.0),
+            })
+        );
+        assert_eq!(events[1], &InputEvent::File(FileEvent::HoverCancelled));
+        assert!(matches!(events[2], InputEvent::MouseButton { .. }));
+        assert!(matches!(events[3], InputEvent::MouseButton { .. }));
+        assert_eq!(events[4], &InputEvent::File(sent.unwrap()));
+
+        let dropped: Vec<_> = state.dropped_files().collect();
+        assert_eq!(dropped, [(Path::new("level.png"), (25.0, 30.0))]);
+        assert_eq!(state.file_events().count(), 3);
+
+        assert_eq!(input.step().file_events().count(), 0);
+    }
+
+    #[cfg(feature = "textures")]
+    #[test]
+    fn dropped_images() {
+        let dropped = |path: &str| FileEvent::Dropped {
+            path: path.into(),
+            position: (0.0, 0.0),
+        };
+        assert!(dropped("level.png").is_image());
+        assert!(dropped("textures/grass.JPG").is_image());
+        assert!(!dropped("level.ron").is_image());
+        assert!(!FileEvent::HoverCancelled.is_image());
+
+        // nothing to decode, so no GL context is needed either
+        let mip_levels = crate::texture::MipLevels::default();
+        assert!(dropped("level.ron").load_texture(mip_levels).is_none());
+        assert!(
+            FileEvent::Hovered {
+                path: "level.png".into(),
+                position: (0.0, 0.0),
+            }
+            .load_texture(mip_levels)
+            .is_none()
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use crate::sync::TriCell;
+
+/// The maximum amount of gamepads tracked at once.
+pub const MAX_GAMEPADS: usize = 4;
+
+pub(crate) const GAMEPAD_BUTTONS: usize = GamepadButton::ALL.len();
+pub(crate) const GAMEPAD_AXES: usize = GamepadAxis::ALL.len();
+
+/// The value of every [`GamepadAxis`] of a gamepad, indexed by axis.
+pub type GamepadAxesValues = [f32; GAMEPAD_AXES];
+
+/// The slot of a connected gamepad, in `0..MAX_GAMEPADS`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GamepadId(pub(crate) u8);
+
+impl GamepadId {
+    /// # Returns
+    /// [`None`] if `index` is not below [`MAX_GAMEPADS`].
+    pub const fn new(index: usize) -> Option<Self> {
+        if index < MAX_GAMEPADS {
+            Some(Self(index as u8))
+        } else {
+            None
+        }
+    }
+
+    #[inline(always)]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A gamepad button, named after its position on the gamepad rather than
+/// its label, which differs between vendors.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    /// The digital state of the left trigger, see [`GamepadAxis::LeftTrigger`]
+    /// for its analog value.
+    LeftTrigger,
+    /// The digital state of the right trigger, see
+    /// [`GamepadAxis::RightTrigger`] for its analog value.
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    pub const ALL: [Self; 17] = [
+        Self::South,
+        Self::East,
+        Self::West,
+        Self::North,
+        Self::LeftBumper,
+        Self::RightBumper,
+        Self::LeftTrigger,
+        Self::RightTrigger,
+        Self::Select,
+        Self::Start,
+        Self::Mode,
+        Self::LeftStick,
+        Self::RightStick,
+        Self::DPadUp,
+        Self::DPadDown,
+        Self::DPadLeft,
+        Self::DPadRight,
+    ];
+
+    #[inline(always)]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub const fn from_index(index: usize) -> Option<Self> {
+        if index < Self::ALL.len() {
+            Some(Self::ALL[index])
+        } else {
+            None
+        }
+    }
+}
+
+/// A gamepad analog axis.
+///
+/// Sticks are in the range `-1.0..=1.0`, with up and right being positive,
+/// while triggers are in the range `0.0..=1.0`.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    pub const ALL: [Self; 6] = [
+        Self::LeftStickX,
+        Self::LeftStickY,
+        Self::RightStickX,
+        Self::RightStickY,
+        Self::LeftTrigger,
+        Self::RightTrigger,
+    ];
+
+    #[inline(always)]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub const fn is_trigger(self) -> bool {
+        matches!(self, Self::LeftTrigger | Self::RightTrigger)
+    }
+}
+
+/// A raw gamepad event, as produced by a [`GamepadBackend`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    Button {
+        pad: GamepadId,
+        button: GamepadButton,
+        down: bool,
+    },
+    Axis {
+        pad: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// A source of gamepad events, polled by the
+/// [`InputDispatcher`](crate::input::InputDispatcher) on the window thread
+/// once per event loop iteration.
+///
+/// See [`InputDispatcher::set_gamepad_backend`].
+///
+/// [`InputDispatcher::set_gamepad_backend`]: crate::input::InputDispatcher::set_gamepad_backend
+pub trait GamepadBackend: Send + Debug {
+    /// Pop the next pending event, if any.
+    fn poll(&mut self) -> Option<GamepadEvent>;
+}
+
+/// A [`GamepadBackend`] driven by hand, e.g. for tests or for input
+/// synthesised by the application.
+///
+/// Clones share the same events, so that a clone may be given to the
+/// dispatcher while another is kept to drive it.
+#[derive(Clone, Debug, Default)]
+pub struct VirtualGamepads {
+    events: Arc<Mutex<VecDeque<GamepadEvent>>>,
+}
+
+impl VirtualGamepads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, event: GamepadEvent) {
+        self.events
+            .lock()
+            .expect("virtual gamepad events poisoned")
+            .push_back(event);
+    }
+
+    pub fn connect(&self, pad: GamepadId) {
+        self.push(GamepadEvent::Connected(pad));
+    }
+
+    pub fn disconnect(&self, pad: GamepadId) {
+        self.push(GamepadEvent::Disconnected(pad));
+    }
+
+    pub fn press(&self, pad: GamepadId, button: GamepadButton) {
+        self.push(GamepadEvent::Button {
+            pad,
+            button,
+            down: true,
+        });
+    }
+
+    pub fn release(&self, pad: GamepadId, button: GamepadButton) {
+        self.push(GamepadEvent::Button {
+            pad,
+            button,
+            down: false,
+        });
+    }
+
+    pub fn set_axis(&self, pad: GamepadId, axis: GamepadAxis, value: f32) {
+        self.push(GamepadEvent::Axis { pad, axis, value });
+    }
+}
+
+impl GamepadBackend for VirtualGamepads {
+    fn poll(&mut self) -> Option<GamepadEvent> {
+        self.events
+            .lock()
+            .expect("virtual gamepad events poisoned")
+            .pop_front()
+    }
+}
+
+/// The raw axes of every gamepad, synchronised once per frame.
+#[derive(Debug, Default)]
+pub struct GamepadAxes {
+    pub(crate) pads: [TriCell<GamepadAxesValues>; MAX_GAMEPADS],
+}
+
+/// The deadzones applied to the gamepad axes by [`Gamepads`].
+///
+/// Stick deadzones are radial, trigger deadzones are axial; in both cases the
+/// value past the deadzone is rescaled to start from `0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Deadzones {
+    pub stick: f32,
+    pub trigger: f32,
+}
+
+impl Default for Deadzones {
+    fn default() -> Self {
+        Self {
+            stick: 0.15,
+            trigger: 0.05,
+        }
+    }
+}
+
+/// Read-only view of the gamepads, as received from the window thread.
+///
+/// Buttons are tracked by [`Keys`](crate::input::Keys) along with the
+/// keyboard and mouse.
+#[derive(Debug, Default)]
+pub struct Gamepads {
+    axes: Arc<GamepadAxes>,
+    connected: [bool; MAX_GAMEPADS],
+    deadzones: Deadzones,
+}
+
+impl Gamepads {
+    pub(crate) fn axes_shared(&self) -> &Arc<GamepadAxes> {
+        &self.axes
+    }
+
+    pub(crate) fn set_connected(&mut self, pad: GamepadId, connected: bool) {
+        self.connected[pad.index()] = connected;
+    }
+
+    pub fn is_connected(&self, pad: GamepadId) -> bool {
+        self.connected[pad.index()]
+    }
+
+    pub fn connected(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        (0..MAX_GAMEPADS as u8)
+            .map(GamepadId)
+            .filter(|&pad| self.is_connected(pad))
+    }
+
+    pub fn deadzones(&self) -> Deadzones {
+        self.deadzones
+    }
+
+    pub fn set_deadzones(&mut self, deadzones: Deadzones) {
+        self.deadzones = deadzones;
+    }
+
+    /// The value of `axis` as received from the backend, without deadzones.
+    pub fn raw_axis(&self, pad: GamepadId, axis: GamepadAxis) -> f32 {
+        self.axes.pads[pad.index()].get()[axis.index()]
+    }
+
+    /// The value of `axis` with deadzones applied.
+    ///
+    /// For a stick axis, the deadzone is applied to the whole stick.
+    pub fn axis(&self, pad: GamepadId, axis: GamepadAxis) -> f32 {
+        match axis {
+            GamepadAxis::LeftStickX => self.left_stick(pad).0,
+            GamepadAxis::LeftStickY => self.left_stick(pad).1,
+            GamepadAxis::RightStickX => self.right_stick(pad).0,
+            GamepadAxis::RightStickY => self.right_stick(pad).1,
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => {
+                let value = self.raw_axis(pad, axis);
+                let magnitude = rescale(value.abs(), self.deadzones.trigger);
+                magnitude.copysign(value)
+            }
+        }
+    }
+
+    pub fn left_stick(&self, pad: GamepadId) -> (f32, f32) {
+        self.stick(pad, GamepadAxis::LeftStickX, GamepadAxis::LeftStickY)
+    }
+
+    pub fn right_stick(&self, pad: GamepadId) -> (f32, f32) {
+        self.stick(pad, GamepadAxis::RightStickX, GamepadAxis::RightStickY)
+    }
+
+    fn stick(&self, pad: GamepadId, x: GamepadAxis, y: GamepadAxis) -> (f32, f32) {
+        let values = self.axes.pads[pad.index()].get();
+        let (x, y) = (values[x.index()], values[y.index()]);
+        let length = (x * x + y * y).sqrt();
+        if length == 0.0 {
+            return (0.0, 0.0);
+        }
+        let scale = rescale(length, self.deadzones.stick) / length;
+        (x * scale, y * scale)
+    }
+}
+
+/// Rescale `magnitude` past `deadzone` to the range `0.0..=1.0`.
+#[inline(always)]
+fn rescale(magnitude: f32, deadzone: f32) -> f32 {
+    if magnitude <= deadzone {
+        0.0
+    } else {
+        ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{InputEvent, InputHarness};
+
+    #[test]
+    fn virtual_gamepad() {
+        let mut input = InputHarness::new();
+        let gamepads = VirtualGamepads::new();
+        input.dispatcher().set_gamepad_backend(gamepads.clone());
+
+        let pad = GamepadId::new(1).unwrap();
+        gamepads.connect(pad);
+        gamepads.press(pad, GamepadButton::South);
+        gamepads.set_axis(pad, GamepadAxis::LeftStickX, 0.1);
+        gamepads.set_axis(pad, GamepadAxis::RightStickY, 1.0);
+        gamepads.set_axis(pad, GamepadAxis::RightTrigger, 0.5);
+
+        input.dispatcher().poll_gamepads();
+        let state = input.step();
+        assert_eq!(state.events()[0].event, InputEvent::GamepadConnected(pad));
+        assert!(state.gamepads().is_connected(pad));
+        assert!(state.keys().gamepad_pressed(pad, GamepadButton::South));
+
+        let gamepads_state = state.gamepads();
+        assert_eq!(gamepads_state.raw_axis(pad, GamepadAxis::LeftStickX), 0.1);
+        assert_eq!(gamepads_state.left_stick(pad), (0.0, 0.0));
+        assert_eq!(gamepads_state.right_stick(pad), (0.0, 1.0));
+        let trigger = gamepads_state.axis(pad, GamepadAxis::RightTrigger);
+        assert!((trigger - 0.45 / 0.95).abs() < 1e-6);
+
+        gamepads.disconnect(pad);
+
+        input.dispatcher().poll_gamepads();
+        let state = input.step();
+        assert_eq!(
+            state.events()[0].event,
+            InputEvent::GamepadDisconnected(pad)
+        );
+        assert!(!state.gamepads().is_connected(pad));
+        assert!(state.keys().gamepad_released(pad, GamepadButton::South));
+        assert_eq!(state.gamepads().right_stick(pad), (0.0, 0.0));
+    }
+}
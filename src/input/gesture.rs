@@ -0,0 +1,369 @@
+use std::time::{Duration, Instant};
+
+use crate::input::{
+    InputEvent, InputSnapshot, KeyEvent, MOUSE_ENTRIES, MouseButton, MouseButtonIndex,
+};
+
+/// The thresholds used by a [`GestureRecognizer`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GestureConfig {
+    /// The longest time between two presses of a multi-click.
+    pub multi_click_interval: Duration,
+    /// The furthest, in physical pixels, a press of a multi-click may be from
+    /// the previous one.
+    pub multi_click_radius: f64,
+    /// The distance, in physical pixels, the cursor has to move away from
+    /// where a button has been pressed for a drag to start.
+    pub drag_threshold: f64,
+    /// The frames a button has to be held without dragging to be
+    /// long-pressed.
+    pub long_press_frames: u16,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            multi_click_interval: Duration::from_millis(500),
+            multi_click_radius: 4.0,
+            drag_threshold: 4.0,
+            long_press_frames: 30,
+        }
+    }
+}
+
+/// A mouse gesture, recognised from the mouse button events and the cursor
+/// position of a frame.
+///
+/// Positions are in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    /// A button has been released without being dragged or long-pressed.
+    ///
+    /// `count` is 2 for a double-click, 3 for a triple-click and so on.
+    Click {
+        button: MouseButton,
+        position: (f64, f64),
+        count: u32,
+    },
+    /// The cursor has moved past the drag threshold while the button is held.
+    DragStart {
+        button: MouseButton,
+        start: (f64, f64),
+        position: (f64, f64),
+    },
+    /// The cursor has moved during a drag, by `delta` since the last frame.
+    Drag {
+        button: MouseButton,
+        start: (f64, f64),
+        position: (f64, f64),
+        delta: (f64, f64),
+    },
+    /// The button of a drag has been released.
+    DragEnd {
+        button: MouseButton,
+        start: (f64, f64),
+        position: (f64, f64),
+    },
+    /// The button has been held for long enough without being dragged.
+    LongPress {
+        button: MouseButton,
+        position: (f64, f64),
+    },
+}
+
+/// The gesture tracking of a single button.
+#[derive(Clone, Copy, Debug, Default)]
+struct ButtonGesture {
+    /// Where the button has been pressed, while it is held.
+    start: Option<(f64, f64)>,
+    /// The cursor position of the last frame, while dragging.
+    dragging: Option<(f64, f64)>,
+    long_pressed: bool,
+    count: u32,
+    last_press: Option<(Instant, (f64, f64))>,
+}
+
+/// Recognises the [`Gesture`]s of every mouse button, frame by frame.
+///
+/// See [`InputState::gestures`](crate::input::InputState::gestures).
+#[derive(Debug)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    buttons: [ButtonGesture; MOUSE_ENTRIES],
+    gestures: Vec<Gesture>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new(GestureConfig::default())
+    }
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            buttons: [ButtonGesture::default(); MOUSE_ENTRIES],
+            gestures: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: GestureConfig) {
+        self.config = config;
+    }
+
+    /// The gestures recognised in the last [`update`](GestureRecognizer::update).
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    /// Whether `button` is being dragged.
+    pub fn is_dragging(&self, button: MouseButton) -> bool {
+        self.button(button).dragging.is_some()
+    }
+
+    /// Where `button` has been pressed, while it is held.
+    pub fn press_position(&self, button: MouseButton) -> Option<(f64, f64)> {
+        self.button(button).start
+    }
+
+    fn button(&self, button: MouseButton) -> &ButtonGesture {
+        let index: MouseButtonIndex = button.into();
+        &self.buttons[u16::from(index) as usize]
+    }
+
+    /// Recognise the gestures of the frame from its mouse button events, in
+    /// order, and then from the cursor position and held frames of the
+    /// buttons still held.
+    pub fn update(&mut self, input: &InputSnapshot) {
+        self.gestures.clear();
+
+        for timed in input.events() {
+            if let InputEvent::MouseButton {
+                event: KeyEvent::Mouse { code, release, .. },
+                position,
+            } = timed.event
+            {
+                self.button_change(code, release, position, timed.timestamp);
+            }
+        }
+
+        let cursor = input.cursor().current();
+        for (i, state) in self.buttons.iter_mut().enumerate() {
+            let Some(start) = state.start else {
+                continue;
+            };
+            let button = MouseButton::from(MouseButtonIndex(i as u16));
+
+            match state.dragging {
+                Some(last) if last != cursor => {
+                    state.dragging = Some(cursor);
+                    self.gestures.push(Gesture::Drag {
+                        button,
+                        start,
+                        position: cursor,
+                        delta: (cursor.0 - last.0, cursor.1 - last.1),
+                    });
+                }
+                Some(_) => {}
+                None if distance(start, cursor) >= self.config.drag_threshold => {
+                    state.dragging = Some(cursor);
+                    self.gestures.push(Gesture::DragStart {
+                        button,
+                        start,
+                        position: cursor,
+                    });
+                }
+                None if !state.long_pressed
+                    && input.keys().mouse_frames_held(button) >= self.config.long_press_frames =>
+                {
+                    state.long_pressed = true;
+                    self.gestures.push(Gesture::LongPress {
+                        button,
+                        position: start,
+                    });
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn button_change(&mut self, code: u16, release: bool, position: (f64, f64), time: Instant) {
+        let button = MouseButton::from(MouseButtonIndex(code));
+        let config = self.config;
+        let state = &mut self.buttons[code as usize];
+
+        if !release {
+            state.count = match state.last_press {
+                Some((last_time, last_position))
+                    if time.saturating_duration_since(last_time) <= config.multi_click_interval
+                        && distance(last_position, position) <= config.multi_click_radius =>
+                {
+                    state.count + 1
+                }
+                _ => 1,
+            };
+            state.last_press = Some((time, position));
+            state.start = Some(position);
+            state.dragging = None;
+            state.long_pressed = false;
+            return;
+        }
+
+        let Some(start) = state.start.take() else {
+            return;
+        };
+        if state.dragging.take().is_some() {
+            self.gestures.push(Gesture::DragEnd {
+                button,
+                start,
+                position,
+            });
+            // a drag is never part of a multi-click
+            state.last_press = None;
+        } else if !state.long_pressed {
+            self.gestures.push(Gesture::Click {
+                button,
+                position,
+                count: state.count,
+            });
+        } else {
+            state.last_press = None;
+        }
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputHarness;
+
+    #[test]
+    fn clicks_drags_and_long_presses() {
+        let mut input = InputHarness::new();
+        input.state_mut().set_gesture_config(GestureConfig {
+            long_press_frames: 3,
+            ..Default::default()
+        });
+
+        input.dispatcher().move_cursor((10.0, 10.0));
+        input.dispatcher().click(MouseButton::Left);
+        assert_eq!(
+            input.step().gestures(),
+            [Gesture::Click {
+                button: MouseButton::Left,
+                position: (10.0, 10.0),
+                count: 1,
+            }]
+        );
+
+        input.dispatcher().move_cursor((11.0, 12.0));
+        input.dispatcher().click(MouseButton::Left);
+        assert_eq!(
+            input.step().gestures(),
+            [Gesture::Click {
+                button: MouseButton::Left,
+                position: (11.0, 12.0),
+                count: 2,
+            }]
+        );
+
+        // too far away from the previous click
+        input.dispatcher().move_cursor((30.0, 12.0));
+        input.dispatcher().click(MouseButton::Left);
+        assert!(matches!(
+            input.step().gestures(),
+            [Gesture::Click { count: 1, .. }]
+        ));
+
+        input
+            .dispatcher()
+            .push_mouse_button(MouseButton::Right, true);
+        input.dispatcher().move_cursor((32.0, 12.0));
+        assert!(input.step().gestures().is_empty());
+
+        input.dispatcher().move_cursor((40.0, 12.0));
+        let state = input.step();
+        assert!(state.is_dragging(MouseButton::Right));
+        assert_eq!(
+            state.gestures(),
+            [Gesture::DragStart {
+                button: MouseButton::Right,
+                start: (30.0, 12.0),
+                position: (40.0, 12.0),
+            }]
+        );
+
+        input.dispatcher().move_cursor((45.0, 10.0));
+        assert_eq!(
+            input.step().gestures(),
+            [Gesture::Drag {
+                button: MouseButton::Right,
+                start: (30.0, 12.0),
+                position: (45.0, 10.0),
+                delta: (5.0, -2.0),
+            }]
+        );
+
+        input
+            .dispatcher()
+            .push_mouse_button(MouseButton::Right, false);
+        let state = input.step();
+        assert!(!state.is_dragging(MouseButton::Right));
+        assert_eq!(
+            state.gestures(),
+            [Gesture::DragEnd {
+                button: MouseButton::Right,
+                start: (30.0, 12.0),
+                position: (45.0, 10.0),
+            }]
+        );
+
+        input
+            .dispatcher()
+            .push_mouse_button(MouseButton::Left, true);
+        input.step_frames(2);
+        assert_eq!(
+            input.step().gestures(),
+            [Gesture::LongPress {
+                button: MouseButton::Left,
+                position: (45.0, 10.0),
+            }]
+        );
+        assert!(input.step().gestures().is_empty());
+
+        // no click once long-pressed
+        input
+            .dispatcher()
+            .push_mouse_button(MouseButton::Left, false);
+        assert!(input.step().gestures().is_empty());
+        // presses past the top left corner of the window keep their position
+        input.dispatcher().move_cursor((-20.0, -5.0));
+        input
+            .dispatcher()
+            .push_mouse_button(MouseButton::Right, true);
+        assert!(matches!(
+            input.step().events()[0].event,
+            InputEvent::MouseButton {
+                position: (-20.0, -5.0),
+                ..
+            }
+        ));
+        input.dispatcher().move_cursor((-40.0, 10.0));
+        assert!(matches!(
+            input.step().gestures(),
+            [Gesture::DragStart {
+                start: (-20.0, -5.0),
+                ..
+            }]
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+//! Drive an [`InputState`] without a window or threads, e.g. to unit test
+//! input-driven logic.
+
+use std::time::Duration;
+
+use crate::{
+    context::{Clock, SystemClock},
+    input::{InputDispatcher, InputState, SECTION_COUNT, SLOT_COUNT, stream},
+};
+
+/// Both ends of an input stream, synchronised in lockstep.
+///
+/// Input is injected through the [`dispatcher`](InputHarness::dispatcher),
+/// e.g. with [`InputDispatcher::press_key`], and becomes visible in the
+/// [`state`](InputHarness::state) on the next [`step`](InputHarness::step),
+/// just as it would at the start of the next logic frame.
+#[derive(Debug)]
+pub struct InputHarness<const SLOTS: usize = SLOT_COUNT, const SECTIONS: usize = SECTION_COUNT> {
+    state: InputState<SLOTS, SECTIONS>,
+    dispatcher: InputDispatcher<SLOTS, SECTIONS>,
+}
+
+impl Default for InputHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputHarness {
+    pub fn new() -> Self {
+        Self::sized()
+    }
+
+    /// Same as [`InputHarness::new`], with the input stamped by `clock`, e.g.
+    /// a [`ManualClock`](crate::context::ManualClock) to control the
+    /// timestamps of the events.
+    pub fn with_clock(clock: impl Clock) -> Self {
+        Self::sized_with_clock(clock)
+    }
+}
+
+impl<const SLOTS: usize, const SECTIONS: usize> InputHarness<SLOTS, SECTIONS> {
+    /// Same as [`InputHarness::new`], with the given stream sizes.
+    pub fn sized() -> Self {
+        Self::sized_with_clock(SystemClock)
+    }
+
+    /// Same as [`InputHarness::with_clock`], with the given stream sizes.
+    pub fn sized_with_clock(clock: impl Clock) -> Self {
+        let (mut state, mut dispatcher) = stream();
+        dispatcher.set_clock(clock);
+
+        // the dispatcher writes ahead of the section being read; leave the
+        // state right behind it, with the next sync already requested.
+        state.sync();
+        dispatcher.sync();
+        state.sync();
+
+        Self { state, dispatcher }
+    }
+
+    /// The window side of the stream, to inject input with.
+    pub fn dispatcher(&mut self) -> &mut InputDispatcher<SLOTS, SECTIONS> {
+        &mut self.dispatcher
+    }
+
+    pub fn state(&self) -> &InputState<SLOTS, SECTIONS> {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut InputState<SLOTS, SECTIONS> {
+        &mut self.state
+    }
+
+    /// Start a new logic frame, receiving all of the input injected since the
+    /// last one.
+    pub fn step(&mut self) -> &mut InputState<SLOTS, SECTIONS> {
+        self.dispatcher.sync();
+        self.state.sync();
+        self.state.poll_key_events();
+        &mut self.state
+    }
+
+    /// Same as [`step`](InputHarness::step), also specifying the delta time
+    /// of the frame.
+    pub fn step_with_delta(&mut self, frame_delta: Duration) -> &mut InputState<SLOTS, SECTIONS> {
+        self.dispatcher.sync();
+        self.state.sync_with_delta(frame_delta);
+        self.state.poll_key_events();
+        &mut self.state
+    }
+
+    /// Step over `frames` frames, e.g. to hold a key down for a while.
+    pub fn step_frames(&mut self, frames: usize) -> &mut InputState<SLOTS, SECTIONS> {
+        for _ in 0..frames {
+            self.step();
+        }
+        &mut self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{InputEvent, KeyCode, KeyEvent, MouseButton};
+
+    #[test]
+    fn lockstep_injection() {
+        let mut input = InputHarness::new();
+
+        input.dispatcher().press_key(KeyCode::KeyW);
+        input.dispatcher().move_cursor((40.0, 30.0));
+        assert!(input.step().keys().key_pressed(KeyCode::KeyW));
+        assert_eq!(input.state().cursor().current(), (40.0, 30.0));
+        assert_eq!(input.state().cursor().delta(), (40.0, 30.0));
+
+        let state = input.step_frames(3);
+        assert_eq!(state.keys().key_frames(KeyCode::KeyW), 4);
+        assert_eq!(state.cursor().delta(), (0.0, 0.0));
+        assert_eq!(state.cursor().current(), (40.0, 30.0));
+
+        input.dispatcher().release_key(KeyCode::KeyW);
+        input.dispatcher().scroll((0.0, -2.0));
+        input.dispatcher().move_cursor((10.0, 20.0));
+        input.dispatcher().click(MouseButton::Left);
+
+        let state = input.step();
+        assert!(state.keys().key_released(KeyCode::KeyW));
+        assert_eq!(state.mouse_wheel(), (0.0, -2.0));
+        assert_eq!(state.cursor().delta(), (-30.0, -10.0));
+
+        let clicks: Vec<_> = state
+            .events()
+            .iter()
+            .filter_map(|timed| match timed.event {
+                InputEvent::MouseButton { event, position } => Some((event, position)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(clicks.len(), 2);
+        assert!(matches!(
+            clicks[0],
+            (KeyEvent::Mouse { release: false, .. }, (10.0, 20.0))
+        ));
+        assert!(matches!(
+            clicks[1],
+            (KeyEvent::Mouse { release: true, .. }, (10.0, 20.0))
+        ));
+
+        assert!(input.step().events().is_empty());
+        assert_eq!(input.state().mouse_wheel(), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,182 @@
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use crate::input::LOGICAL_ENTRIES;
+
+/// The state of the modifier keys, as a bitset.
+///
+/// Left and right modifier keys are not distinguished; use the physical
+/// [`KeyCode`](crate::input::KeyCode)s for that.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const SHIFT: Self = Self(1 << 0);
+    pub const CONTROL: Self = Self(1 << 1);
+    pub const ALT: Self = Self(1 << 2);
+    pub const SUPER: Self = Self(1 << 3);
+
+    #[inline(always)]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    #[inline(always)]
+    pub const fn all() -> Self {
+        Self(Self::SHIFT.0 | Self::CONTROL.0 | Self::ALT.0 | Self::SUPER.0)
+    }
+
+    /// Any bit outside of [`Modifiers::all`] is discarded.
+    #[inline(always)]
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & Self::all().0)
+    }
+
+    #[inline(always)]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    #[inline(always)]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline(always)]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[inline(always)]
+    pub const fn shift(self) -> bool {
+        self.contains(Self::SHIFT)
+    }
+
+    #[inline(always)]
+    pub const fn control(self) -> bool {
+        self.contains(Self::CONTROL)
+    }
+
+    #[inline(always)]
+    pub const fn alt(self) -> bool {
+        self.contains(Self::ALT)
+    }
+
+    #[inline(always)]
+    pub const fn super_key(self) -> bool {
+        self.contains(Self::SUPER)
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    #[inline(always)]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Modifiers {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl From<ModifiersState> for Modifiers {
+    fn from(value: ModifiersState) -> Self {
+        let mut modifiers = Self::empty();
+        if value.shift_key() {
+            modifiers |= Self::SHIFT;
+        }
+        if value.control_key() {
+            modifiers |= Self::CONTROL;
+        }
+        if value.alt_key() {
+            modifiers |= Self::ALT;
+        }
+        if value.super_key() {
+            modifiers |= Self::SUPER;
+        }
+        modifiers
+    }
+}
+
+/// A key as interpreted by the current keyboard layout, e.g. the key labeled
+/// `Z` is `'z'` on both QWERTY and AZERTY layouts, even though its physical
+/// [`KeyCode`](crate::input::KeyCode) is respectively `KeyZ` and `KeyW`.
+///
+/// Characters are case-insensitive and limited to Latin-1; named keys (such as
+/// [`NamedKey::Enter`]) are also supported. Any other key is
+/// [`unidentified`](LogicalKeyCode::UNIDENTIFIED).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogicalKeyCode(pub(crate) u16);
+
+impl LogicalKeyCode {
+    pub const UNIDENTIFIED: Self = Self(0);
+
+    const CHAR_ENTRIES: u16 = 0x100;
+    const NAMED_OFFSET: u16 = Self::CHAR_ENTRIES;
+
+    /// # Returns
+    /// [`None`] if the key cannot be represented.
+    pub fn from_key(key: &Key) -> Option<Self> {
+        let code = match key {
+            Key::Character(text) => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Self::from(c),
+                    _ => return None,
+                }
+            }
+            Key::Named(named) => Self::from(*named),
+            _ => return None,
+        };
+        (code != Self::UNIDENTIFIED).then_some(code)
+    }
+}
+
+impl From<LogicalKeyCode> for u16 {
+    #[inline(always)]
+    fn from(value: LogicalKeyCode) -> Self {
+        value.0
+    }
+}
+
+impl From<char> for LogicalKeyCode {
+    fn from(value: char) -> Self {
+        let mut lower = value.to_lowercase();
+        let c = match (lower.next(), lower.next()) {
+            (Some(c), None) => c,
+            _ => value,
+        };
+        if (c as u32) < Self::CHAR_ENTRIES as u32 {
+            Self(c as u16)
+        } else {
+            Self::UNIDENTIFIED
+        }
+    }
+}
+
+impl From<NamedKey> for LogicalKeyCode {
+    fn from(value: NamedKey) -> Self {
+        let code = value as u32 + Self::NAMED_OFFSET as u32;
+        if code < LOGICAL_ENTRIES as u32 {
+            Self(code as u16)
+        } else {
+            Self::UNIDENTIFIED
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use crate::input::{
+    GAMEPAD_BUTTONS, GamepadButton, GamepadId, InputSnapshot, KEYBOARD_ENTRIES, KeyCode,
+    KeyboardKeyCode, Keys, LOGICAL_ENTRIES, LogicalKeyCode, MAX_GAMEPADS, MOUSE_ENTRIES,
+    MouseButton, MouseButtonIndex, RELEASE_SIGNAL,
+};
+
+// offsets of each kind of input in a mask, in the order of `Keys::frames_mut`
+const LOGICAL_OFFSET: usize = KEYBOARD_ENTRIES;
+const MOUSE_OFFSET: usize = LOGICAL_OFFSET + LOGICAL_ENTRIES;
+const GAMEPAD_OFFSET: usize = MOUSE_OFFSET + MOUSE_ENTRIES;
+const MASK_ENTRIES: usize = GAMEPAD_OFFSET + GAMEPAD_BUTTONS * MAX_GAMEPADS;
+
+/// Identifies a layer of [`InputLayers`] for as long as it is in the stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LayerId(u32);
+
+/// The inputs consumed by a layer, or hidden from it.
+#[derive(Clone, Debug)]
+struct Mask {
+    keys: Vec<bool>,
+    scroll: bool,
+    cursor: bool,
+}
+
+impl Default for Mask {
+    fn default() -> Self {
+        Self {
+            keys: vec![false; MASK_ENTRIES],
+            scroll: false,
+            cursor: false,
+        }
+    }
+}
+
+impl Mask {
+    fn clear(&mut self) {
+        self.keys.fill(false);
+        self.scroll = false;
+        self.cursor = false;
+    }
+
+    fn union(&mut self, other: &Mask) {
+        for (entry, other) in self.keys.iter_mut().zip(&other.keys) {
+            *entry |= *other;
+        }
+        self.scroll |= other.scroll;
+        self.cursor |= other.cursor;
+    }
+}
+
+#[derive(Debug)]
+struct Layer {
+    id: LayerId,
+    priority: i32,
+    /// Consumed by this layer in this frame.
+    consumed: Mask,
+    /// Hidden from this layer as of its last view.
+    hidden: Mask,
+    /// Keys that were hidden in the last frame and have not been pressed
+    /// again since, which stay hidden until released.
+    sticky: Vec<bool>,
+    keys: Keys,
+}
+
+/// A stack of input layers, e.g. a debug UI over the gameplay, where every
+/// layer sees the input of the frame minus what the layers above it have
+/// consumed.
+///
+/// Must be [`updated`](InputLayers::update) once per frame, after the input
+/// has been polled, and then its layers [`viewed`](InputLayers::layer) from
+/// the top down, so that each layer has consumed its input before the layers
+/// below see it.
+///
+/// A layer sees a consumed key or button as released if it has seen it down
+/// before, and as up otherwise. Once hidden, a key or button stays hidden
+/// until it is released, so that a layer never sees it held without having
+/// seen it pressed.
+///
+/// Only the [`Keys`], the cursor and the mouse wheel are layered. The events,
+/// gestures, committed text and dropped files of the
+/// [`InputState`](crate::input::InputState) are the same for every layer, so
+/// a layer below one that has consumed the keyboard should also ignore the
+/// text, and so on.
+///
+/// See [`InputState::layers_mut`](crate::input::InputState::layers_mut).
+#[derive(Debug, Default)]
+pub struct InputLayers {
+    /// Sorted from the top.
+    layers: Vec<Layer>,
+    next_id: u32,
+
+    keys: Keys,
+    cursor: ((f64, f64), (f64, f64)),
+    mouse_wheel: ((f32, f32), (f32, f32)),
+}
+
+impl InputLayers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push a layer above every layer of a lower or equal `priority`.
+    pub fn push(&mut self, priority: i32) -> LayerId {
+        let id = LayerId(self.next_id);
+        self.next_id = self.next_id.wrapping_add(1);
+
+        let index = self
+            .layers
+            .iter()
+            .position(|layer| layer.priority <= priority)
+            .unwrap_or(self.layers.len());
+        self.layers.insert(
+            index,
+            Layer {
+                id,
+                priority,
+                consumed: Mask::default(),
+                hidden: Mask::default(),
+                sticky: vec![false; MASK_ENTRIES],
+                keys: Keys::default(),
+            },
+        );
+        id
+    }
+
+    /// Remove the top layer.
+    pub fn pop(&mut self) -> Option<LayerId> {
+        (!self.layers.is_empty()).then(|| self.layers.remove(0).id)
+    }
+
+    /// # Returns
+    /// Whether the layer was in the stack.
+    pub fn remove(&mut self, id: LayerId) -> bool {
+        let len = self.layers.len();
+        self.layers.retain(|layer| layer.id != id);
+        self.layers.len() != len
+    }
+
+    pub fn contains(&self, id: LayerId) -> bool {
+        self.layers.iter().any(|layer| layer.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Start a new frame from the polled `input`, clearing what every layer
+    /// has consumed in the last one.
+    pub fn update(&mut self, input: &InputSnapshot) {
+        self.keys.clone_from(input.keys());
+        self.cursor = (input.cursor().current(), input.cursor().delta());
+        self.mouse_wheel = (input.mouse_wheel(), input.mouse_wheel_pixels());
+
+        // held or released in this frame, but not pressed
+        let seen: Vec<bool> = self.keys.frames_mut().map(|frames| *frames > 1).collect();
+        for layer in self.layers.iter_mut() {
+            let entries = layer.sticky.iter_mut().zip(&layer.hidden.keys).zip(&seen);
+            for ((sticky, hidden), seen) in entries {
+                *sticky = *hidden && *seen;
+            }
+            layer.consumed.clear();
+            layer.hidden.clear();
+        }
+    }
+
+    /// View the input of this frame as seen by the layer `id`, given what the
+    /// layers above it have consumed so far.
+    pub fn layer(&mut self, id: LayerId) -> Option<LayerInput<'_>> {
+        let index = self.layers.iter().position(|layer| layer.id == id)?;
+        let (above, below) = self.layers.split_at_mut(index);
+        let layer = &mut below[0];
+
+        layer.hidden.clear();
+        for other in above.iter() {
+            layer.hidden.union(&other.consumed);
+        }
+
+        layer.keys.clone_from(&self.keys);
+        let entries = layer
+            .keys
+            .frames_mut()
+            .zip(layer.hidden.keys.iter_mut())
+            .zip(&layer.sticky);
+        for ((frames, hidden), sticky) in entries {
+            *hidden |= *sticky;
+            *frames = match (*hidden, *sticky) {
+                (false, _) => *frames,
+                (true, true) => 0,
+                // seen down before, unless pressed in this very frame
+                (true, false) if *frames > 1 => RELEASE_SIGNAL,
+                (true, false) => 0,
+            };
+        }
+
+        Some(LayerInput {
+            layer,
+            cursor: self.cursor,
+            mouse_wheel: self.mouse_wheel,
+        })
+    }
+}
+
+/// The input of a frame as seen by a layer of [`InputLayers`], through which
+/// it can also consume input from the layers below.
+#[derive(Debug)]
+pub struct LayerInput<'a> {
+    layer: &'a mut Layer,
+    cursor: ((f64, f64), (f64, f64)),
+    mouse_wheel: ((f32, f32), (f32, f32)),
+}
+
+impl LayerInput<'_> {
+    pub fn id(&self) -> LayerId {
+        self.layer.id
+    }
+
+    pub fn keys(&self) -> &Keys {
+        &self.layer.keys
+    }
+
+    /// The cursor position, unless consumed by a layer above.
+    pub fn cursor_position(&self) -> Option<(f64, f64)> {
+        (!self.layer.hidden.cursor).then_some(self.cursor.0)
+    }
+
+    /// The raw cursor movement, which is zero if consumed by a layer above.
+    pub fn cursor_delta(&self) -> (f64, f64) {
+        if self.layer.hidden.cursor {
+            (0.0, 0.0)
+        } else {
+            self.cursor.1
+        }
+    }
+
+    /// Same as [`InputState::mouse_wheel`](crate::input::InputState::mouse_wheel),
+    /// which is zero if consumed by a layer above.
+    pub fn mouse_wheel(&self) -> (f32, f32) {
+        if self.layer.hidden.scroll {
+            (0.0, 0.0)
+        } else {
+            self.mouse_wheel.0
+        }
+    }
+
+    /// Same as
+    /// [`InputState::mouse_wheel_pixels`](crate::input::InputState::mouse_wheel_pixels),
+    /// which is zero if consumed by a layer above.
+    pub fn mouse_wheel_pixels(&self) -> (f32, f32) {
+        if self.layer.hidden.scroll {
+            (0.0, 0.0)
+        } else {
+            self.mouse_wheel.1
+        }
+    }
+
+    fn consume_entry(&mut self, index: usize) {
+        self.layer.consumed.keys[index] = true;
+    }
+
+    /// Hide the physical key from the layers below for this frame.
+    pub fn consume_key(&mut self, code: KeyCode) {
+        let code: KeyboardKeyCode = code.into();
+        self.consume_entry(u16::from(code) as usize);
+    }
+
+    pub fn consume_logical(&mut self, key: impl Into<LogicalKeyCode>) {
+        let key: LogicalKeyCode = key.into();
+        self.consume_entry(LOGICAL_OFFSET + u16::from(key) as usize);
+    }
+
+    pub fn consume_mouse(&mut self, button: MouseButton) {
+        let index: MouseButtonIndex = button.into();
+        self.consume_entry(MOUSE_OFFSET + u16::from(index) as usize);
+    }
+
+    pub fn consume_gamepad(&mut self, pad: GamepadId, button: GamepadButton) {
+        self.consume_entry(GAMEPAD_OFFSET + pad.index() * GAMEPAD_BUTTONS + button.index());
+    }
+
+    /// Hide every physical and logical key from the layers below for this
+    /// frame, e.g. while a text field has focus.
+    pub fn consume_keyboard(&mut self) {
+        self.layer.consumed.keys[..MOUSE_OFFSET].fill(true);
+    }
+
+    pub fn consume_scroll(&mut self) {
+        self.layer.consumed.scroll = true;
+    }
+
+    pub fn consume_cursor(&mut self) {
+        self.layer.consumed.cursor = true;
+    }
+
+    /// Hide all of the input from the layers below for this frame, e.g.
+    /// while a modal window is open.
+    pub fn consume_all(&mut self) {
+        self.layer.consumed.keys.fill(true);
+        self.consume_scroll();
+        self.consume_cursor();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputHarness;
+
+    #[test]
+    fn consumed_input_is_hidden_below() {
+        let mut input = InputHarness::new();
+        let game = input.state_mut().layers_mut().push(0);
+        let ui = input.state_mut().layers_mut().push(10);
+
+        input.dispatcher().press_key(KeyCode::KeyW);
+        input
+            .dispatcher()
+            .push_mouse_button(MouseButton::Left, true);
+        input.dispatcher().move_cursor((10.0, 10.0));
+        input.dispatcher().scroll((0.0, 1.0));
+        let layers = input.step().layers_mut();
+
+        let mut top = layers.layer(ui).unwrap();
+        assert!(top.keys().mouse_pressed(MouseButton::Left));
+        top.consume_mouse(MouseButton::Left);
+        top.consume_scroll();
+        top.consume_cursor();
+
+        let bottom = layers.layer(game).unwrap();
+        assert!(bottom.keys().key_pressed(KeyCode::KeyW));
+        assert!(!bottom.keys().mouse_down(MouseButton::Left));
+        assert!(!bottom.keys().mouse_released(MouseButton::Left));
+        assert_eq!(bottom.mouse_wheel(), (0.0, 0.0));
+        assert_eq!(bottom.cursor_position(), None);
+        assert_eq!(bottom.cursor_delta(), (0.0, 0.0));
+
+        // still hidden while held, even once no longer consumed
+        let layers = input.step().layers_mut();
+        let mut top = layers.layer(ui).unwrap();
+        top.consume_key(KeyCode::KeyW);
+        assert!(top.keys().mouse_held(MouseButton::Left, 0));
+
+        let bottom = layers.layer(game).unwrap();
+        assert!(bottom.keys().key_released(KeyCode::KeyW));
+        assert!(!bottom.keys().mouse_down(MouseButton::Left));
+        assert_eq!(bottom.cursor_position(), Some((10.0, 10.0)));
+
+        input.dispatcher().release_key(KeyCode::KeyW);
+        input
+            .dispatcher()
+            .push_mouse_button(MouseButton::Left, false);
+        let layers = input.step().layers_mut();
+        assert!(layers.layer(ui).unwrap().keys().key_released(KeyCode::KeyW));
+        let bottom = layers.layer(game).unwrap();
+        assert_eq!(bottom.keys().key_frames(KeyCode::KeyW), 0);
+        assert_eq!(bottom.keys().mouse_frames(MouseButton::Left), 0);
+
+        // the layers below see the input again once released
+        input.dispatcher().press_key(KeyCode::KeyW);
+        let layers = input.step().layers_mut();
+        assert!(layers.layer(ui).is_some());
+        assert!(
+            layers
+                .layer(game)
+                .unwrap()
+                .keys()
+                .key_pressed(KeyCode::KeyW)
+        );
+    }
+
+    #[test]
+    fn layers_ordered_by_priority() {
+        let mut layers = InputLayers::new();
+        let game = layers.push(0);
+        let ui = layers.push(10);
+        let console = layers.push(10);
+        let background = layers.push(-5);
+        assert_eq!(layers.len(), 4);
+
+        let order: Vec<_> = layers.layers.iter().map(|layer| layer.id).collect();
+        assert_eq!(order, [console, ui, game, background]);
+
+        let mut snapshot = InputSnapshot::default();
+        snapshot
+            .keys_mut()
+            .press_change(crate::input::DeltaPacket::Keyboard {
+                code: KeyCode::Escape.into(),
+                down: true,
+            });
+        layers.update(&snapshot);
+        layers.layer(console).unwrap().consume_keyboard();
+        assert!(!layers.layer(game).unwrap().keys().key_down(KeyCode::Escape));
+
+        assert_eq!(layers.pop(), Some(console));
+        assert!(layers.remove(background));
+        assert!(!layers.remove(background));
+        assert!(!layers.contains(console));
+        layers.update(&snapshot);
+        assert!(layers.layer(ui).unwrap().keys().key_down(KeyCode::Escape));
+    }
+}
@@ -1,215 +1,1578 @@
+pub mod action;
+pub mod cursor;
+pub mod file;
+pub mod gamepad;
+pub mod gesture;
+pub mod harness;
+pub mod keyboard;
+pub mod layers;
+pub mod record;
+pub mod scroll;
 pub mod stream;
+pub mod text;
+mod timestamp;
+pub mod touch;
 
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
+    path::Path,
     sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU16, Ordering},
     },
+    time::{Duration, Instant},
 };
 
+pub use action::{ActionBindings, ActionMap, AxisBinding, Binding, Trigger};
+pub use cursor::{CursorAppearance, CursorIcon, CursorImageError, CustomCursorImage, GrabMode};
+pub use file::FileEvent;
+pub use gamepad::{
+    Deadzones, GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, Gamepads,
+    MAX_GAMEPADS, VirtualGamepads,
+};
+pub use gesture::{Gesture, GestureConfig, GestureRecognizer};
+pub use harness::InputHarness;
+pub use keyboard::{LogicalKeyCode, Modifiers};
+pub use layers::{InputLayers, LayerId, LayerInput};
+pub use record::{InputFrame, InputRecorder, InputReplay};
+pub use scroll::{DEFAULT_PIXELS_PER_LINE, MouseWheel, ScrollEvent, ScrollUnit};
 pub use stream::{DeltaPacket, IterInputStream};
+pub use text::{ImeOptions, TextEvent};
+pub use touch::{MAX_TOUCHES, TouchOptions, TouchPhase, TouchPoint, Touches};
 pub use winit::event::MouseButton;
 use winit::event::MouseScrollDelta;
 pub use winit::keyboard::KeyCode;
 
-use crate::{input::stream::InputStream, sync};
+use crate::{
+    context::Clock,
+    input::{
+        file::FileQueue,
+        gamepad::{GAMEPAD_BUTTONS, GamepadAxes, GamepadAxesValues},
+        stream::{InputStream, SideQueue, SideSender, StreamPacket, side_queue},
+        text::TextQueue,
+        timestamp::{
+            ELAPSED_UNIT_MICROS, Stamp, TIMESTAMP_MASK, TimeOrigin, TimestampDecoder,
+            TimestampEncoder,
+        },
+        touch::{TouchPoints, TouchTracker},
+    },
+    sync,
+};
 
 const KEYBOARD_ENTRIES: usize = 512;
 const MOUSE_ENTRIES: usize = 24;
+const LOGICAL_ENTRIES: usize = 768;
 
 const RELEASE_SIGNAL: u16 = 0xFFFF;
 const MAX_HOLD_FRAMES: u16 = 0xFFFF - 1;
 
-pub const SLOT_COUNT: usize = 12;
+/// The default amount of folds in each section of the input stream, each
+/// holding 2 packets.
+///
+/// A mouse button change takes a single packet, a key press two along with
+/// its logical key, and a typed character another one for its text. A full
+/// timestamp takes one more, usually once per frame. The default holds 32
+/// packets per frame, e.g. 10 typed characters.
+pub const SLOT_COUNT: usize = 16;
+/// The default amount of sections of the input stream.
 pub const SECTION_COUNT: usize = 6;
 
+/// Create the input dispatcher, to be owned by the window thread, and its
+/// first subscriber.
+///
+/// Further consumers of the same input can be created with
+/// [`InputDispatcher::subscribe`].
 pub fn stream<const SLOTS: usize, const SECTIONS: usize>() -> (
     InputState<SLOTS, SECTIONS>,
     InputDispatcher<SLOTS, SECTIONS>,
 ) {
-    let stream = Arc::new(InputStream::new());
-
-    let state = InputState {
-        stream: Arc::clone(&stream),
-        ..Default::default()
-    };
-
-    // clones local value and shared arc data used to sync
-    let cursor_options = state.cursor_options.clone();
-    let cursor = state.snapshot.cursor.clone();
-    let mouse_wheel = state.snapshot.mouse_wheel.clone();
-    let resync_flag = state.resync_flag.clone();
-
-    let dispatcher = InputDispatcher {
-        stream,
-        cursor_options,
-        cursor,
-        mouse_wheel,
-        resync_flag,
+    let mut dispatcher = InputDispatcher {
+        subscribers: Vec::new(),
+        cursor_options: Arc::default(),
+        ime_options: Arc::default(),
+        touch_options: Arc::default(),
+        touch_tracker: TouchTracker::default(),
+        text_seq: 0,
+        ime_enabled: false,
+        file_seq: 0,
+        logical_keys: HashMap::new(),
+        held_keys: HashSet::new(),
+        held_logical_keys: HashSet::new(),
+        held_buttons: HashSet::new(),
+        modifiers: Modifiers::empty(),
+        gamepad_backend: None,
+        gamepad_values: Default::default(),
+        cursor_position: (0.0, 0.0),
+        click_seq: 0,
+        timestamps: TimestampEncoder::new(TimeOrigin::default()),
     };
+    let state = dispatcher.subscribe();
 
     (state, dispatcher)
 }
 
+/// The synchronisation structures shared with a single [`InputState`], all
+/// of which are written to by the [`InputDispatcher`] for each of its
+/// subscribers.
+#[derive(Debug)]
+struct Subscriber<const SLOTS: usize, const SECTIONS: usize> {
+    stream: Arc<InputStream<SLOTS, SECTIONS>>,
+    cursor: Arc<Cursor>,
+    mouse_wheel: Arc<sync::TriCell<MouseWheelValue>>,
+    touches: Arc<Touches>,
+    gamepad_axes: Arc<GamepadAxes>,
+    text: SideSender<TextEvent>,
+    files: SideSender<FileEvent>,
+    clicks: SideSender<ClickValues>,
+    resync_flag: Arc<AtomicBool>,
+
+    /// The touches of the frame being written, which ends whenever this
+    /// subscriber is synchronised.
+    touch_tracker: TouchTracker,
+    /// Whether a packet has been dropped by the stream, after which the held
+    /// input is sent again at the start of the next frame.
+    resend_held: bool,
+}
+
+impl<const SLOTS: usize, const SECTIONS: usize> Subscriber<SLOTS, SECTIONS> {
+    /// Whether the [`InputState`] has been dropped, as it is the only other
+    /// owner of the shared structures.
+    fn is_orphaned(&self) -> bool {
+        Arc::strong_count(&self.resync_flag) == 1
+    }
+}
+
 type CursorValues = (f64, f64);
-type MouseWheelValue = f32;
+type MouseWheelValue = MouseWheel;
 
 /// This is the proper "owner" of the input synchronisation structures,
 /// despite shared ownership.
 ///
 /// This is the only place where [`TriCell::advance`] or similar main
 /// synchronisation duties should occur.
+///
+/// Every packet and value is sent to each of its
+/// [`subscribers`](InputDispatcher::subscribe) without ever locking: the
+/// events which do not fit in a packet go through bounded side channels, and
+/// are dropped along with their markers when a side channel is full, like any
+/// packet the stream has no room for.
 #[derive(Debug, Default)]
 pub struct InputDispatcher<const SLOTS: usize, const SECTIONS: usize> {
-    stream: Arc<InputStream<SLOTS, SECTIONS>>,
+    subscribers: Vec<Subscriber<SLOTS, SECTIONS>>,
 
     cursor_options: Arc<CursorOptions>,
-    cursor: Arc<Cursor>,
-    mouse_wheel: Arc<sync::TriCell<MouseWheelValue>>,
-
-    resync_flag: Arc<AtomicBool>,
+    ime_options: Arc<ImeOptions>,
+
+    touch_options: Arc<TouchOptions>,
+    touch_tracker: TouchTracker,
+
+    text_seq: u16,
+    ime_enabled: bool,
+
+    file_seq: u16,
+
+    /// The logical key each held physical key has been pressed as, so that
+    /// its release is sent for the same logical key even if the layout's
+    /// interpretation has changed since, e.g. by releasing shift first.
+    logical_keys: HashMap<KeyboardKeyCode, LogicalKeyCode>,
+
+    /// Everything that is currently down, to be released once the window
+    /// stops receiving its input.
+    held_keys: HashSet<KeyboardKeyCode>,
+    /// Logical keys pressed without a physical key.
+    held_logical_keys: HashSet<LogicalKeyCode>,
+    held_buttons: HashSet<MouseButton>,
+    modifiers: Modifiers,
+
+    gamepad_backend: Option<Box<dyn GamepadBackend>>,
+    /// Latest value of every gamepad axis, carried over to the next frame on
+    /// every sync.
+    gamepad_values: [GamepadAxesValues; MAX_GAMEPADS],
+
+    /// Latest cursor position, sent along with mouse button events.
+    cursor_position: CursorValues,
+    click_seq: u16,
+    timestamps: TimestampEncoder,
 }
 
 impl<const SLOTS: usize, const SECTIONS: usize> InputDispatcher<SLOTS, SECTIONS> {
+    /// Create another consumer of the input, with its own stream and
+    /// [`Keys`] snapshot, which receives everything sent from now on.
+    ///
+    /// The keys, logical keys, mouse buttons and modifiers held at the time
+    /// are sent as pressed in its first frame, along with the touches still
+    /// in contact. Gamepad buttons are not.
+    ///
+    /// The subscriber is removed once its [`InputState`] is dropped.
+    ///
+    /// Each subscriber is synchronised independently, see
+    /// [`InputState::sync`], while the cursor, IME and touch options are
+    /// shared by all of them.
+    pub fn subscribe(&mut self) -> InputState<SLOTS, SECTIONS> {
+        let (text, text_queue) = side_queue();
+        let (files, file_queue) = side_queue();
+        let (clicks, click_queue) = side_queue();
+        let mut state = InputState {
+            text: text_queue,
+            files: file_queue,
+            clicks: click_queue,
+            cursor_options: Arc::clone(&self.cursor_options),
+            ime_options: Arc::clone(&self.ime_options),
+            touch_options: Arc::clone(&self.touch_options),
+            ..Default::default()
+        };
+        state.snapshot.timestamps = TimestampDecoder::new(self.timestamps.origin());
+
+        let touch_tracker = self.touch_tracker.clone();
+        state.snapshot.touches.points.set(touch_tracker.points());
+
+        self.subscribers
+            .retain(|subscriber| !subscriber.is_orphaned());
+        self.subscribers.push(Subscriber {
+            stream: Arc::clone(&state.stream),
+            cursor: Arc::clone(&state.snapshot.cursor),
+            mouse_wheel: Arc::clone(&state.snapshot.mouse_wheel),
+            touches: Arc::clone(&state.snapshot.touches),
+            gamepad_axes: Arc::clone(state.snapshot.gamepads.axes_shared()),
+            text,
+            files,
+            clicks,
+            resync_flag: Arc::clone(&state.resync_flag),
+            touch_tracker,
+            resend_held: false,
+        });
+
+        // the new subscriber needs a full stamp to measure the elapsed time
+        // from, sent at the next sync or along with the next event
+        self.timestamps.reset();
+
+        let held = self.held_packets();
+        if let Some(subscriber) = self.subscribers.last_mut() {
+            for packet in held {
+                subscriber.resend_held |= !subscriber.stream.push_front(packet.into());
+            }
+        }
+        state
+    }
+
+    /// The amount of [`subscribers`](InputDispatcher::subscribe), including
+    /// the one created along with the dispatcher.
+    ///
+    /// Dropped subscribers are only removed at the next
+    /// [`sync`](InputDispatcher::sync) or subscription.
+    pub fn subscribers(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    /// Stamp the input with the time of `clock` from now on, e.g. the
+    /// [`ManualClock`](crate::context::ManualClock) of a test.
+    ///
+    /// The [`Context`](crate::context::Context) sets its own clock.
+    pub fn set_clock(&mut self, clock: impl Clock) {
+        self.timestamps.set_clock(clock);
+    }
+
+    /// Advance every subscriber whose consumer thread has synchronised since
+    /// the last time, to its next frame.
     pub fn sync(&mut self) {
-        // ensure input consumer thread has passed
-        if self.resync_flag.swap(false, Ordering::Acquire) {
-            self.stream.frame_front();
+        self.subscribers
+            .retain(|subscriber| !subscriber.is_orphaned());
+
+        let mut advanced = Vec::new();
+        for (i, subscriber) in self.subscribers.iter_mut().enumerate() {
+            // ensure input consumer thread has passed
+            if !subscriber.resync_flag.swap(false, Ordering::Acquire) {
+                continue;
+            }
+            advanced.push(i);
+            subscriber.stream.frame_front();
 
-            let cursor_abs = self.cursor.current.get();
+            // the read value is a frame behind, carry over the latest one
+            let cursor_abs = self.cursor_position;
 
-            let _ = self.cursor.current.advance();
-            let _ = self.cursor.delta.advance();
-            let _ = self.mouse_wheel.advance();
+            let _ = subscriber.cursor.current.advance();
+            let _ = subscriber.cursor.delta.advance();
+            let _ = subscriber.mouse_wheel.advance();
+
+            subscriber.cursor.current.set(cursor_abs);
+            subscriber.cursor.delta.set((0.0, 0.0));
+            subscriber.mouse_wheel.set(MouseWheel::default());
+
+            let pads = subscriber.gamepad_axes.pads.iter();
+            for (cell, values) in pads.zip(self.gamepad_values) {
+                let _ = cell.advance();
+                cell.set(values);
+            }
 
-            self.cursor.current.set(cursor_abs);
-            self.cursor.delta.set((0.0, 0.0));
-            self.mouse_wheel.set(0.0);
+            let _ = subscriber.touches.points.advance();
+            subscriber.touch_tracker.next_frame();
+            subscriber
+                .touches
+                .points
+                .set(subscriber.touch_tracker.points());
 
             // cursor options handled separately
         }
+
+        if !advanced.is_empty()
+            && let Some(micros) = self.timestamps.keep_alive()
+        {
+            self.push_front(DeltaPacket::Timestamp { micros }.into());
+        }
+
+        // the held input goes after the stamp, at the start of a new frame
+        if !advanced.iter().any(|&i| self.subscribers[i].resend_held) {
+            return;
+        }
+        let held = self.held_packets();
+        for i in advanced {
+            let subscriber = &mut self.subscribers[i];
+            if !subscriber.resend_held {
+                continue;
+            }
+            subscriber.resend_held = false;
+            for packet in held.iter() {
+                subscriber.resend_held |= !subscriber.stream.push_front((*packet).into());
+            }
+        }
+    }
+
+    /// The packets pressing everything currently held.
+    fn held_packets(&self) -> Vec<DeltaPacket> {
+        let mut packets = Vec::new();
+        if !self.modifiers.is_empty() {
+            packets.push(DeltaPacket::Modifiers {
+                state: self.modifiers,
+            });
+        }
+        for &code in self.held_keys.iter() {
+            packets.push(DeltaPacket::Keyboard { code, down: true });
+        }
+        let logical_keys = self.held_logical_keys.iter();
+        for &key in self.logical_keys.values().chain(logical_keys) {
+            packets.push(DeltaPacket::Logical { key, down: true });
+        }
+        for &button in self.held_buttons.iter() {
+            let button = button.into();
+            packets.push(DeltaPacket::Mouse { button, down: true });
+        }
+        packets
+    }
+
+    /// Push a packet to the input stream of every subscriber.
+    fn push_front(&mut self, packet: StreamPacket) {
+        self.push_front_with(packet, |_| true);
+    }
+
+    /// Push a marker packet to the input stream of every subscriber, after
+    /// `send` has sent what it refers to through the subscriber's side
+    /// channel.
+    ///
+    /// If `send` fails, the marker is dropped as well.
+    fn push_front_with(
+        &mut self,
+        packet: StreamPacket,
+        mut send: impl FnMut(&mut Subscriber<SLOTS, SECTIONS>) -> bool,
+    ) {
+        let mut pushed = true;
+        for subscriber in self.subscribers.iter_mut() {
+            let sent = send(subscriber);
+            if !sent {
+                subscriber.stream.drop_front(1);
+            }
+            if !sent || !subscriber.stream.push_front(packet) {
+                // the consumer may release everything once it notices
+                subscriber.resend_held = true;
+                pushed = false;
+            }
+        }
+
+        // the elapsed time is only meaningful after the stamps it follows
+        if !pushed {
+            self.timestamps.reset();
+        }
+    }
+
+    /// Push a packet to the input stream along with the time it has been
+    /// received at, either as the time elapsed since the previous packet or
+    /// as a full timestamp sent before it.
+    fn push_packet(&mut self, packet: DeltaPacket) {
+        self.push_packet_with(packet, |_| true);
+    }
+
+    /// [`Push_packet`](InputDispatcher::push_packet) a marker packet, see
+    /// [`push_front_with`](InputDispatcher::push_front_with).
+    fn push_packet_with(
+        &mut self,
+        packet: DeltaPacket,
+        send: impl FnMut(&mut Subscriber<SLOTS, SECTIONS>) -> bool,
+    ) {
+        let elapsed = match self.timestamps.stamp() {
+            Stamp::Full(micros) => {
+                self.push_front(DeltaPacket::Timestamp { micros }.into());
+                0
+            }
+            Stamp::Elapsed(elapsed) => elapsed,
+        };
+        self.push_front_with(StreamPacket { packet, elapsed }, send);
+    }
+
+    fn set_cursor(&self, position: CursorValues) {
+        for subscriber in self.subscribers.iter() {
+            subscriber.cursor.current.set(position);
+        }
+    }
+
+    fn add_cursor_delta(&self, (dx, dy): CursorValues) {
+        for subscriber in self.subscribers.iter() {
+            subscriber
+                .cursor
+                .delta
+                .set_with(|(odx, ody)| (odx + dx, ody + dy));
+        }
+    }
+
+    pub fn cursor_options(&self) -> &CursorOptions {
+        &self.cursor_options
+    }
+
+    pub fn cursor_options_shared(&self) -> &Arc<CursorOptions> {
+        &self.cursor_options
+    }
+
+    pub fn ime_options(&self) -> &ImeOptions {
+        &self.ime_options
+    }
+
+    /// Send a [`TextEvent`] through the text channel, in order with the key
+    /// events pushed to the input stream.
+    pub fn push_text(&mut self, event: TextEvent) {
+        let seq = self.text_seq;
+        self.text_seq = self.text_seq.wrapping_add(1);
+
+        self.push_packet_with(DeltaPacket::Text { seq }, |subscriber| {
+            subscriber.text.push(seq, event.clone())
+        });
+    }
+
+    /// Send a [`FileEvent`] through the file channel, in order with the
+    /// events pushed to the input stream.
+    pub fn push_file(&mut self, event: FileEvent) {
+        let seq = self.file_seq;
+        self.file_seq = self.file_seq.wrapping_add(1);
+
+        self.push_packet_with(DeltaPacket::File { seq }, |subscriber| {
+            subscriber.files.push(seq, event.clone())
+        });
+    }
+
+    /// Forward files dragged over or dropped onto the window, at the latest
+    /// cursor position.
+    ///
+    /// # Returns
+    /// The event that has been sent, if any, so that it can also be handled
+    /// by the render thread.
+    pub fn handle_file_events(&mut self, event: &winit::event::WindowEvent) -> Option<FileEvent> {
+        use winit::event::WindowEvent;
+
+        let position = self.cursor_position;
+        let event = match event {
+            WindowEvent::HoveredFile(path) => FileEvent::Hovered {
+                path: path.clone(),
+                position,
+            },
+            WindowEvent::DroppedFile(path) => FileEvent::Dropped {
+                path: path.clone(),
+                position,
+            },
+            WindowEvent::HoveredFileCancelled => FileEvent::HoverCancelled,
+            _ => return None,
+        };
+        self.push_file(event.clone());
+        Some(event)
+    }
+
+    pub fn handle_text_event(&mut self, event: &winit::event::WindowEvent) {
+        use winit::event::{Ime, WindowEvent};
+
+        if let WindowEvent::Ime(ime) = event {
+            let event = match ime {
+                Ime::Enabled => {
+                    self.ime_enabled = true;
+                    TextEvent::ImeEnabled
+                }
+                Ime::Disabled => {
+                    self.ime_enabled = false;
+                    TextEvent::ImeDisabled
+                }
+                Ime::Preedit(text, cursor) => TextEvent::Preedit {
+                    text: text.clone(),
+                    cursor: *cursor,
+                },
+                Ime::Commit(text) => TextEvent::Commit(text.clone()),
+            };
+            self.push_text(event);
+        }
+    }
+
+    /// Set the source of gamepad events, polled by
+    /// [`poll_gamepads`](InputDispatcher::poll_gamepads).
+    pub fn set_gamepad_backend(&mut self, backend: impl GamepadBackend + 'static) {
+        self.gamepad_backend = Some(Box::new(backend));
+    }
+
+    pub fn take_gamepad_backend(&mut self) -> Option<Box<dyn GamepadBackend>> {
+        self.gamepad_backend.take()
+    }
+
+    /// Dispatch all pending events of the gamepad backend, if any.
+    pub fn poll_gamepads(&mut self) {
+        let Some(mut backend) = self.gamepad_backend.take() else {
+            return;
+        };
+        while let Some(event) = backend.poll() {
+            self.push_gamepad_event(event);
+        }
+        self.gamepad_backend = Some(backend);
+    }
+
+    /// Buttons and connection changes are sent through the input stream,
+    /// while axes are sent with the next [`sync`](InputDispatcher::sync).
+    pub fn push_gamepad_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(pad) => {
+                self.push_packet(DeltaPacket::GamepadConnection {
+                    pad,
+                    connected: true,
+                });
+            }
+            GamepadEvent::Disconnected(pad) => {
+                self.set_gamepad_values(pad, Default::default());
+                self.push_packet(DeltaPacket::GamepadConnection {
+                    pad,
+                    connected: false,
+                });
+            }
+            GamepadEvent::Button { pad, button, down } => {
+                self.push_packet(DeltaPacket::Gamepad { pad, button, down });
+            }
+            GamepadEvent::Axis { pad, axis, value } => {
+                let mut values = self.gamepad_values[pad.index()];
+                values[axis.index()] = value;
+                self.set_gamepad_values(pad, values);
+            }
+        }
+    }
+
+    fn set_gamepad_values(&mut self, pad: GamepadId, values: GamepadAxesValues) {
+        self.gamepad_values[pad.index()] = values;
+        for subscriber in self.subscribers.iter() {
+            subscriber.gamepad_axes.pads[pad.index()].set(values);
+        }
+    }
+
+    pub fn handle_mouse_events(&mut self, event: &winit::event::WindowEvent) {
+        match event {
+            winit::event::WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = (position.x, position.y);
+                self.set_cursor(self.cursor_position);
+            }
+            winit::event::WindowEvent::MouseWheel { delta, .. } => match *delta {
+                MouseScrollDelta::LineDelta(x, y) => self.push_scroll(ScrollUnit::Lines, (x, y)),
+                MouseScrollDelta::PixelDelta(position) => {
+                    let delta = (position.x as f32, position.y as f32);
+                    self.push_scroll(ScrollUnit::Pixels, delta);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Send a scroll `delta`, both as part of the frame's accumulated
+    /// [`MouseWheel`] and as ordered [`ScrollEvent`]s.
+    ///
+    /// A delta too large to be sent as a single packet is split into several
+    /// [`ScrollEvent`]s along the same axis. Deltas beyond
+    /// [`ScrollUnit::max_delta`] are clamped, in both the events and the
+    /// [`MouseWheel`].
+    pub fn push_scroll(&mut self, unit: ScrollUnit, delta: (f32, f32)) {
+        let max = unit.max_delta();
+        let clamp = |delta: f32| {
+            if delta.is_nan() {
+                0.0
+            } else {
+                delta.clamp(-max, max)
+            }
+        };
+        let clamped = (clamp(delta.0), clamp(delta.1));
+        if clamped != delta {
+            tracing::event!(
+                name: "input.scroll.clamped",
+                tracing::Level::WARN,
+                "Scroll delta {delta:?} has been clamped to {clamped:?} {unit:?}"
+            );
+        }
+        let delta = clamped;
+
+        for subscriber in self.subscribers.iter() {
+            subscriber
+                .mouse_wheel
+                .set_with(|wheel| wheel.add(unit, delta));
+        }
+
+        for (horizontal, delta) in [(true, delta.0), (false, delta.1)] {
+            for delta in unit.encode(delta) {
+                self.push_packet(DeltaPacket::Scroll {
+                    unit,
+                    horizontal,
+                    delta,
+                });
+            }
+        }
+    }
+
+    pub fn handle_raw_cursor_events(&mut self, event: &winit::event::DeviceEvent) {
+        match event {
+            winit::event::DeviceEvent::MouseMotion { delta } => {
+                self.add_cursor_delta(*delta);
+            }
+            _ => {}
+        }
+    }
+
+    /// Send the state change of a key, by physical key and/or by logical key.
+    ///
+    /// The release of a physical key is always sent for the logical key it has
+    /// been pressed as, ignoring `logical`.
+    pub fn push_key(
+        &mut self,
+        physical: Option<KeyboardKeyCode>,
+        logical: Option<LogicalKeyCode>,
+        down: bool,
+    ) {
+        let logical = match physical {
+            Some(code) if down => {
+                self.held_keys.insert(code);
+                if let Some(key) = logical {
+                    self.logical_keys.insert(code, key);
+                }
+                logical
+            }
+            Some(code) => {
+                self.held_keys.remove(&code);
+                self.logical_keys.remove(&code).or(logical)
+            }
+            None => {
+                if let Some(key) = logical {
+                    if down {
+                        self.held_logical_keys.insert(key);
+                    } else {
+                        self.held_logical_keys.remove(&key);
+                    }
+                }
+                logical
+            }
+        };
+
+        if let Some(code) = physical {
+            self.push_packet(DeltaPacket::Keyboard { code, down });
+        }
+        if let Some(key) = logical {
+            self.push_packet(DeltaPacket::Logical { key, down });
+        }
+    }
+
+    pub fn push_modifiers(&mut self, state: Modifiers) {
+        self.modifiers = state;
+        self.push_packet(DeltaPacket::Modifiers { state });
+    }
+
+    /// Send the release of every key and mouse button that is held, as well
+    /// as of the modifiers.
+    ///
+    /// Gamepads are left untouched, as they do not depend on the window.
+    pub fn release_all(&mut self) {
+        let keys: Vec<_> = self.held_keys.iter().copied().collect();
+        for code in keys {
+            self.push_key(Some(code), None, false);
+        }
+        let keys: Vec<_> = self.held_logical_keys.iter().copied().collect();
+        for key in keys {
+            self.push_key(None, Some(key), false);
+        }
+        self.release_mouse_buttons();
+
+        if !self.modifiers.is_empty() {
+            self.push_modifiers(Modifiers::empty());
+        }
+    }
+
+    /// Send the release of every mouse button that is held.
+    pub fn release_mouse_buttons(&mut self) {
+        let buttons: Vec<_> = self.held_buttons.iter().copied().collect();
+        for button in buttons {
+            self.push_mouse_button(button, false);
+        }
+    }
+
+    /// Send whether the window is focused, releasing everything that is held
+    /// once it is not, as the releases will never be received.
+    pub fn push_focus(&mut self, focused: bool) {
+        if !focused {
+            self.release_all();
+        }
+        self.push_packet(DeltaPacket::Focus { focused });
+    }
+
+    /// Send whether the cursor is over the window, releasing the held mouse
+    /// buttons once it is not.
+    ///
+    /// The keys are left untouched, as they are still received for as long as
+    /// the window is focused.
+    pub fn push_hover(&mut self, hovered: bool) {
+        if !hovered {
+            self.release_mouse_buttons();
+        }
+        self.push_packet(DeltaPacket::Hover { hovered });
+    }
+
+    pub fn handle_focus_events(&mut self, event: &winit::event::WindowEvent) {
+        use winit::event::WindowEvent;
+
+        match event {
+            WindowEvent::Focused(focused) => self.push_focus(*focused),
+            WindowEvent::CursorEntered { .. } => self.push_hover(true),
+            WindowEvent::CursorLeft { .. } => self.push_hover(false),
+            _ => {}
+        }
+    }
+
+    pub fn handle_key_event(&mut self, event: &winit::event::WindowEvent) {
+        use winit::event::{ElementState, WindowEvent};
+        use winit::keyboard::PhysicalKey;
+
+        match event {
+            WindowEvent::KeyboardInput { event: key, .. } => {
+                let physical = match key.physical_key {
+                    PhysicalKey::Code(code) => Some(code.into()),
+                    PhysicalKey::Unidentified(_) => None,
+                };
+                let logical = LogicalKeyCode::from_key(&key.logical_key);
+                let down = matches!(key.state, ElementState::Pressed);
+                self.push_key(physical, logical, down);
+
+                // while the IME is enabled, text is only received through
+                // its commits
+                if let Some(text) = key.text.as_ref().filter(|_| !self.ime_enabled) {
+                    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+                    if !text.is_empty() {
+                        self.push_text(TextEvent::Commit(text));
+                    }
+                }
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.push_modifiers(modifiers.state().into());
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let down = matches!(*state, ElementState::Pressed);
+                self.push_mouse_button(*button, down);
+            }
+            _ => {}
+        }
+    }
+
+    /// Send the state change of a mouse button, along with the current cursor
+    /// position in whole physical pixels, see [`DeltaPacket::ClickPosition`].
+    pub fn push_mouse_button(&mut self, button: MouseButton, down: bool) {
+        let min = DeltaPacket::CLICK_POSITION_MIN as f64;
+        let max = DeltaPacket::CLICK_POSITION_MAX as f64;
+        let (x, y) = self.cursor_position;
+        let position = (
+            x.round().clamp(min, max) as i16,
+            y.round().clamp(min, max) as i16,
+        );
+
+        let seq = self.click_seq;
+        self.click_seq = (self.click_seq + 1) & DeltaPacket::CLICK_SEQ_MAX;
+        if down {
+            self.held_buttons.insert(button);
+        } else {
+            self.held_buttons.remove(&button);
+        }
+
+        let button: MouseButtonIndex = button.into();
+        self.push_packet_with(DeltaPacket::Click { button, down, seq }, |subscriber| {
+            subscriber.clicks.push(seq, position)
+        });
+    }
+
+    /// Press a physical key, without any logical key.
+    ///
+    /// This and the following methods inject input without a window, e.g. to
+    /// drive an [`InputHarness`] in tests.
+    pub fn press_key(&mut self, code: KeyCode) {
+        self.push_key(Some(code.into()), None, true);
+    }
+
+    /// Release a physical key, along with the logical key it has been pressed
+    /// as, if any.
+    pub fn release_key(&mut self, code: KeyCode) {
+        self.push_key(Some(code.into()), None, false);
+    }
+
+    /// Move the cursor to `position`, in physical pixels.
+    ///
+    /// As with a real mouse, the movement is also accumulated in the raw
+    /// cursor delta.
+    pub fn move_cursor(&mut self, position: (f64, f64)) {
+        let (x, y) = self.cursor_position;
+        let (dx, dy) = (position.0 - x, position.1 - y);
+
+        self.cursor_position = position;
+        self.set_cursor(position);
+        self.add_cursor_delta((dx, dy));
+    }
+
+    /// Send the change of a touch, with its `force` normalised in
+    /// `0.0..=1.0`.
+    ///
+    /// If [`TouchOptions::emulates_mouse`], the primary touch also moves the
+    /// cursor and presses the left mouse button.
+    pub fn push_touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: (f64, f64),
+        force: Option<f64>,
+    ) {
+        let Some(point) = self.touch_tracker.update(id, phase, position, force) else {
+            return;
+        };
+        // only the touches in contact are kept here, each subscriber keeps
+        // those of its own frame
+        self.touch_tracker.next_frame();
+        for subscriber in self.subscribers.iter_mut() {
+            let tracker = &mut subscriber.touch_tracker;
+            if tracker.update(id, phase, position, force).is_some() {
+                subscriber.touches.points.set(tracker.points());
+            }
+        }
+
+        if point.primary && self.touch_options.emulates_mouse() {
+            self.move_cursor(position);
+            match phase {
+                TouchPhase::Started => self.push_mouse_button(MouseButton::Left, true),
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    self.push_mouse_button(MouseButton::Left, false)
+                }
+                TouchPhase::Moved => {}
+            }
+        }
+    }
+
+    /// Pens are handled as touches on the platforms that report them.
+    pub fn handle_touch_events(&mut self, event: &winit::event::WindowEvent) {
+        if let winit::event::WindowEvent::Touch(touch) = event {
+            let position = (touch.location.x, touch.location.y);
+            let force = touch.force.map(|force| force.normalized());
+            self.push_touch(touch.id, touch.phase, position, force);
+        }
+    }
+
+    /// Scroll by `delta` lines.
+    pub fn scroll(&mut self, delta: (f32, f32)) {
+        self.push_scroll(ScrollUnit::Lines, delta);
+    }
+
+    /// Press and release a mouse `button` at the current cursor position.
+    pub fn click(&mut self, button: MouseButton) {
+        self.push_mouse_button(button, true);
+        self.push_mouse_button(button, false);
+    }
+}
+
+/// Cursor options requested by the consumer thread, to be applied on the
+/// window by the [`Context`](crate::context::Context).
+///
+/// Every flag is packed in a single atomic, with only the appearance stored
+/// separately:
+/// * bits 0-1: requested [`GrabMode`]
+/// * bit 2: the grab mode has changed
+/// * bit 3: the cursor is hidden
+/// * bit 4: the appearance has changed
+/// * bits 5-6: applied [`GrabMode`]
+/// * bit 7: the last requested grab mode could not be applied at all
+/// * bit 8: the visibility has changed
+#[derive(Debug, Default)]
+pub struct CursorOptions {
+    state: AtomicU16,
+    appearance: Mutex<CursorAppearance>,
+}
+
+impl CursorOptions {
+    const MODE_MASK: u16 = 0b11;
+    const GRAB_DIRTY: u16 = 1 << 2;
+    const HIDDEN: u16 = 1 << 3;
+    const APPEARANCE_DIRTY: u16 = 1 << 4;
+    const APPLIED_SHIFT: u16 = 5;
+    const GRAB_FAILED: u16 = 1 << 7;
+    const VISIBILITY_DIRTY: u16 = 1 << 8;
+
+    /// Whether a grab mode other than [`GrabMode::Free`] is requested.
+    pub fn check_grabbed(&self) -> bool {
+        self.grab_mode() != GrabMode::Free
+    }
+
+    /// Whether the requested grab mode has yet to be applied.
+    pub fn check_dirty(&self) -> bool {
+        self.state.load(Ordering::Acquire) & Self::GRAB_DIRTY != 0
+    }
+
+    /// Request [`GrabMode::Locked`] if `grabbed`, [`GrabMode::Free`]
+    /// otherwise.
+    pub fn set_grabbed(&self, grabbed: bool) {
+        self.set_grab_mode(if grabbed {
+            GrabMode::Locked
+        } else {
+            GrabMode::Free
+        });
+    }
+
+    /// The requested grab mode, which may not have been applied yet.
+    pub fn grab_mode(&self) -> GrabMode {
+        GrabMode::from_bits(self.state.load(Ordering::Relaxed) as u8)
+    }
+
+    pub fn set_grab_mode(&self, mode: GrabMode) {
+        let _ = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |state| {
+                (GrabMode::from_bits(state as u8) != mode)
+                    .then_some(state & !Self::MODE_MASK | mode as u16 | Self::GRAB_DIRTY)
+            });
+    }
+
+    /// The grab mode last applied on the window, which differs from the
+    /// requested one if the platform does not support it and a fallback has
+    /// been applied instead.
+    pub fn applied_grab_mode(&self) -> GrabMode {
+        GrabMode::from_bits((self.state.load(Ordering::Acquire) >> Self::APPLIED_SHIFT) as u8)
+    }
+
+    /// Whether the last requested grab mode could not be applied, not even
+    /// through a fallback.
+    pub fn grab_failed(&self) -> bool {
+        self.state.load(Ordering::Acquire) & Self::GRAB_FAILED != 0
+    }
+
+    /// Clear the grab change.
+    ///
+    /// # Returns
+    /// The requested grab mode if it had changed.
+    pub(crate) fn take_grab_change(&self) -> Option<GrabMode> {
+        let state = self.state.fetch_and(!Self::GRAB_DIRTY, Ordering::AcqRel);
+        (state & Self::GRAB_DIRTY != 0).then(|| GrabMode::from_bits(state as u8))
+    }
+
+    pub(crate) fn report_grab(&self, applied: GrabMode, failed: bool) {
+        let mut report = (applied as u16) << Self::APPLIED_SHIFT;
+        if failed {
+            report |= Self::GRAB_FAILED;
+        }
+        let clear = !(Self::MODE_MASK << Self::APPLIED_SHIFT | Self::GRAB_FAILED);
+        let _ = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |state| {
+                Some(state & clear | report)
+            });
+    }
+
+    /// Clear the appearance change.
+    ///
+    /// # Returns
+    /// Whether the appearance had changed.
+    pub(crate) fn take_appearance_change(&self) -> bool {
+        let state = self
+            .state
+            .fetch_and(!Self::APPEARANCE_DIRTY, Ordering::AcqRel);
+        state & Self::APPEARANCE_DIRTY != 0
+    }
+
+    /// Clear the visibility change.
+    ///
+    /// # Returns
+    /// The requested visibility if it had changed.
+    pub(crate) fn take_visibility_change(&self) -> Option<bool> {
+        let state = self
+            .state
+            .fetch_and(!Self::VISIBILITY_DIRTY, Ordering::AcqRel);
+        (state & Self::VISIBILITY_DIRTY != 0).then_some(state & Self::HIDDEN == 0)
+    }
+
+    /// Whether the cursor is shown while over the window, regardless of it
+    /// being grabbed.
+    pub fn visible(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & Self::HIDDEN == 0
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        let _ = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |state| {
+                let hidden = (!visible as u16) * Self::HIDDEN;
+                (state & Self::HIDDEN != hidden)
+                    .then_some(state & !Self::HIDDEN | hidden | Self::VISIBILITY_DIRTY)
+            });
+    }
+
+    pub fn appearance(&self) -> CursorAppearance {
+        self.appearance
+            .lock()
+            .expect("cursor appearance poisoned")
+            .clone()
+    }
+
+    /// Request the cursor to be shown as either a system [`CursorIcon`] or a
+    /// [`CustomCursorImage`].
+    pub fn set_appearance(&self, appearance: impl Into<CursorAppearance>) {
+        let appearance = appearance.into();
+        let mut current = self.appearance.lock().expect("cursor appearance poisoned");
+        if *current != appearance {
+            *current = appearance;
+            self.state
+                .fetch_or(Self::APPEARANCE_DIRTY, Ordering::Release);
+        }
+    }
+}
+
+/// Read-only view of the current state of the input as received from the
+/// window thread.
+///
+/// The input may also be [`recorded`](InputState::record) frame by frame, or
+/// [`replayed`](InputState::replay) from a previous recording in place of the
+/// input received from the window thread.
+#[derive(Debug, Default)]
+pub struct InputState<const SLOTS: usize, const SECTIONS: usize> {
+    snapshot: InputSnapshot,
+    cursor_options: Arc<CursorOptions>,
+    ime_options: Arc<ImeOptions>,
+    touch_options: Arc<TouchOptions>,
+    stream: Arc<InputStream<SLOTS, SECTIONS>>,
+    text: TextQueue,
+    files: FileQueue,
+    clicks: ClickQueue,
+    resync_flag: Arc<AtomicBool>,
+
+    overflow_recovery: OverflowRecovery,
+    window: WindowPresence,
+    gestures: GestureRecognizer,
+    layers: InputLayers,
+
+    frame_delta: Duration,
+    recorder: Option<InputRecorder>,
+    record_frame: InputFrame,
+    replay: Option<InputReplay>,
+    replay_frame: Option<InputFrame>,
+    live: Option<LiveInput>,
+}
+
+/// How the [`InputState`] recovers from packets dropped by the input stream,
+/// which happens when more input than the stream can hold is received before
+/// the logic thread polls it, e.g. during a long stall.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OverflowRecovery {
+    /// Keep the input state as is: any key whose release has been dropped
+    /// stays held until it is pressed and released again.
+    Ignore,
+    /// Synthesise the release of every held key and button, as well as of
+    /// the modifiers, right after the last packet that was received.
+    ///
+    /// Whatever is still held on the window is pressed again at the start of
+    /// the next frame, as the dispatcher sends its held input once again.
+    #[default]
+    ReleaseAll,
+}
+
+/// The state of the window, always kept up to date with the live input, even
+/// while replaying.
+#[derive(Clone, Copy, Debug)]
+struct WindowPresence {
+    focused: bool,
+    hovered: bool,
+}
+
+impl Default for WindowPresence {
+    fn default() -> Self {
+        // windows are usually created focused, but the cursor's whereabouts
+        // are unknown until it first enters
+        Self {
+            focused: true,
+            hovered: false,
+        }
+    }
+}
+
+impl WindowPresence {
+    fn change(&mut self, packet: DeltaPacket) {
+        match packet {
+            DeltaPacket::Focus { focused } => self.focused = focused,
+            DeltaPacket::Hover { hovered } => self.hovered = hovered,
+            _ => {}
+        }
+    }
+}
+
+/// The packets a packet received through the input stream stands for, as they
+/// are applied and recorded: the elapsed time as a full timestamp, and a click
+/// as its position followed by the mouse button change.
+fn unpack(
+    packet: StreamPacket,
+    timestamps: &TimestampDecoder,
+    clicks: &mut ClickQueue,
+) -> impl Iterator<Item = DeltaPacket> + use<> {
+    let timestamp = (packet.elapsed != 0).then(|| {
+        let elapsed = packet.elapsed as u32 * ELAPSED_UNIT_MICROS as u32;
+        DeltaPacket::Timestamp {
+            micros: timestamps.stamp().wrapping_add(elapsed) & TIMESTAMP_MASK,
+        }
+    });
+    let (position, packet) = match packet.packet {
+        DeltaPacket::Click { button, down, seq } => (
+            clicks
+                .pop(seq)
+                .map(|(x, y)| DeltaPacket::ClickPosition { x, y }),
+            DeltaPacket::Mouse { button, down },
+        ),
+        packet => (None, packet),
+    };
+    [timestamp, position, Some(packet)].into_iter().flatten()
+}
+
+/// The live input values, kept aside while replaying.
+#[derive(Debug)]
+struct LiveInput {
+    cursor: Arc<Cursor>,
+    touches: Arc<Touches>,
+    mouse_wheel: Arc<sync::TriCell<MouseWheelValue>>,
+    gamepads: Gamepads,
+    /// Still kept up to date while replaying, so that the live timestamps
+    /// can be resumed.
+    timestamps: TimestampDecoder,
+}
+
+impl<const SLOTS: usize, const SECTIONS: usize> InputState<SLOTS, SECTIONS> {
+    pub fn cursor_options(&self) -> &Arc<CursorOptions> {
+        &self.cursor_options
+    }
+
+    pub fn ime_options(&self) -> &Arc<ImeOptions> {
+        &self.ime_options
+    }
+
+    pub fn touch_options(&self) -> &Arc<TouchOptions> {
+        &self.touch_options
+    }
+
+    /// Request the window to allow or disallow IME input.
+    ///
+    /// While allowed, text is received through [`TextEvent::Commit`] and
+    /// [`TextEvent::Preedit`] as composed by the IME.
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        self.ime_options.set_allowed(allowed);
+    }
+
+    /// Set the area of the text being edited, in physical pixels, so that the
+    /// IME candidate window can be positioned next to it.
+    pub fn set_ime_cursor_area(&self, x: f32, y: f32, width: f32, height: f32) {
+        self.ime_options.set_cursor_area(x, y, width, height);
+    }
+
+    /// Request the window to show the system cursor `icon`.
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.cursor_options.set_appearance(icon);
+    }
+
+    /// Request the window to show the custom cursor `image`.
+    pub fn set_custom_cursor(&self, image: CustomCursorImage) {
+        self.cursor_options.set_appearance(image);
+    }
+
+    /// Request the window to show or hide the cursor, independently of it
+    /// being grabbed.
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.cursor_options.set_visible(visible);
+    }
+
+    /// Request the window to grab the cursor as `mode`.
+    ///
+    /// The mode actually applied is known from
+    /// [`grab_mode`](InputState::grab_mode) once the window has handled the
+    /// request, usually by the next frame.
+    pub fn set_grab_mode(&self, mode: GrabMode) {
+        self.cursor_options.set_grab_mode(mode);
+    }
+
+    /// The grab mode applied on the window, see
+    /// [`CursorOptions::applied_grab_mode`].
+    pub fn grab_mode(&self) -> GrabMode {
+        self.cursor_options.applied_grab_mode()
+    }
+
+    /// Whether the last requested grab mode could not be applied, see
+    /// [`CursorOptions::grab_failed`].
+    pub fn grab_failed(&self) -> bool {
+        self.cursor_options.grab_failed()
+    }
+
+    pub fn sync(&mut self) {
+        self.resync_flag.store(true, Ordering::Release);
+        self.snapshot.keys.update();
+        self.snapshot.events.clear();
+        self.stream.frame_back();
+
+        if self.replay.is_some() {
+            self.next_replay_frame();
+        }
+
+        // cursor options handled separately
+    }
+
+    /// Same as [`sync`](InputState::sync), also specifying the delta time of
+    /// the logic frame that is starting.
+    ///
+    /// The delta is only used when [`recording`](InputState::record).
+    pub fn sync_with_delta(&mut self, frame_delta: Duration) {
+        self.frame_delta = frame_delta;
+        self.sync();
+    }
+
+    /// Poll all of the key and mouse button events received this frame.
+    ///
+    /// While [`replaying`](InputState::replay), the events of the replayed
+    /// frame are polled instead and the live events are discarded.
+    ///
+    /// While [`recording`](InputState::record), this is where the frame is
+    /// recorded.
+    pub fn poll_key_events(&mut self) {
+        let recording = self.recorder.is_some();
+        self.record_frame.packets.clear();
+
+        let live = self.stream.drain_back();
+        let replaying = self.replay_frame.is_some();
+        if let Some(frame) = self.replay_frame.take() {
+            for packet in live {
+                self.window.change(packet.packet);
+                let Some(live) = self.live.as_mut() else {
+                    continue;
+                };
+                for packet in unpack(packet, &live.timestamps, &mut self.clicks) {
+                    if let DeltaPacket::Timestamp { micros } = packet {
+                        live.timestamps.decode(micros);
+                    }
+                }
+            }
+            self.text.clear();
+            self.files.clear();
+            for packet in frame.packets {
+                self.snapshot
+                    .press_change(packet, &mut self.text, &mut self.files);
+                if recording {
+                    self.record_frame.packets.push(packet);
+                }
+            }
+        } else {
+            for packet in live {
+                self.window.change(packet.packet);
+                let timestamps = &self.snapshot.timestamps;
+                for packet in unpack(packet, timestamps, &mut self.clicks) {
+                    self.snapshot
+                        .press_change(packet, &mut self.text, &mut self.files);
+
+                    // text, files and the window's state are not part of recordings
+                    let recorded = !matches!(
+                        packet,
+                        DeltaPacket::Text { .. }
+                            | DeltaPacket::File { .. }
+                            | DeltaPacket::Focus { .. }
+                            | DeltaPacket::Hover { .. }
+                    );
+                    if recording && recorded {
+                        self.record_frame.packets.push(packet);
+                    }
+                }
+            }
+        }
+
+        let dropped = self.stream.take_overflow_back();
+        if dropped > 0 {
+            tracing::event!(
+                name: "input.stream.overflow",
+                tracing::Level::WARN,
+                dropped,
+                "Input stream overflowed, {dropped} packets have been dropped"
+            );
+
+            // the replayed state is unaffected by the live stream
+            if !replaying && self.overflow_recovery == OverflowRecovery::ReleaseAll {
+                for packet in self.snapshot.keys.held_releases() {
+                    self.snapshot
+                        .press_change(packet, &mut self.text, &mut self.files);
+                    if recording {
+                        self.record_frame.packets.push(packet);
+                    }
+                }
+            }
+        }
+
+        self.gestures.update(&self.snapshot);
+        self.layers.update(&self.snapshot);
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            let frame = &mut self.record_frame;
+            frame.delta = self.frame_delta;
+            frame.cursor = self.snapshot.cursor.current();
+            frame.cursor_delta = self.snapshot.cursor.delta();
+            frame.mouse_wheel = self.snapshot.mouse_wheel_raw();
+            frame.touches.clear();
+            frame.touches.extend(self.snapshot.touches.iter());
+
+            if let Err(err) = recorder.record(frame) {
+                tracing::event!(
+                    name: "input.record.fail",
+                    tracing::Level::ERROR,
+                    "Failed to record input frame, the recording has been stopped: {err}"
+                );
+                self.recorder = None;
+            }
+        }
+    }
+
+    /// Whether the window has the keyboard focus.
+    ///
+    /// Every held key and mouse button is released once the window loses
+    /// focus.
+    pub fn is_focused(&self) -> bool {
+        self.window.focused
+    }
+
+    /// Whether the cursor is over the window.
+    ///
+    /// Every held mouse button is released once the cursor leaves the window.
+    pub fn is_hovered(&self) -> bool {
+        self.window.hovered
+    }
+
+    /// The mouse gestures recognised in this frame, in the order they have
+    /// been completed.
+    pub fn gestures(&self) -> &[Gesture] {
+        self.gestures.gestures()
+    }
+
+    /// Whether `button` is being dragged, see [`Gesture::DragStart`].
+    pub fn is_dragging(&self, button: MouseButton) -> bool {
+        self.gestures.is_dragging(button)
+    }
+
+    pub fn gesture_recognizer(&self) -> &GestureRecognizer {
+        &self.gestures
+    }
+
+    pub fn set_gesture_config(&mut self, config: GestureConfig) {
+        self.gestures.set_config(config);
+    }
+
+    /// The input layers, updated with every poll, see [`InputLayers`].
+    pub fn layers(&self) -> &InputLayers {
+        &self.layers
+    }
+
+    pub fn layers_mut(&mut self) -> &mut InputLayers {
+        &mut self.layers
+    }
+
+    /// The total amount of packets dropped by the input stream so far.
+    ///
+    /// See [`OverflowRecovery`].
+    pub fn dropped_packets(&self) -> u64 {
+        self.stream.dropped()
+    }
+
+    pub fn overflow_recovery(&self) -> OverflowRecovery {
+        self.overflow_recovery
+    }
+
+    pub fn set_overflow_recovery(&mut self, recovery: OverflowRecovery) {
+        self.overflow_recovery = recovery;
+    }
+
+    /// Start recording every frame with the given `recorder`, replacing any
+    /// previous recording.
+    pub fn record(&mut self, recorder: InputRecorder) {
+        self.recorder = Some(recorder);
     }
 
-    pub fn cursor_options(&self) -> &CursorOptions {
-        &self.cursor_options
+    /// Stop recording, flushing the recorder.
+    ///
+    /// # Returns
+    /// The recorder, if a recording was in progress.
+    pub fn stop_recording(&mut self) -> Option<InputRecorder> {
+        let mut recorder = self.recorder.take()?;
+        if let Err(err) = recorder.flush() {
+            tracing::event!(
+                name: "input.record.flush_fail",
+                tracing::Level::ERROR,
+                "Failed to flush input recording: {err}"
+            );
+        }
+        Some(recorder)
     }
 
-    pub fn cursor_options_shared(&self) -> &Arc<CursorOptions> {
-        &self.cursor_options
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
     }
 
-    pub fn handle_mouse_events(&mut self, event: &winit::event::WindowEvent) {
-        match event {
-            winit::event::WindowEvent::CursorMoved { position, .. } => {
-                self.cursor.current.set((position.x, position.y));
-            }
-            winit::event::WindowEvent::MouseWheel {
-                delta: MouseScrollDelta::LineDelta(_, delta),
-                ..
-            } => {
-                self.mouse_wheel.set_with(|d| d + delta);
-            }
-            _ => {}
+    /// Feed the input from `replay` instead of the live input, starting from
+    /// the next [`sync`](InputState::sync).
+    ///
+    /// Once all of its frames have been replayed, the live input is resumed.
+    pub fn replay(&mut self, replay: InputReplay) {
+        if self.live.is_none() {
+            let snapshot = &mut self.snapshot;
+            let replay_timestamps = snapshot.timestamps.restarted();
+            self.live = Some(LiveInput {
+                cursor: std::mem::take(&mut snapshot.cursor),
+                touches: std::mem::take(&mut snapshot.touches),
+                mouse_wheel: std::mem::take(&mut snapshot.mouse_wheel),
+                gamepads: std::mem::take(&mut snapshot.gamepads),
+                timestamps: std::mem::replace(&mut snapshot.timestamps, replay_timestamps),
+            });
+            snapshot.keys = Keys::new();
         }
+        self.replay = Some(replay);
     }
 
-    pub fn handle_raw_cursor_events(&mut self, event: &winit::event::DeviceEvent) {
-        match event {
-            winit::event::DeviceEvent::MouseMotion { delta: (dx, dy) } => {
-                self.cursor
-                    .delta
-                    .set_with(|(odx, ody)| (odx + dx, ody + dy));
-            }
-            _ => {}
+    /// Stop replaying and resume the live input.
+    ///
+    /// # Returns
+    /// The frames left to replay, if a replay was in progress.
+    pub fn stop_replay(&mut self) -> Option<InputReplay> {
+        if let Some(live) = self.live.take() {
+            self.snapshot.cursor = live.cursor;
+            self.snapshot.touches = live.touches;
+            self.snapshot.mouse_wheel = live.mouse_wheel;
+            self.snapshot.gamepads = live.gamepads;
+            self.snapshot.timestamps = live.timestamps;
+            self.snapshot.keys = Keys::new();
         }
+        self.replay_frame = None;
+        self.replay.take()
     }
 
-    pub fn handle_key_event(&mut self, event: &winit::event::WindowEvent) {
-        use winit::event::{ElementState, WindowEvent};
-        use winit::keyboard::PhysicalKey;
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
 
-        match event {
-            WindowEvent::KeyboardInput { event: key, .. } => {
-                if let PhysicalKey::Code(code) = key.physical_key {
-                    let code: KeyboardKeyCode = code.into();
-                    let down = matches!(key.state, ElementState::Pressed);
-                    self.stream.push_front(DeltaPacket::Keyboard { code, down });
-                }
-            }
-            WindowEvent::MouseInput { state, button, .. } => {
-                let button: MouseButtonIndex = (*button).into();
-                let down = matches!(*state, ElementState::Pressed);
-                self.stream.push_front(DeltaPacket::Mouse { button, down });
-            }
-            _ => {}
-        }
+    /// The delta time of the current frame, either as given to
+    /// [`sync_with_delta`](InputState::sync_with_delta) or as recorded in the
+    /// frame being replayed.
+    pub fn frame_delta(&self) -> Duration {
+        self.frame_delta
     }
-}
 
-// todo: change to single AtomicU8
-#[derive(Debug, Default)]
-pub struct CursorOptions {
-    pub grabbed: AtomicBool,
-    pub dirty: AtomicBool,
-}
+    fn next_replay_frame(&mut self) {
+        let Some(frame) = self.replay.as_mut().and_then(InputReplay::next_frame) else {
+            self.stop_replay();
+            return;
+        };
+
+        // the replayed values are owned solely by this state: advance them
+        // right away so they are visible in this frame.
+        let cursor = &self.snapshot.cursor;
+        cursor.current.set(frame.cursor);
+        cursor.delta.set(frame.cursor_delta);
+        let _ = cursor.current.advance();
+        let _ = cursor.delta.advance();
+        self.snapshot.mouse_wheel.set(frame.mouse_wheel);
+        let _ = self.snapshot.mouse_wheel.advance();
+        let mut points = TouchPoints::default();
+        for (slot, point) in points.iter_mut().zip(&frame.touches) {
+            *slot = Some(*point);
+        }
+        self.snapshot.touches.points.set(points);
+        let _ = self.snapshot.touches.points.advance();
 
-impl CursorOptions {
-    pub fn check_grabbed(&self) -> bool {
-        self.grabbed.load(Ordering::Relaxed)
+        self.frame_delta = frame.delta;
+        self.replay_frame = Some(frame);
     }
 
-    pub fn check_dirty(&self) -> bool {
-        self.grabbed.load(Ordering::Relaxed)
+    /// See [`InputSnapshot::mouse_wheel`].
+    pub fn mouse_wheel(&self) -> (f32, f32) {
+        self.snapshot.mouse_wheel()
     }
 
-    pub fn set_grabbed(&self, grabbed: bool) {
-        let changed = self
-            .grabbed
-            .compare_exchange(!grabbed, grabbed, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok();
+    /// See [`InputSnapshot::mouse_wheel_pixels`].
+    pub fn mouse_wheel_pixels(&self) -> (f32, f32) {
+        self.snapshot.mouse_wheel_pixels()
+    }
 
-        if changed {
-            self.dirty.store(true, Ordering::Relaxed);
-        }
+    pub fn set_pixels_per_line(&mut self, pixels_per_line: f32) {
+        self.snapshot.set_pixels_per_line(pixels_per_line);
     }
-}
 
-/// Read-only view of the current state of the input as received from the
-/// window thread.
-#[derive(Debug, Default)]
-pub struct InputState<const SLOTS: usize, const SECTIONS: usize> {
-    snapshot: InputSnapshot,
-    cursor_options: Arc<CursorOptions>,
-    stream: Arc<InputStream<SLOTS, SECTIONS>>,
-    resync_flag: Arc<AtomicBool>,
-}
+    /// The scroll events received this frame, in order.
+    pub fn scroll_events(&self) -> impl Iterator<Item = ScrollEvent> + '_ {
+        self.events().iter().filter_map(|timed| match timed.event {
+            InputEvent::Scroll(scroll) => Some(scroll),
+            _ => None,
+        })
+    }
 
-impl<const SLOTS: usize, const SECTIONS: usize> InputState<SLOTS, SECTIONS> {
-    pub fn cursor_options(&self) -> &Arc<CursorOptions> {
-        &self.cursor_options
+    /// Pop the oldest key or mouse button event not popped yet.
+    pub fn pop_key_event(&mut self) -> Option<KeyEvent> {
+        self.snapshot.keys.pop_key_event()
     }
 
-    pub fn sync(&mut self) {
-        self.resync_flag.store(true, Ordering::Release);
-        self.snapshot.keys.update();
-        self.stream.frame_back();
+    /// All of the input events polled this frame, in the order they were
+    /// received, each stamped with the time it has been received at.
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.snapshot.events
+    }
 
-        // cursor options handled separately
+    /// The text events polled this frame, in order.
+    pub fn text_events(&self) -> impl Iterator<Item = &TextEvent> {
+        self.snapshot
+            .events
+            .iter()
+            .filter_map(|timed| match &timed.event {
+                InputEvent::Text(text) => Some(text),
+                _ => None,
+            })
     }
 
-    pub fn poll_key_events(&mut self) {
-        self.stream
-            .drain_back()
-            .for_each(|ev| self.snapshot.keys.press_change(ev));
+    /// The text typed or committed through the IME this frame, in order.
+    pub fn committed_text(&self) -> impl Iterator<Item = &str> {
+        self.text_events().filter_map(TextEvent::committed)
     }
 
-    pub fn mouse_wheel(&self) -> MouseWheelValue {
-        self.snapshot.mouse_wheel()
+    /// The files dragged over or dropped onto the window this frame, in
+    /// order.
+    pub fn file_events(&self) -> impl Iterator<Item = &FileEvent> {
+        self.snapshot
+            .events
+            .iter()
+            .filter_map(|timed| match &timed.event {
+                InputEvent::File(file) => Some(file),
+                _ => None,
+            })
     }
 
-    pub fn pop_key_event(&mut self) -> Option<KeyEvent> {
-        self.snapshot.keys.pop_key_event()
+    /// The files dropped onto the window this frame, along with the cursor
+    /// position they have been dropped at.
+    pub fn dropped_files(&self) -> impl Iterator<Item = (&Path, (f64, f64))> {
+        self.file_events().filter_map(|event| match event {
+            FileEvent::Dropped { path, position } => Some((path.as_path(), *position)),
+            _ => None,
+        })
     }
 
     pub fn keys(&self) -> &Keys {
@@ -220,19 +1583,122 @@ impl<const SLOTS: usize, const SECTIONS: usize> InputState<SLOTS, SECTIONS> {
         &self.snapshot.cursor
     }
 
+    pub fn touches(&self) -> &Touches {
+        &self.snapshot.touches
+    }
+
+    pub fn gamepads(&self) -> &Gamepads {
+        &self.snapshot.gamepads
+    }
+
+    /// Mutable access to the gamepads, e.g. to change their
+    /// [`deadzones`](Gamepads::set_deadzones).
+    pub fn gamepads_mut(&mut self) -> &mut Gamepads {
+        &mut self.snapshot.gamepads
+    }
+
     pub fn snapshot(&self) -> &InputSnapshot {
         &self.snapshot
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct InputSnapshot {
     keys: Keys,
+    events: Vec<TimedEvent>,
     cursor: Arc<Cursor>,
+    touches: Arc<Touches>,
     mouse_wheel: Arc<sync::TriCell<MouseWheelValue>>,
+    pixels_per_line: f32,
+    gamepads: Gamepads,
+    timestamps: TimestampDecoder,
+    click_position: Option<CursorValues>,
+}
+
+impl Default for InputSnapshot {
+    fn default() -> Self {
+        Self {
+            keys: Keys::default(),
+            events: Vec::new(),
+            cursor: Arc::default(),
+            touches: Arc::default(),
+            mouse_wheel: Arc::default(),
+            pixels_per_line: DEFAULT_PIXELS_PER_LINE,
+            gamepads: Gamepads::default(),
+            timestamps: TimestampDecoder::default(),
+            click_position: None,
+        }
+    }
 }
 
 impl InputSnapshot {
+    fn press_change(&mut self, packet: DeltaPacket, text: &mut TextQueue, files: &mut FileQueue) {
+        let event = match packet {
+            DeltaPacket::Timestamp { micros } => {
+                self.timestamps.decode(micros);
+                return;
+            }
+            DeltaPacket::ClickPosition { x, y } => {
+                self.click_position = Some((x as f64, y as f64));
+                return;
+            }
+            DeltaPacket::Text { seq } => match text.pop(seq) {
+                Some(event) => InputEvent::Text(event),
+                None => return,
+            },
+            DeltaPacket::File { seq } => match files.pop(seq) {
+                Some(event) => InputEvent::File(event),
+                None => return,
+            },
+            DeltaPacket::GamepadConnection { pad, connected } => {
+                self.keys.press_change(packet);
+                self.gamepads.set_connected(pad, connected);
+                if connected {
+                    InputEvent::GamepadConnected(pad)
+                } else {
+                    InputEvent::GamepadDisconnected(pad)
+                }
+            }
+            DeltaPacket::Scroll {
+                unit,
+                horizontal,
+                delta,
+            } => {
+                let delta = unit.decode(delta);
+                let delta = if horizontal {
+                    (delta, 0.0)
+                } else {
+                    (0.0, delta)
+                };
+                InputEvent::Scroll(ScrollEvent { unit, delta })
+            }
+            DeltaPacket::Mouse { .. } => {
+                let position = self.click_position.take();
+                match self.keys.press_change(packet) {
+                    Some(event) => InputEvent::MouseButton {
+                        event,
+                        position: position.unwrap_or_else(|| self.cursor.current()),
+                    },
+                    None => return,
+                }
+            }
+            _ => match self.keys.press_change(packet) {
+                Some(event) => InputEvent::Key(event),
+                None => return,
+            },
+        };
+
+        self.events.push(TimedEvent {
+            timestamp: self.timestamps.current(),
+            event,
+        });
+    }
+
+    /// See [`InputState::events`].
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.events
+    }
+
     pub fn keys(&self) -> &Keys {
         &self.keys
     }
@@ -241,10 +1707,45 @@ impl InputSnapshot {
         &self.cursor
     }
 
-    pub fn mouse_wheel(&self) -> MouseWheelValue {
+    pub fn touches(&self) -> &Touches {
+        &self.touches
+    }
+
+    /// The scroll delta of this frame in lines, converting pixel deltas with
+    /// the [`pixels_per_line`](InputSnapshot::pixels_per_line).
+    pub fn mouse_wheel(&self) -> (f32, f32) {
+        self.mouse_wheel.get().to_lines(self.pixels_per_line)
+    }
+
+    /// The scroll delta of this frame in pixels, converting line deltas with
+    /// the [`pixels_per_line`](InputSnapshot::pixels_per_line).
+    pub fn mouse_wheel_pixels(&self) -> (f32, f32) {
+        self.mouse_wheel.get().to_pixels(self.pixels_per_line)
+    }
+
+    /// The scroll deltas of this frame, in the unit they have been received.
+    pub fn mouse_wheel_raw(&self) -> MouseWheel {
         self.mouse_wheel.get()
     }
 
+    pub fn pixels_per_line(&self) -> f32 {
+        self.pixels_per_line
+    }
+
+    /// Set the conversion between line and pixel scroll deltas.
+    /// Defaults to [`DEFAULT_PIXELS_PER_LINE`].
+    pub fn set_pixels_per_line(&mut self, pixels_per_line: f32) {
+        self.pixels_per_line = pixels_per_line;
+    }
+
+    pub fn gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
+    pub fn gamepads_mut(&mut self) -> &mut Gamepads {
+        &mut self.gamepads
+    }
+
     pub fn cursor_shared(&self) -> &Arc<Cursor> {
         &self.cursor
     }
@@ -258,6 +1759,48 @@ impl InputSnapshot {
     }
 }
 
+/// An [`InputEvent`] along with the time it has been received at by the
+/// [`InputDispatcher`].
+///
+/// Timestamps are measured with the dispatcher's
+/// [`clock`](InputDispatcher::set_clock). Events received within 25.5ms of the
+/// previous one are stamped with the time elapsed since, rounded down to
+/// 100µs; any other event is stamped to the microsecond.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimedEvent {
+    pub timestamp: Instant,
+    pub event: InputEvent,
+}
+
+/// An input event received in a frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    /// A keyboard key or gamepad button event.
+    Key(KeyEvent),
+    /// A mouse button event, along with the cursor position at the time, in
+    /// whole physical pixels.
+    MouseButton {
+        event: KeyEvent,
+        position: CursorValues,
+    },
+    Text(TextEvent),
+    /// A file dragged over or dropped onto the window.
+    File(FileEvent),
+    Scroll(ScrollEvent),
+    GamepadConnected(GamepadId),
+    GamepadDisconnected(GamepadId),
+}
+
+impl InputEvent {
+    /// The key, mouse button or gamepad button event, if any.
+    pub fn key_event(&self) -> Option<KeyEvent> {
+        match self {
+            InputEvent::Key(event) | InputEvent::MouseButton { event, .. } => Some(*event),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum KeyEvent {
     Mouse {
@@ -270,6 +1813,12 @@ pub enum KeyEvent {
         release: bool,
         press_time: u32,
     },
+    Gamepad {
+        pad: GamepadId,
+        code: u16,
+        release: bool,
+        press_time: u32,
+    },
 }
 impl KeyEvent {
     pub const fn is_mouse(self) -> bool {
@@ -280,10 +1829,15 @@ impl KeyEvent {
         matches!(self, KeyEvent::Keyboard { .. })
     }
 
+    pub const fn is_gamepad(self) -> bool {
+        matches!(self, KeyEvent::Gamepad { .. })
+    }
+
     pub const fn code(self) -> u16 {
         match self {
             KeyEvent::Mouse { code, .. } => code,
             KeyEvent::Keyboard { code, .. } => code,
+            KeyEvent::Gamepad { code, .. } => code,
         }
     }
 
@@ -291,6 +1845,7 @@ impl KeyEvent {
         match self {
             KeyEvent::Mouse { release, .. } => release,
             KeyEvent::Keyboard { release, .. } => release,
+            KeyEvent::Gamepad { release, .. } => release,
         }
     }
 }
@@ -301,6 +1856,11 @@ pub struct Keys {
     keyboard: [u16; KEYBOARD_ENTRIES],
     /// index is represented by button id, value is held frames
     mouse: [u16; MOUSE_ENTRIES],
+    /// index is represented by logical key code, value is held frames
+    logical: [u16; LOGICAL_ENTRIES],
+    /// index is represented by gamepad id and button, value is held frames
+    gamepad: [[u16; GAMEPAD_BUTTONS]; MAX_GAMEPADS],
+    modifiers: Modifiers,
     local_key_queue: VecDeque<KeyEvent>,
 }
 impl Default for Keys {
@@ -313,19 +1873,15 @@ impl Keys {
         Self {
             keyboard: [0u16; KEYBOARD_ENTRIES],
             mouse: [0u16; MOUSE_ENTRIES],
+            logical: [0u16; LOGICAL_ENTRIES],
+            gamepad: [[0u16; GAMEPAD_BUTTONS]; MAX_GAMEPADS],
+            modifiers: Modifiers::empty(),
             local_key_queue: VecDeque::new(),
         }
     }
 
     pub fn update(&mut self) {
-        for state in self.keyboard.iter_mut() {
-            match *state {
-                RELEASE_SIGNAL => *state = 0,
-                1..=MAX_HOLD_FRAMES => *state += 1,
-                _ => {}
-            }
-        }
-        for state in self.mouse.iter_mut() {
+        for state in self.frames_mut() {
             match *state {
                 RELEASE_SIGNAL => *state = 0,
                 1..=MAX_HOLD_FRAMES => *state += 1,
@@ -334,8 +1890,25 @@ impl Keys {
         }
     }
 
-    pub fn press_change(&mut self, delta: DeltaPacket) {
-        match delta {
+    /// The held frames of every keyboard key, logical key, mouse button and
+    /// gamepad button, in this order.
+    pub(crate) fn frames_mut(&mut self) -> impl Iterator<Item = &mut u16> {
+        self.keyboard
+            .iter_mut()
+            .chain(self.logical.iter_mut())
+            .chain(self.mouse.iter_mut())
+            .chain(self.gamepad.iter_mut().flatten())
+    }
+
+    /// Apply a key, mouse button or modifiers state change.
+    ///
+    /// Logical keys and modifiers only update their state and never queue a
+    /// [`KeyEvent`], as they are already reported through the physical keys.
+    ///
+    /// # Returns
+    /// The [`KeyEvent`] queued as a result of the change, if any.
+    pub fn press_change(&mut self, delta: DeltaPacket) -> Option<KeyEvent> {
+        let event = match delta {
             DeltaPacket::Keyboard { code, down } => {
                 let code = code.0;
                 let index = u16::from(code) as usize;
@@ -343,20 +1916,20 @@ impl Keys {
                     if self.keyboard[index] == 0 {
                         self.keyboard[index] = 1;
                     }
-                    self.local_key_queue.push_back(KeyEvent::Keyboard {
+                    KeyEvent::Keyboard {
                         code,
                         release: false,
                         press_time: self.keyboard[index] as u32,
-                    });
-                } else {
-                    if self.keyboard[index] > 0 && self.keyboard[index] != RELEASE_SIGNAL {
-                        self.keyboard[index] = RELEASE_SIGNAL;
-                        self.local_key_queue.push_back(KeyEvent::Keyboard {
-                            code: code,
-                            release: true,
-                            press_time: self.keyboard[index] as u32,
-                        });
                     }
+                } else if self.keyboard[index] > 0 && self.keyboard[index] != RELEASE_SIGNAL {
+                    self.keyboard[index] = RELEASE_SIGNAL;
+                    KeyEvent::Keyboard {
+                        code,
+                        release: true,
+                        press_time: self.keyboard[index] as u32,
+                    }
+                } else {
+                    return None;
                 }
             }
             DeltaPacket::Mouse { button, down } => {
@@ -366,27 +1939,138 @@ impl Keys {
                     if self.mouse[index] == 0 {
                         self.mouse[index] = 1;
                     }
-                    self.local_key_queue.push_back(KeyEvent::Mouse {
-                        code: code,
+                    KeyEvent::Mouse {
+                        code,
                         release: false,
                         press_time: self.mouse[index] as u32,
-                    });
+                    }
+                } else if self.mouse[index] > 0 && self.mouse[index] != RELEASE_SIGNAL {
+                    self.mouse[index] = RELEASE_SIGNAL;
+                    KeyEvent::Mouse {
+                        code,
+                        release: true,
+                        press_time: self.mouse[index] as u32,
+                    }
                 } else {
-                    if self.mouse[index] > 0 && self.mouse[index] != RELEASE_SIGNAL {
-                        self.mouse[index] = RELEASE_SIGNAL;
-                        self.local_key_queue.push_back(KeyEvent::Mouse {
-                            code: code,
-                            release: true,
-                            press_time: self.mouse[index] as u32,
-                        });
+                    return None;
+                }
+            }
+            DeltaPacket::Logical { key, down } => {
+                let index = u16::from(key) as usize;
+                if down {
+                    if self.logical[index] == 0 {
+                        self.logical[index] = 1;
                     }
+                } else if self.logical[index] > 0 && self.logical[index] != RELEASE_SIGNAL {
+                    self.logical[index] = RELEASE_SIGNAL;
                 }
+                return None;
             }
-        }
+            DeltaPacket::Modifiers { state } => {
+                self.modifiers = state;
+                return None;
+            }
+            DeltaPacket::Gamepad { pad, button, down } => {
+                let code = button as u16;
+                let state = &mut self.gamepad[pad.index()][button.index()];
+                if down {
+                    if *state == 0 {
+                        *state = 1;
+                    }
+                    KeyEvent::Gamepad {
+                        pad,
+                        code,
+                        release: false,
+                        press_time: *state as u32,
+                    }
+                } else if *state > 0 && *state != RELEASE_SIGNAL {
+                    *state = RELEASE_SIGNAL;
+                    KeyEvent::Gamepad {
+                        pad,
+                        code,
+                        release: true,
+                        press_time: *state as u32,
+                    }
+                } else {
+                    return None;
+                }
+            }
+            DeltaPacket::GamepadConnection { pad, connected } => {
+                // a disconnected gamepad's buttons will never be released
+                if !connected {
+                    for state in self.gamepad[pad.index()].iter_mut() {
+                        if *state != 0 {
+                            *state = RELEASE_SIGNAL;
+                        }
+                    }
+                }
+                return None;
+            }
+            DeltaPacket::Text { .. }
+            | DeltaPacket::Scroll { .. }
+            | DeltaPacket::Timestamp { .. }
+            | DeltaPacket::ClickPosition { .. }
+            | DeltaPacket::Click { .. }
+            | DeltaPacket::Focus { .. }
+            | DeltaPacket::Hover { .. }
+            | DeltaPacket::File { .. } => return None,
+        };
+
+        self.local_key_queue.push_back(event);
+        Some(event)
     }
 
     pub fn pop_key_event(&mut self) -> Option<KeyEvent> {
-        self.local_key_queue.pop_back()
+        self.local_key_queue.pop_front()
+    }
+
+    /// The packets releasing every key and button currently down, along with
+    /// the modifiers.
+    pub(crate) fn held_releases(&self) -> Vec<DeltaPacket> {
+        let down = |frames: u16| frames != 0 && frames != RELEASE_SIGNAL;
+        let indices = |frames: &[u16]| {
+            frames
+                .iter()
+                .enumerate()
+                .filter(|&(_, &frames)| down(frames))
+                .map(|(i, _)| i as u16)
+                .collect::<Vec<_>>()
+        };
+
+        let mut packets = Vec::new();
+        for i in indices(&self.keyboard) {
+            packets.push(DeltaPacket::Keyboard {
+                code: KeyboardKeyCode(i),
+                down: false,
+            });
+        }
+        for i in indices(&self.logical) {
+            packets.push(DeltaPacket::Logical {
+                key: LogicalKeyCode(i),
+                down: false,
+            });
+        }
+        for i in indices(&self.mouse) {
+            packets.push(DeltaPacket::Mouse {
+                button: MouseButtonIndex(i),
+                down: false,
+            });
+        }
+        for (pad, buttons) in self.gamepad.iter().enumerate() {
+            for i in indices(buttons) {
+                packets.push(DeltaPacket::Gamepad {
+                    pad: GamepadId(pad as u8),
+                    button: GamepadButton::ALL[i as usize],
+                    down: false,
+                });
+            }
+        }
+        if !self.modifiers.is_empty() {
+            packets.push(DeltaPacket::Modifiers {
+                state: Modifiers::empty(),
+            });
+        }
+        packets
     }
 
     #[inline(always)]
@@ -415,6 +2099,110 @@ impl Keys {
         self.key_frames(code) > 1
     }
 
+    #[inline(always)]
+    pub fn gamepad_frames(&self, pad: GamepadId, button: GamepadButton) -> u16 {
+        self.gamepad[pad.index()][button.index()]
+    }
+
+    #[inline(always)]
+    pub fn gamepad_down(&self, pad: GamepadId, button: GamepadButton) -> bool {
+        let frames = self.gamepad_frames(pad, button);
+        frames != 0 && frames != RELEASE_SIGNAL
+    }
+
+    #[inline(always)]
+    pub fn gamepad_pressed(&self, pad: GamepadId, button: GamepadButton) -> bool {
+        self.gamepad_frames(pad, button) == 1
+    }
+
+    #[inline(always)]
+    pub fn gamepad_released(&self, pad: GamepadId, button: GamepadButton) -> bool {
+        self.gamepad_frames(pad, button) == RELEASE_SIGNAL
+    }
+
+    #[inline(always)]
+    pub fn gamepad_held(&self, pad: GamepadId, button: GamepadButton) -> bool {
+        let frames = self.gamepad_frames(pad, button);
+        frames > 1 && frames != RELEASE_SIGNAL
+    }
+
+    /// Whether `button` is down on any gamepad.
+    pub fn gamepad_down_any(&self, button: GamepadButton) -> bool {
+        (0..MAX_GAMEPADS as u8).any(|pad| self.gamepad_down(GamepadId(pad), button))
+    }
+
+    /// The held frames of `button` on the gamepad it has been down the longest
+    /// on, or [`RELEASE_SIGNAL`] if it is only released on any of them.
+    pub fn gamepad_frames_any(&self, button: GamepadButton) -> u16 {
+        let frames = self.gamepad.iter().map(|pad| pad[button.index()]);
+        frames
+            .clone()
+            .filter(|&frames| frames != RELEASE_SIGNAL)
+            .max()
+            .filter(|&frames| frames != 0)
+            .or_else(|| frames.clone().find(|&frames| frames == RELEASE_SIGNAL))
+            .unwrap_or(0)
+    }
+
+    /// The modifier keys held as of the last key event of this frame.
+    #[inline(always)]
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Whether all of `modifiers` are held; other modifiers may be held as
+    /// well.
+    ///
+    /// This is the rule shortcuts and [`Binding`] chords are
+    /// matched with.
+    #[inline(always)]
+    pub fn modifiers_held(&self, modifiers: Modifiers) -> bool {
+        self.modifiers.contains(modifiers)
+    }
+
+    #[inline(always)]
+    pub fn logical_frames(&self, key: impl Into<LogicalKeyCode>) -> u16 {
+        let key: LogicalKeyCode = key.into();
+        self.logical[u16::from(key) as usize]
+    }
+
+    /// Same as [`key_down`](Keys::key_down), but for the key as interpreted by
+    /// the keyboard layout. See [`LogicalKeyCode`].
+    #[inline(always)]
+    pub fn logical_down(&self, key: impl Into<LogicalKeyCode>) -> bool {
+        let frames = self.logical_frames(key);
+        frames != 0 && frames != RELEASE_SIGNAL
+    }
+
+    /// Same as [`key_pressed`](Keys::key_pressed), but for the key as
+    /// interpreted by the keyboard layout. See [`LogicalKeyCode`].
+    #[inline(always)]
+    pub fn logical_pressed(&self, key: impl Into<LogicalKeyCode>) -> bool {
+        self.logical_frames(key) == 1
+    }
+
+    #[inline(always)]
+    pub fn logical_released(&self, key: impl Into<LogicalKeyCode>) -> bool {
+        self.logical_frames(key) == RELEASE_SIGNAL
+    }
+
+    #[inline(always)]
+    pub fn logical_held(&self, key: impl Into<LogicalKeyCode>) -> bool {
+        let frames = self.logical_frames(key);
+        frames > 1 && frames != RELEASE_SIGNAL
+    }
+
+    /// Whether `key` has been pressed this frame while `modifiers` were
+    /// [held](Keys::modifiers_held), e.g. `shortcut_pressed(Modifiers::CONTROL,
+    /// 'z')` for undo regardless of the keyboard layout.
+    ///
+    /// As other modifiers may be held as well, check the shortcuts with the
+    /// most modifiers first, e.g. Ctrl+Shift+Z for redo before Ctrl+Z.
+    #[inline(always)]
+    pub fn shortcut_pressed(&self, modifiers: Modifiers, key: impl Into<LogicalKeyCode>) -> bool {
+        self.modifiers_held(modifiers) && self.logical_pressed(key)
+    }
+
     #[inline(always)]
     fn mouse_code(&self, code: impl Into<MouseButtonIndex>) -> u16 {
         let button_index: MouseButtonIndex = code.into();
@@ -422,7 +2210,7 @@ impl Keys {
     }
 
     #[inline(always)]
-    fn mouse_frames(&self, code: winit::event::MouseButton) -> u16 {
+    pub fn mouse_frames(&self, code: winit::event::MouseButton) -> u16 {
         let code = self.mouse_code(code);
         self.mouse[code as usize]
     }
@@ -510,6 +2298,25 @@ impl From<winit::event::MouseButton> for MouseButtonIndex {
     }
 }
 
+impl From<MouseButtonIndex> for winit::event::MouseButton {
+    fn from(value: MouseButtonIndex) -> Self {
+        match value.0 {
+            MouseButtonIndex::LEFT => Self::Left,
+            MouseButtonIndex::RIGHT => Self::Right,
+            MouseButtonIndex::MIDDLE => Self::Middle,
+            MouseButtonIndex::FORWARD => Self::Back,
+            MouseButtonIndex::BACK => Self::Forward,
+            id => Self::Other(id - MouseButtonIndex::OTHER_OFFSET),
+        }
+    }
+}
+
+type ClickValues = (i16, i16);
+
+/// Side channel for the cursor positions of mouse button events, placed in
+/// order by their [`DeltaPacket::Click`] markers.
+type ClickQueue = SideQueue<ClickValues>;
+
 #[derive(Debug, Default)]
 pub struct Cursor {
     current: sync::TriCell<CursorValues>,
@@ -523,61 +2330,546 @@ impl Cursor {
 
     #[inline(always)]
     pub fn current(&self) -> (f64, f64) {
-        *self.current
+        self.current.get()
     }
 
     #[inline(always)]
     pub fn current_f32(&self) -> (f32, f32) {
-        (self.current.0 as f32, self.current.1 as f32)
+        let (x, y) = self.current();
+        (x as f32, y as f32)
     }
 
     #[inline(always)]
     pub fn delta(&self) -> (f64, f64) {
-        *self.delta
+        self.delta.get()
     }
 
     #[inline(always)]
     pub fn delta_f32(&self) -> (f32, f32) {
-        (self.delta.0 as f32, self.delta.1 as f32)
+        let (dx, dy) = self.delta();
+        (dx as f32, dy as f32)
     }
 
     #[inline(always)]
     pub fn x(&self) -> f64 {
-        self.current.0
+        self.current().0
     }
 
     #[inline(always)]
     pub fn y(&self) -> f64 {
-        self.current.1
+        self.current().1
     }
 
     #[inline(always)]
     pub fn dx(&self) -> f64 {
-        self.delta.0
+        self.delta().0
     }
 
     #[inline(always)]
     pub fn dy(&self) -> f64 {
-        self.delta.1
+        self.delta().1
     }
 
     #[inline(always)]
     pub fn x_f32(&self) -> f32 {
-        self.current.0 as f32
+        self.current().0 as f32
     }
 
     #[inline(always)]
     pub fn y_f32(&self) -> f32 {
-        self.current.1 as f32
+        self.current().1 as f32
     }
 
     #[inline(always)]
     pub fn dx_f32(&self) -> f32 {
-        self.delta.0 as f32
+        self.delta().0 as f32
     }
 
     #[inline(always)]
     pub fn dy_f32(&self) -> f32 {
-        self.delta.1 as f32
+        self.delta().1 as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ManualClock;
+
+    #[test]
+    fn text_in_order_with_keys() {
+        let mut input = InputHarness::new();
+
+        let dispatcher = input.dispatcher();
+        dispatcher.press_key(KeyCode::KeyA);
+        dispatcher.push_text(TextEvent::Commit("a".to_string()));
+        dispatcher.push_text(TextEvent::Preedit {
+            text: "にほ".to_string(),
+            cursor: Some((6, 6)),
+        });
+        dispatcher.release_key(KeyCode::KeyA);
+
+        let state = input.step();
+        let events = state.events();
+        assert_eq!(events.len(), 4);
+        assert!(matches!(
+            events[0].event,
+            InputEvent::Key(KeyEvent::Keyboard { release: false, .. })
+        ));
+        assert_eq!(
+            events[1].event,
+            InputEvent::Text(TextEvent::Commit("a".to_string()))
+        );
+        assert!(matches!(
+            events[2].event,
+            InputEvent::Text(TextEvent::Preedit { .. })
+        ));
+        assert!(matches!(
+            events[3].event,
+            InputEvent::Key(KeyEvent::Keyboard { release: true, .. })
+        ));
+        assert_eq!(state.committed_text().collect::<String>(), "a");
+    }
+
+    #[test]
+    fn logical_keys_and_modifiers() {
+        let mut input = InputHarness::new();
+
+        // ctrl+z on an AZERTY layout, where the key labeled `Z` is `KeyW`
+        input.dispatcher().push_modifiers(Modifiers::CONTROL);
+        input.dispatcher().push_key(
+            Some(KeyCode::KeyW.into()),
+            LogicalKeyCode::from_key(&winit::keyboard::Key::Character("z".into())),
+            true,
+        );
+
+        let state = input.step();
+        let keys = state.keys();
+        assert_eq!(keys.modifiers(), Modifiers::CONTROL);
+        assert!(keys.key_pressed(KeyCode::KeyW));
+        assert!(keys.logical_pressed('z'));
+        assert!(keys.logical_pressed('Z'));
+        assert!(!keys.logical_down('w'));
+        assert!(keys.shortcut_pressed(Modifiers::CONTROL, 'z'));
+        assert!(!keys.shortcut_pressed(Modifiers::CONTROL | Modifiers::SHIFT, 'z'));
+        assert_eq!(state.events().len(), 1);
+
+        // the release is sent for the logical key it was pressed as
+        input.dispatcher().push_modifiers(Modifiers::empty());
+        input.dispatcher().release_key(KeyCode::KeyW);
+
+        let keys = input.step().keys();
+        assert!(keys.modifiers().is_empty());
+        assert!(keys.key_released(KeyCode::KeyW));
+        assert!(keys.logical_released('z'));
+    }
+
+    #[test]
+    fn scroll_lines_and_pixels() {
+        let mut input = InputHarness::new();
+
+        input
+            .dispatcher()
+            .push_scroll(ScrollUnit::Lines, (0.0, 1.5));
+        input
+            .dispatcher()
+            .push_scroll(ScrollUnit::Pixels, (30.0, -10.0));
+
+        let state = input.step();
+        assert_eq!(state.mouse_wheel(), (1.5, 1.0));
+        state.set_pixels_per_line(10.0);
+        assert_eq!(state.mouse_wheel(), (3.0, 0.5));
+        assert_eq!(state.mouse_wheel_pixels(), (30.0, 5.0));
+
+        let events: Vec<_> = state.scroll_events().collect();
+        assert_eq!(
+            events,
+            [
+                ScrollEvent {
+                    unit: ScrollUnit::Lines,
+                    delta: (0.0, 1.5)
+                },
+                ScrollEvent {
+                    unit: ScrollUnit::Pixels,
+                    delta: (30.0, 0.0)
+                },
+                ScrollEvent {
+                    unit: ScrollUnit::Pixels,
+                    delta: (0.0, -10.0)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn large_scrolls_are_split() {
+        let mut input = InputHarness::new();
+
+        input
+            .dispatcher()
+            .push_scroll(ScrollUnit::Lines, (0.0, -300.0));
+
+        let state = input.step();
+        assert_eq!(state.mouse_wheel(), (0.0, -300.0));
+        let deltas: Vec<_> = state.scroll_events().map(|event| event.delta).collect();
+        assert_eq!(deltas.len(), 3);
+        assert_eq!(deltas.iter().map(|delta| delta.1).sum::<f32>(), -300.0);
+
+        // clamped alike in the events and the accumulated wheel
+        let max = ScrollUnit::Pixels.max_delta();
+        input
+            .dispatcher()
+            .push_scroll(ScrollUnit::Pixels, (1e6, f32::NAN));
+
+        let state = input.step();
+        assert_eq!(state.mouse_wheel_pixels(), (max, 0.0));
+        let deltas: Vec<_> = state.scroll_events().map(|event| event.delta).collect();
+        assert_eq!(deltas.len(), 16);
+        assert_eq!(deltas.iter().map(|delta| delta.0).sum::<f32>(), max);
+    }
+
+    #[test]
+    fn events_ordered_with_timestamps() {
+        let clock = ManualClock::new();
+        let mut input = InputHarness::with_clock(clock.clone());
+
+        clock.advance(Duration::from_millis(5));
+        input.dispatcher().press_key(KeyCode::KeyA);
+        clock.advance(Duration::from_micros(2_050));
+        input.dispatcher().move_cursor((12.4, 30.6));
+        input
+            .dispatcher()
+            .push_mouse_button(MouseButton::Left, true);
+        clock.advance(Duration::from_micros(1_960));
+        input.dispatcher().release_key(KeyCode::KeyA);
+
+        let state = input.step();
+        let events = state.events();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(
+            events[0].event,
+            InputEvent::Key(KeyEvent::Keyboard { release: false, .. })
+        ));
+        assert!(matches!(
+            events[1].event,
+            InputEvent::MouseButton {
+                event: KeyEvent::Mouse { release: false, .. },
+                position: (12.0, 31.0),
+            }
+        ));
+        assert!(matches!(
+            events[2].event,
+            InputEvent::Key(KeyEvent::Keyboard { release: true, .. })
+        ));
+
+        // the time elapsed between events is rounded down to 100µs, without
+        // the error adding up
+        let elapsed = |i: usize| events[i].timestamp - events[i - 1].timestamp;
+        assert_eq!(elapsed(1), Duration::from_micros(2_000));
+        assert_eq!(elapsed(2), Duration::from_micros(2_000));
+
+        let last = events[2].timestamp;
+
+        assert!(matches!(
+            state.pop_key_event(),
+            Some(KeyEvent::Keyboard { release: false, .. })
+        ));
+        assert!(state.pop_key_event().unwrap().is_mouse());
+        assert!(state.pop_key_event().unwrap().is_released());
+        assert_eq!(state.pop_key_event(), None);
+
+        // an exact full stamp after a longer pause, the last event having been
+        // stamped 10µs early
+        clock.advance(Duration::from_micros(30_012));
+        input.dispatcher().press_key(KeyCode::KeyB);
+
+        let state = input.step();
+        assert_eq!(
+            state.events()[0].timestamp - last,
+            Duration::from_micros(30_022)
+        );
+    }
+
+    #[test]
+    fn frame_holds_ten_typed_characters() {
+        let mut input = InputHarness::new();
+
+        let keys = [
+            KeyCode::KeyQ,
+            KeyCode::KeyW,
+            KeyCode::KeyE,
+            KeyCode::KeyR,
+            KeyCode::KeyT,
+            KeyCode::KeyY,
+            KeyCode::KeyU,
+            KeyCode::KeyI,
+            KeyCode::KeyO,
+            KeyCode::KeyP,
+        ];
+        for (key, c) in keys.into_iter().zip("qwertyuiop".chars()) {
+            input
+                .dispatcher()
+                .push_key(Some(key.into()), Some(c.into()), true);
+            input
+                .dispatcher()
+                .push_text(TextEvent::Commit(c.to_string()));
+        }
+
+        let state = input.step();
+        assert_eq!(state.dropped_packets(), 0);
+        assert!(keys.into_iter().all(|key| state.keys().key_pressed(key)));
+        assert!(
+            "qwertyuiop"
+                .chars()
+                .all(|c| state.keys().logical_pressed(c))
+        );
+        assert_eq!(state.committed_text().collect::<String>(), "qwertyuiop");
+    }
+
+    #[test]
+    fn overflow_releases_held_keys() {
+        // 6 packets per frame, the first of which is the initial timestamp
+        let mut input = InputHarness::<3, SECTION_COUNT>::sized();
+        let stream = Arc::clone(&input.dispatcher().subscribers[0].stream);
+
+        let key = |code: KeyCode, down| {
+            StreamPacket::from(DeltaPacket::Keyboard {
+                code: code.into(),
+                down,
+            })
+        };
+        assert!(stream.push_front(key(KeyCode::KeyA, true)));
+        assert!(stream.push_front(key(KeyCode::KeyW, true)));
+        assert!(
+            stream.push_front(
+                DeltaPacket::Mouse {
+                    button: MouseButton::Left.into(),
+                    down: true,
+                }
+                .into()
+            )
+        );
+        assert!(
+            stream.push_front(
+                DeltaPacket::Modifiers {
+                    state: Modifiers::SHIFT,
+                }
+                .into()
+            )
+        );
+        assert!(stream.push_front(key(KeyCode::KeyD, true)));
+        assert!(!stream.push_front(key(KeyCode::KeyA, false)));
+
+        let state = input.step();
+        assert_eq!(state.dropped_packets(), 1);
+        let keys = state.keys();
+        assert!(keys.key_released(KeyCode::KeyA));
+        assert!(keys.key_released(KeyCode::KeyW));
+        assert!(keys.mouse_released(MouseButton::Left));
+        assert!(keys.key_released(KeyCode::KeyD));
+        assert!(keys.modifiers().is_empty());
+        assert_eq!(state.events().len(), 8);
+        assert!(state.events()[4..].iter().all(|timed| {
+            timed
+                .event
+                .key_event()
+                .is_some_and(|event| event.is_released())
+        }));
+
+        state.set_overflow_recovery(OverflowRecovery::Ignore);
+        for _ in 0..6 {
+            stream.push_front(key(KeyCode::KeyA, true));
+        }
+        stream.push_front(key(KeyCode::KeyA, false));
+
+        let state = input.step();
+        assert_eq!(state.dropped_packets(), 2);
+        assert!(state.keys().key_pressed(KeyCode::KeyA));
+    }
+
+    #[test]
+    fn overflow_resends_held_input() {
+        // 6 packets per frame, the first of which is the initial timestamp,
+        // with no further full timestamps as the clock never moves
+        let mut input = InputHarness::<3, SECTION_COUNT>::sized_with_clock(ManualClock::new());
+
+        let dispatcher = input.dispatcher();
+        dispatcher.push_modifiers(Modifiers::SHIFT);
+        dispatcher.press_key(KeyCode::KeyA);
+        dispatcher.press_key(KeyCode::KeyW);
+        dispatcher.push_scroll(ScrollUnit::Lines, (0.0, 1.0));
+        dispatcher.press_key(KeyCode::KeyD);
+        dispatcher.push_mouse_button(MouseButton::Left, true);
+
+        let state = input.step();
+        assert_eq!(state.dropped_packets(), 1);
+        assert!(state.keys().key_released(KeyCode::KeyA));
+        assert!(!state.keys().mouse_down(MouseButton::Left));
+
+        // everything still held is pressed again, the dropped click included
+        let state = input.step();
+        let keys = state.keys();
+        assert_eq!(state.dropped_packets(), 1);
+        assert_eq!(keys.modifiers(), Modifiers::SHIFT);
+        for key in [KeyCode::KeyA, KeyCode::KeyW, KeyCode::KeyD] {
+            assert!(keys.key_pressed(key));
+        }
+        assert!(keys.mouse_pressed(MouseButton::Left));
+
+        // and released as usual
+        input.dispatcher().release_key(KeyCode::KeyA);
+        input
+            .dispatcher()
+            .push_mouse_button(MouseButton::Left, false);
+
+        let state = input.step();
+        assert_eq!(state.dropped_packets(), 1);
+        assert!(state.keys().key_released(KeyCode::KeyA));
+        assert!(state.keys().key_held(KeyCode::KeyW));
+        assert!(state.keys().mouse_released(MouseButton::Left));
+    }
+
+    #[test]
+    fn side_channel_overflow_is_dropped() {
+        // 128 packets per frame, more than the text channel holds
+        let mut input = InputHarness::<64, SECTION_COUNT>::sized_with_clock(ManualClock::new());
+
+        let dispatcher = input.dispatcher();
+        dispatcher.press_key(KeyCode::KeyA);
+        for _ in 0..stream::SIDE_QUEUE_CAPACITY + 2 {
+            dispatcher.push_text(TextEvent::Commit("a".to_string()));
+        }
+
+        // the texts and their markers are dropped, as any other packet
+        let state = input.step();
+        assert_eq!(state.dropped_packets(), 2);
+        assert_eq!(
+            state.committed_text().collect::<String>().len(),
+            stream::SIDE_QUEUE_CAPACITY
+        );
+        assert!(state.keys().key_released(KeyCode::KeyA));
+
+        let state = input.step();
+        assert!(state.keys().key_pressed(KeyCode::KeyA));
+        input
+            .dispatcher()
+            .push_text(TextEvent::Commit("b".to_string()));
+
+        let state = input.step();
+        assert_eq!(state.dropped_packets(), 2);
+        assert_eq!(state.committed_text().collect::<String>(), "b");
+    }
+
+    #[test]
+    fn focus_loss_releases_held_input() {
+        let mut input = InputHarness::new();
+
+        let dispatcher = input.dispatcher();
+        dispatcher.push_hover(true);
+        dispatcher.push_modifiers(Modifiers::SHIFT);
+        dispatcher.push_key(Some(KeyCode::KeyW.into()), Some('w'.into()), true);
+        dispatcher.push_key(None, Some('q'.into()), true);
+        dispatcher.push_mouse_button(MouseButton::Left, true);
+
+        let state = input.step();
+        assert!(state.is_focused());
+        assert!(state.is_hovered());
+        assert!(state.keys().key_down(KeyCode::KeyW));
+        assert!(state.keys().mouse_down(MouseButton::Left));
+
+        // only the mouse buttons are released once the cursor leaves
+        input.dispatcher().push_hover(false);
+
+        let state = input.step();
+        assert!(!state.is_hovered());
+        assert!(state.keys().key_held(KeyCode::KeyW));
+        assert!(state.keys().mouse_released(MouseButton::Left));
+
+        input.dispatcher().push_focus(false);
+
+        let state = input.step();
+        let keys = state.keys();
+        assert!(!state.is_focused());
+        assert!(keys.key_released(KeyCode::KeyW));
+        assert!(keys.logical_released('w'));
+        assert!(keys.logical_released('q'));
+        assert!(keys.modifiers().is_empty());
+        assert_eq!(state.events().len(), 1);
+
+        // nothing is left to release
+        input.dispatcher().push_focus(true);
+        input.dispatcher().push_focus(false);
+
+        let state = input.step();
+        assert!(!state.is_focused());
+        assert!(state.events().is_empty());
+    }
+
+    #[test]
+    fn subscribers_receive_the_same_input() {
+        let mut input = InputHarness::new();
+        input.dispatcher().push_modifiers(Modifiers::SHIFT);
+        input.dispatcher().press_key(KeyCode::KeyW);
+        input.step();
+
+        // subscribed while shift and W are held
+        let mut recorder = input.dispatcher().subscribe();
+        assert_eq!(input.dispatcher().subscribers(), 2);
+        recorder.sync();
+        input.step();
+        recorder.sync();
+        recorder.poll_key_events();
+        assert!(recorder.keys().key_pressed(KeyCode::KeyW));
+        assert_eq!(recorder.keys().modifiers(), Modifiers::SHIFT);
+
+        input.dispatcher().press_key(KeyCode::KeyA);
+        input.dispatcher().move_cursor((10.0, 20.0));
+        input
+            .dispatcher()
+            .push_text(TextEvent::Commit("a".to_string()));
+
+        let state = input.step();
+        assert!(state.keys().key_held(KeyCode::KeyW));
+        assert!(state.keys().key_pressed(KeyCode::KeyA));
+        assert_eq!(state.committed_text().collect::<String>(), "a");
+
+        recorder.sync();
+        recorder.poll_key_events();
+        assert!(recorder.keys().key_held(KeyCode::KeyW));
+        assert!(recorder.keys().key_pressed(KeyCode::KeyA));
+        assert_eq!(recorder.keys().modifiers(), Modifiers::SHIFT);
+        assert_eq!(recorder.cursor().current(), (10.0, 20.0));
+        assert_eq!(recorder.committed_text().collect::<String>(), "a");
+
+        // each subscriber is synchronised at its own rate
+        input.dispatcher().press_key(KeyCode::KeyD);
+        assert!(input.step().keys().key_pressed(KeyCode::KeyD));
+        assert!(input.step().keys().key_held(KeyCode::KeyD));
+        assert!(input.state().committed_text().next().is_none());
+
+        recorder.sync();
+        recorder.poll_key_events();
+        assert!(recorder.keys().key_pressed(KeyCode::KeyD));
+        assert!(recorder.keys().key_held(KeyCode::KeyA));
+
+        // touches end in the frames of each subscriber
+        input
+            .dispatcher()
+            .push_touch(1, TouchPhase::Started, (5.0, 5.0), None);
+        input
+            .dispatcher()
+            .push_touch(1, TouchPhase::Ended, (5.0, 5.0), None);
+        assert_eq!(input.step().touches().iter().count(), 1);
+        assert_eq!(input.step().touches().iter().count(), 0);
+
+        recorder.sync();
+        assert_eq!(recorder.touches().get(1).unwrap().phase, TouchPhase::Ended);
+        input.step();
+        recorder.sync();
+        assert!(recorder.touches().get(1).is_none());
+
+        drop(recorder);
+        input.step();
+        assert_eq!(input.dispatcher().subscribers(), 1);
     }
 }
@@ -0,0 +1,449 @@
+//! Deterministic recording and replay of the input received by an
+//! [`InputState`](crate::input::InputState).
+//!
+//! A recording is a compact little-endian binary stream: a short header
+//! followed by one [`InputFrame`] per logic frame.
+//!
+//! See [`InputState::record`](crate::input::InputState::record) and
+//! [`InputState::replay`](crate::input::InputState::replay).
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use crate::input::{
+    CursorValues, DeltaPacket, MAX_TOUCHES, MouseWheelValue, TouchPhase, TouchPoint,
+};
+
+const MAGIC: [u8; 4] = *b"JNSI";
+const VERSION: u16 = 4;
+
+// flags of a recorded touch
+const TOUCH_PRIMARY: u8 = 1 << 0;
+const TOUCH_FORCE: u8 = 1 << 1;
+
+/// All of the input consumed by an [`InputState`](crate::input::InputState)
+/// in a single logic frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputFrame {
+    /// The frame's delta time, as given to
+    /// [`InputState::sync_with_delta`](crate::input::InputState::sync_with_delta).
+    pub delta: Duration,
+    /// The input packets drained from the input stream, in order.
+    pub packets: Vec<DeltaPacket>,
+    pub cursor: CursorValues,
+    pub cursor_delta: CursorValues,
+    pub mouse_wheel: MouseWheelValue,
+    /// The touches of the frame, see
+    /// [`Touches::iter`](crate::input::Touches::iter).
+    pub touches: Vec<TouchPoint>,
+}
+
+impl InputFrame {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let packets = u16::try_from(self.packets.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many input packets"))?;
+        if self.touches.len() > MAX_TOUCHES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "too many touches",
+            ));
+        }
+
+        writer.write_all(&(self.delta.as_nanos() as u64).to_le_bytes())?;
+        writer.write_all(&self.cursor.0.to_le_bytes())?;
+        writer.write_all(&self.cursor.1.to_le_bytes())?;
+        writer.write_all(&self.cursor_delta.0.to_le_bytes())?;
+        writer.write_all(&self.cursor_delta.1.to_le_bytes())?;
+        let wheel = &self.mouse_wheel;
+        for value in [wheel.lines.0, wheel.lines.1, wheel.pixels.0, wheel.pixels.1] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&packets.to_le_bytes())?;
+        for packet in &self.packets {
+            writer.write_all(&packet.as_bits().to_le_bytes())?;
+        }
+
+        writer.write_all(&[self.touches.len() as u8])?;
+        for touch in &self.touches {
+            let mut flags = 0;
+            if touch.primary {
+                flags |= TOUCH_PRIMARY;
+            }
+            if touch.force.is_some() {
+                flags |= TOUCH_FORCE;
+            }
+            writer.write_all(&touch.id.to_le_bytes())?;
+            writer.write_all(&[phase_to_bits(touch.phase), flags])?;
+            writer.write_all(&touch.position.0.to_le_bytes())?;
+            writer.write_all(&touch.position.1.to_le_bytes())?;
+            writer.write_all(&touch.force.unwrap_or(0.0).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read a single frame from the `reader`.
+    ///
+    /// # Returns
+    /// [`None`] if the `reader` has reached its end before the frame.
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Option<Self>> {
+        let mut delta = [0u8; 8];
+        match reader.read_exact(&mut delta) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+
+        let x = f64::from_le_bytes(read_bytes(reader)?);
+        let y = f64::from_le_bytes(read_bytes(reader)?);
+        let dx = f64::from_le_bytes(read_bytes(reader)?);
+        let dy = f64::from_le_bytes(read_bytes(reader)?);
+        let mut wheel = [0f32; 4];
+        for value in wheel.iter_mut() {
+            *value = f32::from_le_bytes(read_bytes(reader)?);
+        }
+        let mouse_wheel = MouseWheelValue {
+            lines: (wheel[0], wheel[1]),
+            pixels: (wheel[2], wheel[3]),
+        };
+        let count = u16::from_le_bytes(read_bytes(reader)?);
+
+        let mut packets = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let bits = u32::from_le_bytes(read_bytes(reader)?);
+            let packet = DeltaPacket::try_from_bits(bits).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "invalid input packet")
+            })?;
+            packets.push(packet);
+        }
+
+        let [count] = read_bytes(reader)?;
+        if count as usize > MAX_TOUCHES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "too many touches",
+            ));
+        }
+        let mut touches = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let id = u64::from_le_bytes(read_bytes(reader)?);
+            let [phase, flags] = read_bytes(reader)?;
+            let phase = phase_from_bits(phase)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid touch phase"))?;
+            let x = f64::from_le_bytes(read_bytes(reader)?);
+            let y = f64::from_le_bytes(read_bytes(reader)?);
+            let force = f64::from_le_bytes(read_bytes(reader)?);
+            touches.push(TouchPoint {
+                id,
+                phase,
+                position: (x, y),
+                force: (flags & TOUCH_FORCE != 0).then_some(force),
+                primary: flags & TOUCH_PRIMARY != 0,
+            });
+        }
+
+        Ok(Some(Self {
+            delta: Duration::from_nanos(u64::from_le_bytes(delta)),
+            packets,
+            cursor: (x, y),
+            cursor_delta: (dx, dy),
+            mouse_wheel,
+            touches,
+        }))
+    }
+}
+
+fn phase_to_bits(phase: TouchPhase) -> u8 {
+    match phase {
+        TouchPhase::Started => 0,
+        TouchPhase::Moved => 1,
+        TouchPhase::Ended => 2,
+        TouchPhase::Cancelled => 3,
+    }
+}
+
+fn phase_from_bits(bits: u8) -> Option<TouchPhase> {
+    match bits {
+        0 => Some(TouchPhase::Started),
+        1 => Some(TouchPhase::Moved),
+        2 => Some(TouchPhase::Ended),
+        3 => Some(TouchPhase::Cancelled),
+        _ => None,
+    }
+}
+
+#[inline(always)]
+fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Writes [`InputFrame`]s to an underlying writer, usually a file.
+pub struct InputRecorder {
+    writer: Box<dyn Write + Send>,
+    frames: u64,
+}
+
+impl std::fmt::Debug for InputRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputRecorder")
+            .field("frames", &self.frames)
+            .finish_non_exhaustive()
+    }
+}
+
+impl InputRecorder {
+    /// Start a new recording, immediately writing its header to `writer`.
+    pub fn new<W: Write + Send + 'static>(writer: W) -> io::Result<Self> {
+        let mut writer: Box<dyn Write + Send> = Box::new(writer);
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+
+        Ok(Self { writer, frames: 0 })
+    }
+
+    pub fn record(&mut self, frame: &InputFrame) -> io::Result<()> {
+        frame.write_to(&mut self.writer)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// The amount of frames recorded so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A fully loaded recording of [`InputFrame`]s.
+#[derive(Clone, Debug, Default)]
+pub struct InputReplay {
+    frames: VecDeque<InputFrame>,
+}
+
+impl InputReplay {
+    /// Read a whole recording from `reader`.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let magic: [u8; 4] = read_bytes(&mut reader)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an input recording",
+            ));
+        }
+
+        let version = u16::from_le_bytes(read_bytes(&mut reader)?);
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported input recording version {version}"),
+            ));
+        }
+
+        let mut frames = VecDeque::new();
+        while let Some(frame) = InputFrame::read_from(&mut reader)? {
+            frames.push_back(frame);
+        }
+        Ok(Self { frames })
+    }
+
+    pub fn from_frames(frames: impl IntoIterator<Item = InputFrame>) -> Self {
+        Self {
+            frames: frames.into_iter().collect(),
+        }
+    }
+
+    /// The amount of frames left to replay.
+    pub fn remaining(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn next_frame(&mut self) -> Option<InputFrame> {
+        self.frames.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{
+        InputEvent, InputHarness, InputState, KeyCode, KeyboardKeyCode, MouseButton,
+        MouseButtonIndex, ScrollUnit,
+    };
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn frame_roundtrip() {
+        let frames = [
+            InputFrame {
+                delta: Duration::from_micros(16_667),
+                packets: vec![
+                    DeltaPacket::Keyboard {
+                        code: KeyboardKeyCode(12),
+                        down: true,
+                    },
+                    DeltaPacket::Mouse {
+                        button: MouseButtonIndex(1),
+                        down: false,
+                    },
+                    DeltaPacket::Scroll {
+                        unit: ScrollUnit::Pixels,
+                        horizontal: true,
+                        delta: -200,
+                    },
+                ],
+                cursor: (320.5, 240.0),
+                cursor_delta: (-1.0, 3.25),
+                mouse_wheel: MouseWheelValue {
+                    lines: (0.0, 2.0),
+                    pixels: (-12.5, 0.0),
+                },
+                touches: vec![
+                    TouchPoint {
+                        id: 7,
+                        phase: TouchPhase::Moved,
+                        position: (10.5, 20.0),
+                        force: Some(0.25),
+                        primary: true,
+                    },
+                    TouchPoint {
+                        id: 9,
+                        phase: TouchPhase::Cancelled,
+                        position: (-1.0, 0.0),
+                        force: None,
+                        primary: false,
+                    },
+                ],
+            },
+            InputFrame::default(),
+        ];
+
+        let buffer = SharedBuffer::default();
+        let mut recorder = InputRecorder::new(buffer.clone()).unwrap();
+        frames.iter().for_each(|f| recorder.record(f).unwrap());
+        assert_eq!(recorder.frames(), 2);
+
+        let bytes = buffer.0.lock().unwrap().clone();
+        let mut replay = InputReplay::read(bytes.as_slice()).unwrap();
+        assert_eq!(replay.remaining(), 2);
+        assert_eq!(replay.next_frame().as_ref(), Some(&frames[0]));
+        assert_eq!(replay.next_frame().as_ref(), Some(&frames[1]));
+        assert!(replay.is_finished());
+
+        assert!(InputReplay::read(&bytes[1..]).is_err());
+    }
+
+    /// Everything observable in a frame, except for the event timestamps
+    /// which depend on when the input has been received.
+    #[derive(Debug, PartialEq)]
+    struct Observed {
+        w_frames: u16,
+        left_frames: u16,
+        cursor: CursorValues,
+        cursor_delta: CursorValues,
+        mouse_wheel: (f32, f32),
+        touches: Vec<TouchPoint>,
+        frame_delta: Duration,
+        events: Vec<InputEvent>,
+    }
+
+    fn observe<const S: usize, const C: usize>(state: &InputState<S, C>) -> Observed {
+        let keys = state.keys();
+        let events = state.events().iter().map(|timed| timed.event.clone());
+        Observed {
+            w_frames: keys.key_frames(KeyCode::KeyW),
+            left_frames: keys.mouse_frames(MouseButton::Left),
+            cursor: state.cursor().current(),
+            cursor_delta: state.cursor().delta(),
+            mouse_wheel: state.mouse_wheel(),
+            touches: state.touches().iter().collect(),
+            frame_delta: state.frame_delta(),
+            events: events.collect(),
+        }
+    }
+
+    #[test]
+    fn replay_drives_the_state() {
+        const DELTA: Duration = Duration::from_micros(16_667);
+
+        let buffer = SharedBuffer::default();
+        let mut input = InputHarness::new();
+        input
+            .state_mut()
+            .record(InputRecorder::new(buffer.clone()).unwrap());
+
+        let mut recorded = Vec::new();
+        let frames: [&dyn Fn(&mut InputHarness); 4] = [
+            &|input| {
+                input.dispatcher().press_key(KeyCode::KeyW);
+                input.dispatcher().move_cursor((40.0, 30.0));
+            },
+            &|input| {
+                input.dispatcher().scroll((0.0, -2.0));
+                input.dispatcher().move_cursor((50.0, 10.0));
+                input
+                    .dispatcher()
+                    .push_touch(3, TouchPhase::Started, (5.0, 6.0), Some(0.5));
+            },
+            &|input| {
+                input.dispatcher().click(MouseButton::Left);
+                input
+                    .dispatcher()
+                    .push_touch(3, TouchPhase::Ended, (7.0, 6.0), None);
+            },
+            &|input| input.dispatcher().release_key(KeyCode::KeyW),
+        ];
+        for (i, inject) in frames.iter().enumerate() {
+            inject(&mut input);
+            let state = input.step_with_delta(DELTA * (i as u32 + 1));
+            recorded.push(observe(state));
+        }
+        let recorder = input.state_mut().stop_recording().unwrap();
+        assert_eq!(recorder.frames(), 4);
+
+        let bytes = buffer.0.lock().unwrap().clone();
+        let mut input = InputHarness::new();
+        input
+            .state_mut()
+            .replay(InputReplay::read(bytes.as_slice()).unwrap());
+
+        for expected in recorded {
+            // the live input is ignored while replaying
+            input.dispatcher().press_key(KeyCode::KeyQ);
+            input.dispatcher().move_cursor((1.0, 1.0));
+            input
+                .dispatcher()
+                .push_touch(1, TouchPhase::Started, (1.0, 1.0), None);
+
+            let state = input.step();
+            assert!(state.is_replaying());
+            assert!(!state.keys().key_down(KeyCode::KeyQ));
+            assert_eq!(observe(state), expected);
+        }
+
+        // the live input is resumed once every frame has been replayed
+        assert!(!input.step().is_replaying());
+    }
+}
@@ -0,0 +1,124 @@
+/// The default conversion between [`ScrollUnit::Lines`] and
+/// [`ScrollUnit::Pixels`].
+pub const DEFAULT_PIXELS_PER_LINE: f32 = 20.0;
+
+/// The most packets a scroll delta along a single axis is split into.
+const MAX_SCROLL_PACKETS: usize = 16;
+
+/// The unit of a scroll delta.
+///
+/// Mouse wheels usually scroll by lines, while touchpads scroll by pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScrollUnit {
+    Lines,
+    Pixels,
+}
+
+impl ScrollUnit {
+    /// The fixed-point scale of deltas sent through the input stream: lines
+    /// have a precision of `1/256` and pixels of `1/16`.
+    const fn fixed_scale(self) -> f32 {
+        match self {
+            ScrollUnit::Lines => 256.0,
+            ScrollUnit::Pixels => 16.0,
+        }
+    }
+
+    /// The largest delta of a single scroll along each axis, beyond which it
+    /// is clamped: 2048 lines or 32767 pixels.
+    pub const fn max_delta(self) -> f32 {
+        i16::MAX as f32 * MAX_SCROLL_PACKETS as f32 / self.fixed_scale()
+    }
+
+    /// Encode `delta` as the deltas of as many packets as it takes to send
+    /// it, at most [`MAX_SCROLL_PACKETS`] for a delta within
+    /// [`max_delta`](ScrollUnit::max_delta).
+    pub(crate) fn encode(self, delta: f32) -> impl Iterator<Item = i16> {
+        let mut remaining = (delta * self.fixed_scale()).round() as i32;
+        std::iter::from_fn(move || {
+            (remaining != 0).then(|| {
+                let part = remaining.clamp(i16::MIN as i32, i16::MAX as i32);
+                remaining -= part;
+                part as i16
+            })
+        })
+    }
+
+    pub(crate) fn decode(self, delta: i16) -> f32 {
+        delta as f32 / self.fixed_scale()
+    }
+}
+
+/// The scroll deltas accumulated in a frame, kept in the unit they have been
+/// received in.
+///
+/// Positive `x` scrolls right and positive `y` scrolls up.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MouseWheel {
+    pub lines: (f32, f32),
+    pub pixels: (f32, f32),
+}
+
+impl MouseWheel {
+    pub(crate) fn add(mut self, unit: ScrollUnit, (x, y): (f32, f32)) -> Self {
+        let delta = match unit {
+            ScrollUnit::Lines => &mut self.lines,
+            ScrollUnit::Pixels => &mut self.pixels,
+        };
+        delta.0 += x;
+        delta.1 += y;
+        self
+    }
+
+    /// The total delta in lines, converting pixels with `pixels_per_line`.
+    pub fn to_lines(self, pixels_per_line: f32) -> (f32, f32) {
+        (
+            self.lines.0 + self.pixels.0 / pixels_per_line,
+            self.lines.1 + self.pixels.1 / pixels_per_line,
+        )
+    }
+
+    /// The total delta in pixels, converting lines with `pixels_per_line`.
+    pub fn to_pixels(self, pixels_per_line: f32) -> (f32, f32) {
+        (
+            self.pixels.0 + self.lines.0 * pixels_per_line,
+            self.pixels.1 + self.lines.1 * pixels_per_line,
+        )
+    }
+
+    pub fn is_zero(self) -> bool {
+        self == Self::default()
+    }
+}
+
+/// A single scroll, in the order it has been received with the other input
+/// events of the frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollEvent {
+    pub unit: ScrollUnit,
+    pub delta: (f32, f32),
+}
+
+impl ScrollEvent {
+    /// The delta in lines, converting pixels with `pixels_per_line`.
+    pub fn lines(self, pixels_per_line: f32) -> (f32, f32) {
+        match self.unit {
+            ScrollUnit::Lines => self.delta,
+            ScrollUnit::Pixels => (
+                self.delta.0 / pixels_per_line,
+                self.delta.1 / pixels_per_line,
+            ),
+        }
+    }
+
+    /// The delta in pixels, converting lines with `pixels_per_line`.
+    pub fn pixels(self, pixels_per_line: f32) -> (f32, f32) {
+        match self.unit {
+            ScrollUnit::Lines => (
+                self.delta.0 * pixels_per_line,
+                self.delta.1 * pixels_per_line,
+            ),
+            ScrollUnit::Pixels => self.delta,
+        }
+    }
+}
@@ -1,6 +1,14 @@
-use std::sync::atomic::{AtomicU16, AtomicU64, Ordering};
-
-use crate::input::{KeyboardKeyCode, MouseButtonIndex};
+use crate::{
+    input::{
+        GamepadButton, GamepadId, KEYBOARD_ENTRIES, KeyboardKeyCode, LOGICAL_ENTRIES,
+        LogicalKeyCode, MAX_GAMEPADS, MOUSE_ENTRIES, Modifiers, MouseButtonIndex, ScrollUnit,
+        timestamp::TIMESTAMP_MASK,
+    },
+    sync::{
+        self, RingBuffer, ring,
+        stream::{EventStream, IterEventStream, Packet, StreamIndex},
+    },
+};
 
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -13,6 +21,89 @@ pub enum DeltaPacket {
         button: MouseButtonIndex,
         down: bool,
     },
+    /// Marks the position of a [`TextEvent`](crate::input::TextEvent) in the
+    /// stream, which is sent separately along with the same `seq`.
+    Text {
+        seq: u16,
+    },
+    /// A key as interpreted by the current keyboard layout, sent along with
+    /// the [`Keyboard`](DeltaPacket::Keyboard) packet of the physical key.
+    Logical {
+        key: LogicalKeyCode,
+        down: bool,
+    },
+    /// The new state of the modifier keys.
+    Modifiers {
+        state: Modifiers,
+    },
+    Gamepad {
+        pad: GamepadId,
+        button: GamepadButton,
+        down: bool,
+    },
+    GamepadConnection {
+        pad: GamepadId,
+        connected: bool,
+    },
+    /// A scroll along a single axis, as a fixed-point `delta` in the given
+    /// `unit`.
+    Scroll {
+        unit: ScrollUnit,
+        horizontal: bool,
+        delta: i16,
+    },
+    /// The time at which the following packets have been received, see
+    /// [`TimedEvent`](crate::input::TimedEvent).
+    Timestamp {
+        micros: u32,
+    },
+    /// The cursor position in whole physical pixels, clamped between
+    /// [`DeltaPacket::CLICK_POSITION_MIN`] and
+    /// [`DeltaPacket::CLICK_POSITION_MAX`], sent before a
+    /// [`Mouse`](DeltaPacket::Mouse) packet.
+    ///
+    /// The position may be negative, e.g. while the cursor is captured by
+    /// the window after a press and dragged past its top left corner.
+    ///
+    /// Only found in [`recordings`](crate::input::record); the input stream
+    /// sends [`Click`](DeltaPacket::Click) packets instead.
+    ClickPosition {
+        x: i16,
+        y: i16,
+    },
+    /// The window has gained or lost focus.
+    Focus {
+        focused: bool,
+    },
+    /// The cursor has entered or left the window.
+    Hover {
+        hovered: bool,
+    },
+    /// Marks the position of a [`FileEvent`](crate::input::FileEvent) in the
+    /// stream, which is sent separately along with the same `seq`.
+    File {
+        seq: u16,
+    },
+    /// A mouse button state change, whose cursor position is sent separately
+    /// along with the same `seq`, which wraps around at
+    /// [`DeltaPacket::CLICK_SEQ_MAX`].
+    Click {
+        button: MouseButtonIndex,
+        down: bool,
+        seq: u16,
+    },
+}
+
+impl Packet for DeltaPacket {
+    type Bits = u32;
+
+    fn encode(self) -> u32 {
+        self.as_bits()
+    }
+
+    fn decode(bits: u32) -> Self {
+        Self::from_bits(bits)
+    }
 }
 
 impl From<u32> for DeltaPacket {
@@ -30,6 +121,32 @@ impl Into<u32> for DeltaPacket {
 impl DeltaPacket {
     const KEYBOARD_ID_BIT: u8 = 1;
     const MOUSE_ID_BIT: u8 = 2;
+    const TEXT_ID_BIT: u8 = 3;
+    const LOGICAL_ID_BIT: u8 = 4;
+    const MODIFIERS_ID_BIT: u8 = 5;
+    const GAMEPAD_ID_BIT: u8 = 6;
+    const GAMEPAD_CONNECTION_ID_BIT: u8 = 7;
+    const SCROLL_ID_BIT: u8 = 8;
+    const TIMESTAMP_ID_BIT: u8 = 9;
+    const CLICK_POSITION_ID_BIT: u8 = 10;
+    const FOCUS_ID_BIT: u8 = 11;
+    const HOVER_ID_BIT: u8 = 12;
+    const FILE_ID_BIT: u8 = 13;
+    const CLICK_ID_BIT: u8 = 14;
+
+    /// Each coordinate of a click position is stored in two's complement.
+    const CLICK_POSITION_BITS: u32 = 14;
+    const CLICK_POSITION_MASK: u32 = (1 << Self::CLICK_POSITION_BITS) - 1;
+    pub const CLICK_POSITION_MIN: i16 = -(1 << (Self::CLICK_POSITION_BITS - 1));
+    pub const CLICK_POSITION_MAX: i16 = (1 << (Self::CLICK_POSITION_BITS - 1)) - 1;
+
+    /// The button of a click takes the lowest bits of the code, its sequence
+    /// number the rest.
+    const CLICK_BUTTON_BITS: u32 = 5;
+    pub const CLICK_SEQ_MAX: u16 = u16::MAX >> Self::CLICK_BUTTON_BITS;
+
+    const SCROLL_HORIZONTAL_BIT: u32 = 0b01;
+    const SCROLL_PIXELS_BIT: u32 = 0b10;
 
     // mask is used for decode op
     // use mask only after shifting
@@ -41,25 +158,99 @@ impl DeltaPacket {
     const CODE_BIT_SHIFT: i32 = 8;
     const ID_BIT_SHIFT: i32 = 28;
 
+    fn encode_click_coordinate(coordinate: i16) -> u32 {
+        let coordinate = coordinate.clamp(Self::CLICK_POSITION_MIN, Self::CLICK_POSITION_MAX);
+        coordinate as u16 as u32 & Self::CLICK_POSITION_MASK
+    }
+
+    /// Sign-extend the lowest [`CLICK_POSITION_BITS`](Self::CLICK_POSITION_BITS)
+    /// of `bits`.
+    fn decode_click_coordinate(bits: u32) -> i16 {
+        let unused = 16 - Self::CLICK_POSITION_BITS;
+        ((bits & Self::CLICK_POSITION_MASK) as i16) << unused >> unused
+    }
+
     fn from_bits(bits: u32) -> Self {
+        Self::try_from_bits(bits).unwrap_or_else(|| {
+            let id = bits >> Self::ID_BIT_SHIFT;
+            unreachable!(
+                "invalid input-delta synchronisation packet type ID of bit {id}; this is a bug"
+            )
+        })
+    }
+
+    /// Decode a packet from untrusted `bits`, such as those read from an
+    /// [`input recording`](crate::input::record).
+    ///
+    /// # Returns
+    /// [`None`] if the type ID is invalid or the code is out of range.
+    pub fn try_from_bits(bits: u32) -> Option<Self> {
         let state = bits & 0x0000000F;
         let code = (bits >> Self::CODE_BIT_SHIFT & 0x0000FFFF) as u16;
         let id = (bits >> Self::ID_BIT_SHIFT) as u8;
 
         match id {
-            Self::KEYBOARD_ID_BIT => Self::Keyboard {
+            Self::KEYBOARD_ID_BIT if (code as usize) < KEYBOARD_ENTRIES => Some(Self::Keyboard {
                 code: KeyboardKeyCode(code),
                 down: state == 1,
-            },
-            Self::MOUSE_ID_BIT => Self::Mouse {
+            }),
+            Self::MOUSE_ID_BIT if (code as usize) < MOUSE_ENTRIES => Some(Self::Mouse {
                 button: MouseButtonIndex(code),
                 down: state == 1,
-            },
-            invalid => {
-                unreachable!(
-                    "invalid input-delta synchronisation packet type ID of bit {invalid}; this is a bug"
-                )
+            }),
+            Self::TEXT_ID_BIT => Some(Self::Text { seq: code }),
+            Self::LOGICAL_ID_BIT if (code as usize) < LOGICAL_ENTRIES => Some(Self::Logical {
+                key: LogicalKeyCode(code),
+                down: state == 1,
+            }),
+            Self::MODIFIERS_ID_BIT if code <= Modifiers::all().bits() as u16 => {
+                Some(Self::Modifiers {
+                    state: Modifiers::from_bits(code as u8),
+                })
             }
+            Self::GAMEPAD_ID_BIT if ((code >> 8) as usize) < MAX_GAMEPADS => Some(Self::Gamepad {
+                pad: GamepadId((code >> 8) as u8),
+                button: GamepadButton::from_index((code & 0xFF) as usize)?,
+                down: state == 1,
+            }),
+            Self::SCROLL_ID_BIT => Some(Self::Scroll {
+                unit: if state & Self::SCROLL_PIXELS_BIT != 0 {
+                    ScrollUnit::Pixels
+                } else {
+                    ScrollUnit::Lines
+                },
+                horizontal: state & Self::SCROLL_HORIZONTAL_BIT != 0,
+                delta: code as i16,
+            }),
+            Self::TIMESTAMP_ID_BIT => Some(Self::Timestamp {
+                micros: bits & TIMESTAMP_MASK,
+            }),
+            Self::CLICK_POSITION_ID_BIT => Some(Self::ClickPosition {
+                x: Self::decode_click_coordinate(bits),
+                y: Self::decode_click_coordinate(bits >> Self::CLICK_POSITION_BITS),
+            }),
+            Self::GAMEPAD_CONNECTION_ID_BIT if (code as usize) < MAX_GAMEPADS => {
+                Some(Self::GamepadConnection {
+                    pad: GamepadId(code as u8),
+                    connected: state == 1,
+                })
+            }
+            Self::FOCUS_ID_BIT => Some(Self::Focus {
+                focused: state == 1,
+            }),
+            Self::HOVER_ID_BIT => Some(Self::Hover {
+                hovered: state == 1,
+            }),
+            Self::FILE_ID_BIT => Some(Self::File { seq: code }),
+            Self::CLICK_ID_BIT => {
+                let button = code & ((1 << Self::CLICK_BUTTON_BITS) - 1);
+                ((button as usize) < MOUSE_ENTRIES).then_some(Self::Click {
+                    button: MouseButtonIndex(button),
+                    down: state == 1,
+                    seq: code >> Self::CLICK_BUTTON_BITS,
+                })
+            }
+            _ => None,
         }
     }
 
@@ -77,247 +268,211 @@ impl DeltaPacket {
                 let id = (Self::MOUSE_ID_BIT as u32) << Self::ID_BIT_SHIFT;
                 state | code << 8 | id
             }
+            DeltaPacket::Text { seq } => {
+                let id = (Self::TEXT_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                (seq as u32) << 8 | id
+            }
+            DeltaPacket::Logical { key, down } => {
+                let code = u16::from(key) as u32;
+                let state = down as u32;
+                let id = (Self::LOGICAL_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                state | code << 8 | id
+            }
+            DeltaPacket::Modifiers { state } => {
+                let id = (Self::MODIFIERS_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                (state.bits() as u32) << 8 | id
+            }
+            DeltaPacket::Gamepad { pad, button, down } => {
+                let code = (pad.0 as u32) << 8 | button as u32;
+                let state = down as u32;
+                let id = (Self::GAMEPAD_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                state | code << 8 | id
+            }
+            DeltaPacket::Scroll {
+                unit,
+                horizontal,
+                delta,
+            } => {
+                let mut state = 0;
+                if horizontal {
+                    state |= Self::SCROLL_HORIZONTAL_BIT;
+                }
+                if unit == ScrollUnit::Pixels {
+                    state |= Self::SCROLL_PIXELS_BIT;
+                }
+                let id = (Self::SCROLL_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                state | (delta as u16 as u32) << 8 | id
+            }
+            DeltaPacket::Timestamp { micros } => {
+                let id = (Self::TIMESTAMP_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                micros & TIMESTAMP_MASK | id
+            }
+            DeltaPacket::ClickPosition { x, y } => {
+                let x = Self::encode_click_coordinate(x);
+                let y = Self::encode_click_coordinate(y);
+                let id = (Self::CLICK_POSITION_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                x | y << Self::CLICK_POSITION_BITS | id
+            }
+            DeltaPacket::GamepadConnection { pad, connected } => {
+                let state = connected as u32;
+                let id = (Self::GAMEPAD_CONNECTION_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                state | (pad.0 as u32) << 8 | id
+            }
+            DeltaPacket::Focus { focused } => {
+                let id = (Self::FOCUS_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                focused as u32 | id
+            }
+            DeltaPacket::Hover { hovered } => {
+                let id = (Self::HOVER_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                hovered as u32 | id
+            }
+            DeltaPacket::File { seq } => {
+                let id = (Self::FILE_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                (seq as u32) << 8 | id
+            }
+            DeltaPacket::Click { button, down, seq } => {
+                let seq = (seq & Self::CLICK_SEQ_MAX) as u32;
+                let code = u16::from(button) as u32 | seq << Self::CLICK_BUTTON_BITS;
+                let state = down as u32;
+                let id = (Self::CLICK_ID_BIT as u32) << Self::ID_BIT_SHIFT;
+                state | code << 8 | id
+            }
         }
     }
 }
 
-/// A thread-safe input deltas/events synchronisation channel.
-#[repr(C, align(64))]
-#[derive(Debug)]
-pub struct InputStream<const FOLDS: usize, const SECTIONS: usize> {
-    stream: [[FoldBits; FOLDS]; SECTIONS],
-
-    // write
-    head: InputStreamIndex<FOLDS, SECTIONS>,
-
-    /// read
-    tail: InputStreamIndex<FOLDS, SECTIONS>,
-}
-
-impl<const FOLDS: usize, const SECTIONS: usize> Default for InputStream<FOLDS, SECTIONS> {
-    fn default() -> Self {
-        Self::new()
-    }
+/// A [`DeltaPacket`] as sent through the [`InputStream`], along with the time
+/// elapsed since the previous timestamp, which is packed into bits 4-7 and
+/// 24-27 that no packet uses otherwise.
+///
+/// [`DeltaPacket::Timestamp`] and [`DeltaPacket::ClickPosition`] packets take
+/// all of those bits, and are always sent without any elapsed time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StreamPacket {
+    pub packet: DeltaPacket,
+    /// In units of 100µs.
+    pub elapsed: u8,
 }
 
-impl<const FOLDS: usize, const SECTIONS: usize> InputStream<FOLDS, SECTIONS> {
-    pub fn new() -> Self {
-        let queue = { core::array::from_fn(|_| core::array::from_fn(|_| FoldBits::new(0))) };
-        Self {
-            stream: queue,
-            head: InputStreamIndex::new(0, 0),
-            tail: InputStreamIndex::new(0, 0),
+impl StreamPacket {
+    const ELAPSED_LOW_SHIFT: u32 = 4;
+    const ELAPSED_HIGH_SHIFT: u32 = 24;
+    const ELAPSED_MASK: u32 = 0x0F00_00F0;
+
+    fn as_bits(self) -> u32 {
+        let bits = self.packet.as_bits();
+        if matches!(
+            self.packet,
+            DeltaPacket::Timestamp { .. } | DeltaPacket::ClickPosition { .. }
+        ) {
+            return bits;
         }
+        let elapsed = self.elapsed as u32;
+        bits | (elapsed & 0xF) << Self::ELAPSED_LOW_SHIFT
+            | (elapsed >> 4) << Self::ELAPSED_HIGH_SHIFT
     }
 
-    pub fn frame_front(&self) {
-        let section = self.head.advance_section();
-        if section == self.tail.section() {
-            self.head.advance_section();
+    fn from_bits(bits: u32) -> Self {
+        let id = (bits >> DeltaPacket::ID_BIT_SHIFT) as u8;
+        if matches!(
+            id,
+            DeltaPacket::TIMESTAMP_ID_BIT | DeltaPacket::CLICK_POSITION_ID_BIT
+        ) {
+            return DeltaPacket::from_bits(bits).into();
         }
-    }
-
-    pub fn frame_back(&self) {
-        let next = (self.tail.section() as usize + 1) % SECTIONS;
-        if next == self.head.section() as usize {
-            return;
+        let low = bits >> Self::ELAPSED_LOW_SHIFT & 0xF;
+        let high = bits >> Self::ELAPSED_HIGH_SHIFT & 0xF;
+        Self {
+            packet: DeltaPacket::from_bits(bits & !Self::ELAPSED_MASK),
+            elapsed: (high << 4 | low) as u8,
         }
-        self.tail.advance_section();
     }
+}
 
-    pub fn push_front(&self, packet: DeltaPacket) {
-        let (local, section_i) = self.head.advance_local();
-        let section = &self.stream[section_i as usize];
-
-        let fold_i = local as usize / 2;
-        let side = local as usize % 2;
-        let fold = &section[fold_i];
-
-        if side == 0 {
-            fold.write_left(packet);
-        } else {
-            fold.write_right(packet);
-        }
+impl From<DeltaPacket> for StreamPacket {
+    fn from(packet: DeltaPacket) -> Self {
+        Self { packet, elapsed: 0 }
     }
+}
 
-    pub fn pop_back(&self) -> Option<DeltaPacket> {
-        let (local, section) = self.tail.advance_local();
-        let section = &self.stream[section as usize];
-
-        let fold = local as usize / 2;
-        let side = local as usize % 2;
-        let fold = &section[fold];
+impl Packet for StreamPacket {
+    type Bits = u32;
 
-        if side == 0 {
-            fold.read_left()
-        } else {
-            fold.read_right()
-        }
+    fn encode(self) -> u32 {
+        self.as_bits()
     }
 
-    pub fn drain_back(&self) -> IterInputStream<'_, FOLDS, SECTIONS> {
-        let section = self.tail.section();
-        IterInputStream {
-            index: &self.tail,
-            stream: &self.stream[section as usize],
-        }
+    fn decode(bits: u32) -> Self {
+        Self::from_bits(bits)
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct IterInputStream<'stream, const FOLDS: usize, const SECTIONS: usize> {
-    index: &'stream InputStreamIndex<FOLDS, SECTIONS>,
-    stream: &'stream [FoldBits; FOLDS],
-}
-
-impl<'stream, const FOLDS: usize, const SECTIONS: usize> Iterator
-    for IterInputStream<'stream, FOLDS, SECTIONS>
-{
-    type Item = DeltaPacket;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let (local, _) = self.index.advance_local();
-
-        let fold_i = local as usize / 2;
-        let side = local as usize % 2;
+/// How many events a side channel holds before its consumer has to catch up,
+/// past which they are dropped along with their markers.
+pub(crate) const SIDE_QUEUE_CAPACITY: usize = 64;
 
-        let fold = &self.stream[fold_i];
-
-        if side == 0 {
-            fold.read_left()
-        } else {
-            fold.read_right()
-        }
-    }
+/// Side channel for events which do not fit in a [`DeltaPacket`], split into
+/// the [`SideSender`] of the dispatcher and the [`SideQueue`] of a consumer.
+///
+/// Every event is tagged with a sequence number which is also sent through
+/// the input stream as a marker packet, such as [`DeltaPacket::Text`], so
+/// that the consumer can place it in order with the key events.
+pub(crate) fn side_queue<T>() -> (SideSender<T>, SideQueue<T>) {
+    let (sender, queue) = RingBuffer::new().split();
+    (SideSender(sender), SideQueue(queue))
 }
 
-/// Packs a section-local index and section index.
-///
-/// The section-local index is not the fold index and it is independent of fold
-/// size and count.
+/// The sending half of a [`side_queue`].
 #[derive(Debug)]
-pub struct InputStreamIndex<const FOLDS: usize, const SECTIONS: usize>(AtomicU16);
+pub(crate) struct SideSender<T>(ring::Writer<(u16, T), SIDE_QUEUE_CAPACITY>);
 
-impl<const FOLDS: usize, const SECTIONS: usize> InputStreamIndex<FOLDS, SECTIONS> {
-    const PER_FOLD_CAP: usize = size_of::<FoldBits>() / size_of::<DeltaPacket>();
-    const SECTION_CAPACITY: usize = FOLDS * Self::PER_FOLD_CAP;
-
-    pub const fn new(inner_index: u8, section: u8) -> Self {
-        let encoded = (inner_index as u16) << 8 | section as u16;
-        Self(AtomicU16::new(encoded))
-    }
-
-    /// Advance to the next local slot in the current buffer.
-    ///
-    /// This may wrap to the beginning of the current buffer if the next slot
-    /// exceeds the section's capacity.
-    ///
+impl<T> SideSender<T> {
     /// # Returns
-    /// The previous local index first with the current section unchanged.
-    pub fn advance_local(&self) -> (u8, u8) {
-        let (section, old_i) = self.extract();
-
-        // wrap around whole section if capacity of next index exceeds capacity
-        let i = (old_i + 1) % Self::SECTION_CAPACITY as u8;
-        self.encode(i, section);
-        (old_i, section)
-    }
-
-    /// Advance to the beginning of the next buffer section.
-    ///
-    /// # Returns
-    /// The new section it has advanced to.
-    pub fn advance_section(&self) -> u8 {
-        let (section, _) = self.extract();
-
-        // wrap around to first section after all have been exhausted
-        let section = (section + 1) % SECTIONS as u8;
-        self.encode(0, section);
-        section
-    }
-
-    fn encode(&self, index: u8, section: u8) {
-        let encoded = (index as u16) << 8 | section as u16;
-        self.0.store(encoded, Ordering::Release);
-    }
-
-    pub fn section(&self) -> u8 {
-        self.extract().0
-    }
-
-    pub fn get(&self) -> u16 {
-        self.0.load(Ordering::Acquire)
-    }
-
-    /// Extract the section index and the local index of the slot.
-    pub fn extract(&self) -> (u8, u8) {
-        let encoded = self.get();
-
-        let local = (encoded >> 8 & 0x00FF) as u8;
-        let section = (encoded & 0x00FF) as u8;
-        (section, local)
+    /// `false` if the queue is full and the event has been dropped, in which
+    /// case its marker must not be sent either.
+    pub(crate) fn push(&mut self, seq: u16, event: T) -> bool {
+        self.0.push((seq, event)).is_ok()
     }
 }
 
+/// The receiving half of a [`side_queue`].
 #[derive(Debug)]
-pub struct FoldBits(AtomicU64);
-
-impl FoldBits {
-    pub const fn new(int: u64) -> Self {
-        Self(AtomicU64::new(int))
-    }
-
-    fn read_bits(&self) -> (u32, u32) {
-        let value = self.0.load(Ordering::Acquire);
-
-        let left = (value >> 32) as u32;
-        let right = (value & 0x00000000FFFFFFFF) as u32;
-
-        (left, right)
-    }
+pub(crate) struct SideQueue<T>(ring::Reader<(u16, T), SIDE_QUEUE_CAPACITY>);
 
-    #[inline(always)]
-    pub fn write_left(&self, packet: DeltaPacket) {
-        let bits: u32 = packet.into();
-        self.write_left_bits(bits);
+impl<T> Default for SideQueue<T> {
+    fn default() -> Self {
+        Self(ring::Reader::default())
     }
+}
 
-    #[inline(always)]
-    pub fn write_right(&self, packet: DeltaPacket) {
-        let bits: u32 = packet.into();
-        self.write_right_bits(bits);
+impl<T> SideQueue<T> {
+    /// Pop the event with sequence number `seq`, discarding any older events
+    /// whose markers have been lost.
+    pub(crate) fn pop(&mut self, seq: u16) -> Option<T> {
+        while let Some((event_seq, event)) = self.0.pop() {
+            if event_seq == seq {
+                return Some(event);
+            }
+        }
+        None
     }
 
-    #[inline(always)]
-    pub fn write_left_bits(&self, bits: u32) {
-        self.0.store((bits as u64) << 32, Ordering::Release);
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
     }
+}
 
-    #[inline(always)]
-    pub fn write_right_bits(&self, bits: u32) {
-        self.0.fetch_or(bits as u64, Ordering::AcqRel);
-    }
+/// The input deltas/events synchronisation channel, see [`EventStream`].
+pub type InputStream<const FOLDS: usize, const SECTIONS: usize> =
+    EventStream<StreamPacket, FOLDS, SECTIONS>;
 
-    pub fn read_left(&self) -> Option<DeltaPacket> {
-        let (left, _) = self.read_bits();
-        self.write_left_bits(0);
+pub type IterInputStream<'stream, const FOLDS: usize, const SECTIONS: usize> =
+    IterEventStream<'stream, StreamPacket, FOLDS, SECTIONS>;
 
-        if left == 0 {
-            return None;
-        }
-        Some(DeltaPacket::from_bits(left))
-    }
+pub type InputStreamIndex<const FOLDS: usize, const SECTIONS: usize> =
+    StreamIndex<StreamPacket, FOLDS, SECTIONS>;
 
-    pub fn read_right(&self) -> Option<DeltaPacket> {
-        let (_, right) = self.read_bits();
-        self.write_right_bits(0);
-
-        if right == 0 {
-            return None;
-        }
-        Some(DeltaPacket::from_bits(right))
-    }
-}
+pub type FoldBits = sync::stream::FoldBits<StreamPacket>;
 
 #[cfg(test)]
 mod tests {
@@ -331,13 +486,17 @@ mod tests {
         assert_eq!(l, 0);
         assert_eq!(r, 0);
 
-        let kb_ev = DeltaPacket::Keyboard {
+        let kb_ev: StreamPacket = DeltaPacket::Keyboard {
             code: KeyboardKeyCode(67),
             down: true,
-        };
-        let mouse_ev = DeltaPacket::Mouse {
-            button: MouseButtonIndex(1),
-            down: false,
+        }
+        .into();
+        let mouse_ev = StreamPacket {
+            packet: DeltaPacket::Mouse {
+                button: MouseButtonIndex(1),
+                down: false,
+            },
+            elapsed: 0xA5,
         };
 
         fold.write_right(kb_ev);
@@ -360,11 +519,18 @@ mod tests {
         assert_eq!(r, None);
         assert_eq!(l, Some(kb_ev));
 
+        // reading takes the packet, the left one has already been read
         fold.write_right(mouse_ev);
         let r = fold.read_right();
         let l = fold.read_left();
         assert_eq!(r, Some(mouse_ev));
-        assert_eq!(l, Some(kb_ev));
+        assert_eq!(l, None);
+
+        fold.write_right(kb_ev);
+        fold.write_left(mouse_ev);
+        assert_eq!(fold.read_bits(), (mouse_ev.as_bits(), kb_ev.as_bits()));
+        assert_eq!(fold.read_right(), Some(kb_ev));
+        assert_eq!(fold.read_left(), Some(mouse_ev));
 
         fold.write_left(mouse_ev);
         let l = fold.read_left();
@@ -372,4 +538,66 @@ mod tests {
         assert_eq!(l, Some(mouse_ev));
         assert_eq!(r, None);
     }
+
+    #[test]
+    fn elapsed_time_packed_in_spare_bits() {
+        let packets = [
+            DeltaPacket::Keyboard {
+                code: KeyboardKeyCode((KEYBOARD_ENTRIES - 1) as u16),
+                down: true,
+            },
+            DeltaPacket::Logical {
+                key: LogicalKeyCode((LOGICAL_ENTRIES - 1) as u16),
+                down: false,
+            },
+            DeltaPacket::Text { seq: u16::MAX },
+            DeltaPacket::Modifiers {
+                state: Modifiers::all(),
+            },
+            DeltaPacket::Scroll {
+                unit: ScrollUnit::Pixels,
+                horizontal: true,
+                delta: -1,
+            },
+            DeltaPacket::Click {
+                button: MouseButtonIndex((MOUSE_ENTRIES - 1) as u16),
+                down: true,
+                seq: DeltaPacket::CLICK_SEQ_MAX,
+            },
+        ];
+        for packet in packets {
+            assert_eq!(DeltaPacket::from_bits(packet.as_bits()), packet);
+            for elapsed in [0, 1, 0x0F, 0xF0, u8::MAX] {
+                let stream_packet = StreamPacket { packet, elapsed };
+                assert_eq!(StreamPacket::decode(stream_packet.encode()), stream_packet);
+            }
+        }
+
+        // full stamps take every bit but those of the ID
+        let stamp = DeltaPacket::Timestamp {
+            micros: TIMESTAMP_MASK,
+        };
+        assert_eq!(StreamPacket::decode(stamp.as_bits()), stamp.into());
+    }
+
+    #[test]
+    fn click_positions_keep_their_sign() {
+        let min = DeltaPacket::CLICK_POSITION_MIN;
+        let max = DeltaPacket::CLICK_POSITION_MAX;
+        for (x, y) in [(0, 0), (-1, 1), (min, max), (max, min), (-640, -360)] {
+            let position = DeltaPacket::ClickPosition { x, y };
+            assert_eq!(DeltaPacket::from_bits(position.as_bits()), position);
+            assert_eq!(StreamPacket::decode(position.as_bits()), position.into());
+        }
+
+        // out of range coordinates are clamped rather than wrapped
+        let position = DeltaPacket::ClickPosition {
+            x: i16::MIN,
+            y: i16::MAX,
+        };
+        assert_eq!(
+            DeltaPacket::from_bits(position.as_bits()),
+            DeltaPacket::ClickPosition { x: min, y: max }
+        );
+    }
 }
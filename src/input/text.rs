@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::input::stream::SideQueue;
+
+/// A text input event, either typed directly or composed through the IME.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TextEvent {
+    /// Text to be inserted, either typed or committed by the IME.
+    Commit(String),
+
+    /// The IME's current composition, replacing any previous one.
+    ///
+    /// An empty `text` means the composition has been cleared.
+    /// The `cursor` is the byte range of the composition's cursor, if any.
+    Preedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+
+    ImeEnabled,
+    ImeDisabled,
+}
+
+impl TextEvent {
+    pub fn committed(&self) -> Option<&str> {
+        match self {
+            TextEvent::Commit(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Side channel for [`TextEvent`]s, which do not fit in a
+/// [`DeltaPacket`](crate::input::DeltaPacket), placed in order by their
+/// [`DeltaPacket::Text`](crate::input::DeltaPacket) markers.
+pub(crate) type TextQueue = SideQueue<TextEvent>;
+
+/// IME options requested by the consumer thread, to be applied on the window
+/// by the [`Context`](crate::context::Context).
+#[derive(Debug, Default)]
+pub struct ImeOptions {
+    allowed: AtomicBool,
+    /// Packed `f32` x and y of the candidate window area.
+    position: AtomicU64,
+    /// Packed `f32` width and height of the candidate window area.
+    size: AtomicU64,
+    pub dirty: AtomicBool,
+}
+
+impl ImeOptions {
+    pub fn allowed(&self) -> bool {
+        self.allowed.load(Ordering::Relaxed)
+    }
+
+    pub fn set_allowed(&self, allowed: bool) {
+        let changed = self
+            .allowed
+            .compare_exchange(!allowed, allowed, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok();
+
+        if changed {
+            self.dirty.store(true, Ordering::Release);
+        }
+    }
+
+    /// The area of the text being edited, which the IME candidate window
+    /// should avoid covering, as `(x, y, width, height)` in physical pixels.
+    pub fn cursor_area(&self) -> (f32, f32, f32, f32) {
+        let (x, y) = unpack(self.position.load(Ordering::Relaxed));
+        let (w, h) = unpack(self.size.load(Ordering::Relaxed));
+        (x, y, w, h)
+    }
+
+    pub fn set_cursor_area(&self, x: f32, y: f32, width: f32, height: f32) {
+        self.position.store(pack(x, y), Ordering::Relaxed);
+        self.size.store(pack(width, height), Ordering::Relaxed);
+        self.dirty.store(true, Ordering::Release);
+    }
+}
+
+#[inline(always)]
+fn pack(a: f32, b: f32) -> u64 {
+    (a.to_bits() as u64) << 32 | b.to_bits() as u64
+}
+
+#[inline(always)]
+fn unpack(packed: u64) -> (f32, f32) {
+    (
+        f32::from_bits((packed >> 32) as u32),
+        f32::from_bits(packed as u32),
+    )
+}
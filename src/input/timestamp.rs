@@ -0,0 +1,198 @@
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::context::{Clock, SystemClock};
+
+/// Full timestamps are sent through the input stream as the lowest bits of
+/// the microseconds elapsed since the shared [`TimeOrigin`], wrapping around
+/// roughly every 268 seconds.
+pub(crate) const TIMESTAMP_BITS: u32 = 28;
+pub(crate) const TIMESTAMP_MASK: u32 = (1 << TIMESTAMP_BITS) - 1;
+
+/// The unit of the time elapsed since the previous stamp, sent along with
+/// every other packet, see [`StreamPacket`](crate::input::stream::StreamPacket).
+pub(crate) const ELAPSED_UNIT_MICROS: u64 = 100;
+/// Beyond this many units, a full stamp is sent instead.
+const MAX_ELAPSED: u64 = u8::MAX as u64;
+
+/// A stamp is always sent at least this often, so that the consumer never
+/// misses a whole wrap around.
+const KEEP_ALIVE_MICROS: u64 = 1 << (TIMESTAMP_BITS - 1);
+
+/// The instant both ends of an input stream measure their timestamps from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct TimeOrigin(Instant);
+
+impl Default for TimeOrigin {
+    fn default() -> Self {
+        Self(Instant::now())
+    }
+}
+
+/// The type-erased [`Clock`] the input is stamped with.
+#[derive(Clone)]
+struct InputClock(Arc<dyn Fn() -> Instant + Send + Sync>);
+
+impl InputClock {
+    fn new(clock: impl Clock) -> Self {
+        Self(Arc::new(move || clock.now()))
+    }
+
+    fn now(&self) -> Instant {
+        (self.0)()
+    }
+}
+
+impl Default for InputClock {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl fmt::Debug for InputClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InputClock").finish_non_exhaustive()
+    }
+}
+
+/// The time of an event, as sent before or along with its packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Stamp {
+    /// A full stamp, to be sent as its own packet.
+    Full(u32),
+    /// The time elapsed since the previous stamp, in units of
+    /// [`ELAPSED_UNIT_MICROS`].
+    Elapsed(u8),
+}
+
+/// Producer side of the timestamps, owned by the
+/// [`InputDispatcher`](crate::input::InputDispatcher).
+///
+/// Time is measured with the [`Clock`] given to
+/// [`set_clock`](TimestampEncoder::set_clock), the system clock otherwise.
+#[derive(Debug)]
+pub(crate) struct TimestampEncoder {
+    origin: TimeOrigin,
+    clock: InputClock,
+    /// The time of the clock at which `base` microseconds had elapsed since
+    /// the origin.
+    clock_start: Instant,
+    base: u64,
+    /// The time of the last stamp, as decoded by the consumer.
+    last: Option<u64>,
+}
+
+impl Default for TimestampEncoder {
+    fn default() -> Self {
+        Self::new(TimeOrigin::default())
+    }
+}
+
+impl TimestampEncoder {
+    pub(crate) fn new(origin: TimeOrigin) -> Self {
+        Self {
+            origin,
+            clock: InputClock::default(),
+            clock_start: origin.0,
+            base: 0,
+            last: None,
+        }
+    }
+
+    pub(crate) fn origin(&self) -> TimeOrigin {
+        self.origin
+    }
+
+    /// Measure the time with `clock` from now on, continuing from the time
+    /// measured so far, if any stamp has been sent.
+    pub(crate) fn set_clock(&mut self, clock: impl Clock) {
+        self.base = match self.last {
+            Some(_) => self.now(),
+            None => 0,
+        };
+        self.clock = InputClock::new(clock);
+        self.clock_start = self.clock.now();
+    }
+
+    /// The microseconds elapsed since the origin.
+    fn now(&self) -> u64 {
+        let elapsed = self.clock.now().saturating_duration_since(self.clock_start);
+        self.base + elapsed.as_micros() as u64
+    }
+
+    /// Send a full stamp before the next event, e.g. after the previous
+    /// stamps may have been lost.
+    pub(crate) fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// # Returns
+    /// The stamp of an event received now.
+    ///
+    /// The elapsed time is rounded down, so that an event is never stamped
+    /// later than it has been received at.
+    pub(crate) fn stamp(&mut self) -> Stamp {
+        let micros = self.now();
+        if let Some(last) = self.last {
+            let elapsed = micros.saturating_sub(last) / ELAPSED_UNIT_MICROS;
+            if elapsed <= MAX_ELAPSED {
+                self.last = Some(last + elapsed * ELAPSED_UNIT_MICROS);
+                return Stamp::Elapsed(elapsed as u8);
+            }
+        }
+        self.last = Some(micros);
+        Stamp::Full(micros as u32 & TIMESTAMP_MASK)
+    }
+
+    /// # Returns
+    /// The full stamp to send if none has been sent for too long.
+    pub(crate) fn keep_alive(&mut self) -> Option<u32> {
+        let micros = self.now();
+        match self.last {
+            Some(last) if micros.saturating_sub(last) < KEEP_ALIVE_MICROS => None,
+            _ => {
+                self.last = Some(micros);
+                Some(micros as u32 & TIMESTAMP_MASK)
+            }
+        }
+    }
+}
+
+/// Consumer side of the timestamps, owned by the
+/// [`InputSnapshot`](crate::input::InputSnapshot).
+#[derive(Debug, Default)]
+pub(crate) struct TimestampDecoder {
+    origin: TimeOrigin,
+    micros: u64,
+}
+
+impl TimestampDecoder {
+    pub(crate) fn new(origin: TimeOrigin) -> Self {
+        Self { origin, micros: 0 }
+    }
+
+    /// Advance to the received full `stamp`, which is never behind the
+    /// current one.
+    pub(crate) fn decode(&mut self, stamp: u32) {
+        let elapsed = stamp.wrapping_sub(self.micros as u32) & TIMESTAMP_MASK;
+        self.micros += elapsed as u64;
+    }
+
+    /// The full stamp of the current timestamp.
+    pub(crate) fn stamp(&self) -> u32 {
+        self.micros as u32 & TIMESTAMP_MASK
+    }
+
+    /// The timestamp of the last decoded stamp.
+    pub(crate) fn current(&self) -> Instant {
+        self.origin.0 + Duration::from_micros(self.micros)
+    }
+
+    /// A decoder with the same origin, starting over from it.
+    pub(crate) fn restarted(&self) -> Self {
+        Self::new(self.origin)
+    }
+}
@@ -0,0 +1,211 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub use winit::event::TouchPhase;
+
+use crate::sync::TriCell;
+
+/// The maximum amount of touches tracked at once, any further touch is
+/// ignored until one of them ends.
+pub const MAX_TOUCHES: usize = 10;
+
+/// The state of every tracked touch in a frame.
+pub(crate) type TouchPoints = [Option<TouchPoint>; MAX_TOUCHES];
+
+/// A finger, or a pen on platforms that report pens as touches, in contact
+/// with the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TouchPoint {
+    /// Unique for as long as the touch lasts, but may be reused afterwards.
+    pub id: u64,
+    /// [`TouchPhase::Started`] or [`TouchPhase::Ended`] if the touch has
+    /// respectively started or ended in this frame, even if it has also moved.
+    ///
+    /// A touch that both started and ended in the same frame is
+    /// [`TouchPhase::Ended`].
+    pub phase: TouchPhase,
+    /// The latest position in physical pixels.
+    pub position: (f64, f64),
+    /// The normalised pressure in `0.0..=1.0`, if supported by the device.
+    pub force: Option<f64>,
+    /// Whether this is the first touch of a gesture, started while no other
+    /// touch was tracked.
+    pub primary: bool,
+}
+
+impl TouchPoint {
+    /// Whether the touch is still in contact.
+    pub fn is_active(&self) -> bool {
+        matches!(self.phase, TouchPhase::Started | TouchPhase::Moved)
+    }
+}
+
+/// Touch options requested by the consumer thread.
+#[derive(Debug, Default)]
+pub struct TouchOptions {
+    emulate_mouse: AtomicBool,
+}
+
+impl TouchOptions {
+    pub fn emulates_mouse(&self) -> bool {
+        self.emulate_mouse.load(Ordering::Relaxed)
+    }
+
+    /// Emulate [`MouseButton::Left`](crate::input::MouseButton::Left) and the
+    /// cursor position from the primary touch.
+    pub fn set_emulate_mouse(&self, emulate: bool) {
+        self.emulate_mouse.store(emulate, Ordering::Relaxed);
+    }
+}
+
+/// Read-only view of the touches, as received from the window thread and
+/// synchronised once per frame.
+#[derive(Debug, Default)]
+pub struct Touches {
+    pub(crate) points: TriCell<TouchPoints>,
+}
+
+impl Touches {
+    /// Every touch of this frame, including those that have ended in it.
+    pub fn iter(&self) -> impl Iterator<Item = TouchPoint> + '_ {
+        self.points.get().into_iter().flatten()
+    }
+
+    pub fn get(&self, id: u64) -> Option<TouchPoint> {
+        self.iter().find(|point| point.id == id)
+    }
+
+    pub fn primary(&self) -> Option<TouchPoint> {
+        self.iter().find(|point| point.primary)
+    }
+
+    /// The amount of touches still in contact.
+    pub fn active(&self) -> usize {
+        self.iter().filter(TouchPoint::is_active).count()
+    }
+}
+
+/// Producer side of the touches, owned by the
+/// [`InputDispatcher`](crate::input::InputDispatcher).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TouchTracker {
+    points: TouchPoints,
+}
+
+impl TouchTracker {
+    pub(crate) fn points(&self) -> TouchPoints {
+        self.points
+    }
+
+    /// Apply a touch change.
+    ///
+    /// # Returns
+    /// The updated touch, or [`None`] if it is not tracked, e.g. if too many
+    /// touches are already.
+    pub(crate) fn update(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: (f64, f64),
+        force: Option<f64>,
+    ) -> Option<TouchPoint> {
+        let slot = match self
+            .points
+            .iter()
+            .position(|p| p.is_some_and(|p| p.id == id))
+        {
+            Some(slot) => slot,
+            None if phase == TouchPhase::Started => self.points.iter().position(Option::is_none)?,
+            None => return None,
+        };
+
+        let point = match (&mut self.points[slot], phase) {
+            (Some(point), TouchPhase::Moved) => {
+                point.position = position;
+                point.force = force;
+                *point
+            }
+            (Some(point), _) => {
+                point.phase = phase;
+                point.position = position;
+                point.force = force;
+                *point
+            }
+            (None, _) => {
+                let primary = !self.points.iter().flatten().any(TouchPoint::is_active);
+                let point = TouchPoint {
+                    id,
+                    phase,
+                    position,
+                    force,
+                    primary,
+                };
+                self.points[slot] = Some(point);
+                point
+            }
+        };
+        Some(point)
+    }
+
+    /// Drop the touches that have ended and mark those that have started as
+    /// held, for the next frame.
+    pub(crate) fn next_frame(&mut self) {
+        for slot in self.points.iter_mut() {
+            match slot {
+                Some(point) if !point.is_active() => *slot = None,
+                Some(point) => point.phase = TouchPhase::Moved,
+                None => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::{InputHarness, MouseButton, TouchPhase};
+
+    #[test]
+    fn touches_and_mouse_emulation() {
+        let mut input = InputHarness::new();
+        input.state().touch_options().set_emulate_mouse(true);
+
+        input
+            .dispatcher()
+            .push_touch(1, TouchPhase::Started, (10.0, 20.0), Some(0.5));
+        input
+            .dispatcher()
+            .push_touch(2, TouchPhase::Started, (50.0, 20.0), None);
+        input
+            .dispatcher()
+            .push_touch(1, TouchPhase::Moved, (12.0, 22.0), Some(0.75));
+
+        let state = input.step();
+        let touches = state.touches();
+        assert_eq!(touches.active(), 2);
+        let primary = touches.primary().unwrap();
+        assert_eq!(primary.id, 1);
+        assert_eq!(primary.phase, TouchPhase::Started);
+        assert_eq!(primary.position, (12.0, 22.0));
+        assert_eq!(primary.force, Some(0.75));
+        assert!(!touches.get(2).unwrap().primary);
+        assert!(state.keys().mouse_pressed(MouseButton::Left));
+        assert_eq!(state.cursor().current(), (12.0, 22.0));
+        assert_eq!(state.cursor().delta(), (12.0, 22.0));
+
+        input
+            .dispatcher()
+            .push_touch(2, TouchPhase::Ended, (55.0, 20.0), None);
+        let state = input.step();
+        assert_eq!(state.touches().get(1).unwrap().phase, TouchPhase::Moved);
+        assert_eq!(state.touches().get(2).unwrap().phase, TouchPhase::Ended);
+        assert!(state.keys().mouse_held(MouseButton::Left, 0));
+
+        input
+            .dispatcher()
+            .push_touch(1, TouchPhase::Cancelled, (12.0, 22.0), None);
+        let state = input.step();
+        assert_eq!(state.touches().active(), 0);
+        assert!(state.keys().mouse_released(MouseButton::Left));
+
+        assert_eq!(input.step().touches().iter().count(), 0);
+    }
+}
@@ -16,11 +16,13 @@ pub mod jobs;
 pub mod sync;
 
 #[cfg(all(feature = "render", feature = "state"))]
-pub fn run<Init, State, Render>(mut context: Context<Init, State, Render>)
-where
+pub fn run<Init, State, Render, C, const SLOTS: usize, const SECTIONS: usize>(
+    mut context: Context<Init, State, Render, C, SLOTS, SECTIONS>,
+) where
     Init: Setup<State, Render>,
     State: Update + Default + Sync + Send + 'static,
     Render: Draw + Default,
+    C: Clock,
 {
     let ev_loop = EventLoop::new().unwrap();
     ev_loop.set_control_flow(ControlFlow::Poll);
@@ -34,7 +36,7 @@ use std::{
 };
 
 #[cfg(feature = "state")]
-use context::{Context, Setup, Update};
+use context::{Clock, Context, Setup, Update};
 
 #[cfg(all(feature = "state", feature = "render"))]
 use context::Draw;
@@ -42,10 +44,11 @@ use context::Draw;
 use winit::event_loop::{ControlFlow, EventLoop};
 
 #[cfg(all(not(feature = "render"), feature = "state"))]
-pub fn run<Init, State>(mut _context: Context<Init, State>)
+pub fn run<Init, State, C>(mut _context: Context<Init, State, C>)
 where
     Init: Setup<State>,
     State: Update + Default,
+    C: Clock,
 {
     unimplemented!("headless runtime is not implemented")
 }
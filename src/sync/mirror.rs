@@ -1,66 +1,259 @@
 use std::{
     ops::Deref,
-    ptr::NonNull,
-    sync::{
-        Arc,
-        atomic::{AtomicU64, AtomicUsize, Ordering},
-    },
+    sync::{Arc, PoisonError, atomic::Ordering},
+    time::{Duration, Instant},
+};
+
+#[cfg(not(loom))]
+use crate::sync::primitives::CellAccess;
+use crate::sync::primitives::{
+    AtomicU64, AtomicUsize, Condvar, Mutex, MutexGuard, UnsafeCell, spin_loop,
 };
 
+/// How many times to spin on a held [`SequentialLock`] before parking, as it
+/// is usually only held for as long as a value is cloned.
+const SPIN_LIMIT: u32 = if cfg!(loom) { 1 } else { 64 };
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum SyncError {
-    TimeoutExceeded { exceed_time_ns: u128 },
+    /// The timeout has passed, by `exceed_time_ns` nanoseconds by the time
+    /// the operation gave up.
+    TimeoutExceeded {
+        exceed_time_ns: u128,
+    },
     Locked,
 }
 
+impl SyncError {
+    fn timeout_exceeded(start: Instant, timeout: Duration) -> Self {
+        Self::TimeoutExceeded {
+            exceed_time_ns: start.elapsed().saturating_sub(timeout).as_nanos(),
+        }
+    }
+}
+
 pub type SyncResult = Result<(), SyncError>;
 
-#[derive(Debug, Default, Clone)]
-pub struct SequentialLock(Arc<AtomicU64>);
+#[derive(Debug)]
+struct LockState {
+    seq: AtomicU64,
+    /// The amount of threads parked, or about to be, waiting for the
+    /// sequence number to change.
+    waiters: AtomicUsize,
+    parking: Mutex<()>,
+    unparked: Condvar,
+}
+
+#[derive(Debug, Clone)]
+pub struct SequentialLock(Arc<LockState>);
+
+impl Default for SequentialLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl SequentialLock {
     pub fn new() -> Self {
-        Self(Arc::new(AtomicU64::new(0)))
+        Self(Arc::new(LockState {
+            seq: AtomicU64::new(0),
+            waiters: AtomicUsize::new(0),
+            parking: Mutex::new(()),
+            unparked: Condvar::new(),
+        }))
     }
 
-    pub fn lock(&self) {
-        let mut seq = self.0.load(Ordering::Relaxed);
+    /// Take exclusive access, spinning for a short while and then parking
+    /// until it is available.
+    ///
+    /// # Returns
+    /// The sequence number before locking.
+    pub fn lock(&self) -> u64 {
+        self.acquire(None)
+            .expect("locking without a deadline cannot time out")
+    }
+
+    /// Same as [`SequentialLock::lock`], giving up at the `deadline`.
+    ///
+    /// # Returns
+    /// The sequence number before locking, or [`None`] if the deadline has
+    /// passed.
+    pub fn lock_until(&self, deadline: Instant) -> Option<u64> {
+        self.acquire(Some(deadline))
+    }
+
+    fn acquire(&self, deadline: Option<Instant>) -> Option<u64> {
+        let mut spins = 0;
+        let mut seq = self.0.seq.load(Ordering::Relaxed);
         loop {
             // even lock number = unlocked, else it's locked
-            if seq % 2 == 0 {
-                match self.0.compare_exchange_weak(
+            if seq.is_multiple_of(2) {
+                match self.0.seq.compare_exchange_weak(
                     seq,
                     seq + 1,
                     Ordering::Acquire,
                     Ordering::Relaxed,
                 ) {
-                    Ok(_) => break,
+                    Ok(_) => return Some(seq),
                     Err(real) => seq = real,
                 }
+            } else if spins < SPIN_LIMIT {
+                spins += 1;
+                spin_loop();
+                seq = self.0.seq.load(Ordering::Relaxed);
             } else {
-                std::hint::spin_loop();
-                seq = self.0.load(Ordering::Relaxed)
+                if !self.wait(seq, deadline) {
+                    return None;
+                }
+                seq = self.0.seq.load(Ordering::Relaxed);
             }
         }
     }
 
+    /// Take exclusive access if it is available right away.
+    ///
+    /// # Returns
+    /// The sequence number before locking, or [`None`] if it is locked.
+    pub fn try_lock(&self) -> Option<u64> {
+        let seq = self.0.seq.load(Ordering::Relaxed);
+        if !seq.is_multiple_of(2) {
+            return None;
+        }
+        self.0
+            .seq
+            .compare_exchange(seq, seq + 1, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+    }
+
+    /// Release exclusive access, publishing a new sequence number.
     pub fn unlock(&self) -> u64 {
-        self.0.fetch_add(1, Ordering::Release) + 1
+        let seq = self.0.seq.fetch_add(1, Ordering::Release) + 1;
+        self.wake();
+        seq
+    }
+
+    /// Release exclusive access without publishing anything, restoring the
+    /// sequence number `seq` returned when locking.
+    pub fn restore(&self, seq: u64) {
+        self.0.seq.store(seq, Ordering::Release);
+        self.wake();
     }
 
     pub fn get(&self, ordering: Ordering) -> u64 {
-        self.0.load(ordering)
+        self.0.seq.load(ordering)
+    }
+
+    /// Park the thread for as long as the sequence number is `seq`, like a
+    /// futex would, or until the `deadline` if there is one.
+    ///
+    /// # Returns
+    /// Whether the sequence number has changed, rather than the deadline
+    /// having passed.
+    pub fn wait(&self, seq: u64, deadline: Option<Instant>) -> bool {
+        let state = &*self.0;
+        state.waiters.fetch_add(1, Ordering::AcqRel);
+
+        let mut parking = state.parking.lock().unwrap_or_else(PoisonError::into_inner);
+        let changed = loop {
+            if state.seq.load(Ordering::Acquire) != seq {
+                break true;
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    if timeout.is_zero() {
+                        break false;
+                    }
+                    parking = state
+                        .unparked
+                        .wait_timeout(parking, timeout)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0;
+                }
+                None => {
+                    parking = state
+                        .unparked
+                        .wait(parking)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        };
+        drop(parking);
+
+        state.waiters.fetch_sub(1, Ordering::Relaxed);
+        changed
+    }
+
+    /// Unpark the threads [`waiting`](SequentialLock::wait) for the sequence
+    /// number to change, if there are any.
+    fn wake(&self) {
+        // read-modify-writes always read the latest value, so either a
+        // waiter is seen here, or it has acquired the new sequence number
+        // along with this release
+        if self.0.waiters.fetch_add(0, Ordering::AcqRel) == 0 {
+            return;
+        }
+
+        // a waiter holds the lock from checking the sequence number until it
+        // is parked, so that it cannot miss the notification
+        drop(
+            self.0
+                .parking
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        self.0.unparked.notify_all();
+    }
+}
+
+/// Restores the [`SequentialLock`] if the shared value panics while it is
+/// being cloned, so that the other mirrors are not locked out forever.
+struct RestoreGuard<'a> {
+    seq_lock: &'a SequentialLock,
+    seq: u64,
+}
+
+impl Drop for RestoreGuard<'_> {
+    fn drop(&mut self) {
+        self.seq_lock.restore(self.seq);
     }
 }
 
+/// Identifies a listener added through [`Mirror::on_change`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ListenerId(u64);
+
+type ChangeListener<T> = Box<dyn Fn(&T) + Send + Sync>;
+
+struct Listeners<T> {
+    next_id: u64,
+    listeners: Vec<(ListenerId, ChangeListener<T>)>,
+    /// The version of the last value the listeners have been called with.
+    notified: u64,
+}
+
+impl<T> std::fmt::Debug for Listeners<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Listeners")
+            .field("len", &self.listeners.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A local copy of a value shared between threads, published and
+/// synchronised explicitly.
+///
+/// The shared value is only ever cloned, swapped or dropped as a whole, so
+/// any [`Clone`] type may be mirrored, including ones owning heap memory.
 #[derive(Debug)]
 pub struct Mirror<T: Clone + std::fmt::Debug> {
     local: T,
     version: u64,
 
-    inner: NonNull<T>,
+    inner: Arc<UnsafeCell<T>>,
     seq_lock: SequentialLock,
-    counter: Arc<AtomicUsize>,
+    listeners: Arc<Mutex<Listeners<T>>>,
 }
 
 impl<T: Default + Clone + Send + Sync + std::fmt::Debug> Default for Mirror<T> {
@@ -71,61 +264,77 @@ impl<T: Default + Clone + Send + Sync + std::fmt::Debug> Default for Mirror<T> {
 
 impl<T: Clone + Send + Sync + std::fmt::Debug> Clone for Mirror<T> {
     fn clone(&self) -> Self {
-        self.counter.fetch_add(1, Ordering::Acquire);
         Self {
             local: self.local.clone(),
             version: self.version,
-            inner: self.inner,
+            inner: self.inner.clone(),
             seq_lock: self.seq_lock.clone(),
-            counter: self.counter.clone(),
-        }
-    }
-}
-
-impl<T: Clone + std::fmt::Debug> Drop for Mirror<T> {
-    fn drop(&mut self) {
-        if self.counter.fetch_sub(1, Ordering::Release) == 1 {
-            std::sync::atomic::fence(Ordering::Acquire);
-            drop(unsafe { Box::from_raw(self.inner.as_ptr()) });
+            listeners: self.listeners.clone(),
         }
     }
 }
 
 impl<T: Clone + Send + Sync + std::fmt::Debug> Mirror<T> {
     pub fn new(value: T) -> Self {
-        let local = value.clone();
-        let inner = Box::leak(Box::from(value));
-        let seq_lock = SequentialLock::new();
-        let counter = Arc::new(AtomicUsize::new(1));
-
         Self {
-            local,
+            local: value.clone(),
             version: 0,
-            inner: NonNull::from(inner),
-            seq_lock,
-            counter,
+            inner: Arc::new(UnsafeCell::new(value)),
+            seq_lock: SequentialLock::new(),
+            listeners: Arc::new(Mutex::new(Listeners {
+                next_id: 0,
+                listeners: Vec::new(),
+                notified: 0,
+            })),
         }
     }
 
-    /// Mutate the inner value with an `operation ` and publish it.
+    /// Call `listener` with every value published from now on, by any of
+    /// the mirrors of the same shared value, so that it does not need to be
+    /// polled.
     ///
-    /// See [`Mirror::publish`].
+    /// Listeners are called on the publishing thread, once the value has
+    /// been published, and must not publish to nor add or remove listeners
+    /// of the same shared value.
     ///
-    /// The `operation` is called once this mirror instance has ensured
-    /// exclusive access to the underlying data.
-    pub fn publish_with<F: FnOnce(&mut T)>(&mut self, operation: F) {
-        self.seq_lock.lock();
+    /// Values are seen in the order they were published in. A value published
+    /// concurrently with a newer one may be skipped if the newer one has been
+    /// seen first.
+    pub fn on_change<F: Fn(&T) + Send + Sync + 'static>(&self, listener: F) -> ListenerId {
+        let mut listeners = self.lock_listeners();
+        let id = ListenerId(listeners.next_id);
+        listeners.next_id += 1;
+        listeners.listeners.push((id, Box::new(listener)));
+        id
+    }
 
-        operation(&mut self.local);
+    /// Remove a listener added through [`Mirror::on_change`].
+    ///
+    /// # Returns
+    /// Whether the listener was found.
+    pub fn remove_listener(&self, id: ListenerId) -> bool {
+        let mut listeners = self.lock_listeners();
+        let len = listeners.listeners.len();
+        listeners.listeners.retain(|(listener, _)| *listener != id);
+        listeners.listeners.len() != len
+    }
 
-        // SAFETY: we ensure the underlying pointer is unused by ensuring the
-        // shared SequentialLock is on an EVEN value; which indicates it is
-        // under no exclusive access and safe to write to.
-        unsafe {
-            std::ptr::copy_nonoverlapping(&self.local, self.inner.as_ptr(), 1);
-        }
+    fn lock_listeners(&self) -> MutexGuard<'_, Listeners<T>> {
+        self.listeners
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
 
-        self.version = self.seq_lock.unlock();
+    /// Mutate the local value with an `operation` and publish it.
+    ///
+    /// See [`Mirror::publish`].
+    ///
+    /// The `operation` only ever sees the local value, so it is called
+    /// without holding exclusive access to the shared one.
+    pub fn publish_with<F: FnOnce(&mut T)>(&mut self, operation: F) {
+        operation(&mut self.local);
+        let value = self.local.clone();
+        self.swap_shared(value);
     }
 
     /// Publish a new `value` to the underlying shared pointer.
@@ -144,23 +353,43 @@ impl<T: Clone + Send + Sync + std::fmt::Debug> Mirror<T> {
     /// In the case of single-producer scenarios, the producer will never
     /// require an explicit [`Mirror::sync`] call.
     pub fn publish(&mut self, value: T) {
+        self.local = value;
+        let value = self.local.clone();
+        self.swap_shared(value);
+    }
+
+    /// Replace the shared value with `value`, which must be a clone of the
+    /// local one.
+    ///
+    /// The value is cloned beforehand and the replaced one dropped
+    /// afterwards, so that no user code runs under exclusive access.
+    fn swap_shared(&mut self, value: T) {
         self.seq_lock.lock();
 
-        // SAFETY: we ensure the underlying pointer is unused by ensuring the
-        // shared SequentialLock is on an EVEN value; which indicates it is
-        // under no exclusive access and safe to write to.
-        unsafe {
-            std::ptr::copy_nonoverlapping(&value, self.inner.as_ptr(), 1);
-        }
+        // SAFETY: the shared value is only ever accessed under exclusive
+        // access of the SequentialLock, which is held until unlocking.
+        let previous = self
+            .inner
+            .with_mut(|inner| unsafe { std::mem::replace(&mut *inner, value) });
 
-        self.local = value;
         self.version = self.seq_lock.unlock();
+        drop(previous);
+
+        // another publisher may have notified a newer value in the meantime
+        let mut listeners = self.lock_listeners();
+        if listeners.notified > self.version {
+            return;
+        }
+        listeners.notified = self.version;
+        for (_, listener) in &listeners.listeners {
+            listener(&self.local);
+        }
     }
 
     /// Checks whether the [`Mirror`] is up-to-date with the other accessors.
     pub fn check_sync_status(&self) -> bool {
         let seq = self.seq_lock.get(Ordering::Acquire);
-        self.version == seq && seq % 2 == 0
+        self.version == seq && seq.is_multiple_of(2)
     }
 
     /// Attempt to synchronise without ever blocking.
@@ -178,40 +407,18 @@ impl<T: Clone + Send + Sync + std::fmt::Debug> Mirror<T> {
     /// returned.
     /// Otherwise, [`Ok`] is returned.
     pub fn sync_noblock(&mut self) -> SyncResult {
-        loop {
-            let seq_0 = self.seq_lock.get(Ordering::Acquire);
-
-            if seq_0 % 2 != 0 {
-                return Err(SyncError::Locked);
-            }
-
-            // up-to-date check
-            if self.version == seq_0 {
-                return Ok(());
-            }
-
-            // SAFETY: we ensure the underlying pointer is unused by ensuring the
-            // shared SequentialLock is on an EVEN value; which indicates it is
-            // under no exclusive access and safe to write to.
-            unsafe {
-                std::ptr::copy_nonoverlapping(self.inner.as_ptr(), &mut self.local, 1);
-            }
-
-            // ensure prior operations (copy from shared to local) is not
-            // reordered before the next version load.
-            std::sync::atomic::fence(Ordering::Acquire);
-            let seq_1 = self.seq_lock.get(Ordering::Acquire);
-
-            // value is guaranteed to not have changed since sync started.
-            if seq_0 == seq_1 {
-                self.version = seq_1;
-                break;
-            }
+        let seq = self.seq_lock.get(Ordering::Acquire);
+        if !seq.is_multiple_of(2) {
+            return Err(SyncError::Locked);
+        }
 
-            // value changed while syncing: re-sync
-            continue;
+        // up-to-date check
+        if self.version == seq {
+            return Ok(());
         }
 
+        let seq = self.seq_lock.try_lock().ok_or(SyncError::Locked)?;
+        self.read_shared(seq);
         Ok(())
     }
 
@@ -227,42 +434,102 @@ impl<T: Clone + Send + Sync + std::fmt::Debug> Mirror<T> {
     /// # Returns
     /// This operation cannot fail. An [`Ok`] is always returned.
     pub fn sync(&mut self) -> SyncResult {
-        loop {
-            let seq_0 = self.seq_lock.get(Ordering::Acquire);
+        let seq = self.seq_lock.get(Ordering::Acquire);
 
-            if seq_0 % 2 != 0 {
-                std::hint::spin_loop();
-                continue;
-            }
+        // up-to-date check
+        if self.version == seq {
+            return Ok(());
+        }
 
-            // up-to-date check
-            if self.version == seq_0 {
-                return Ok(());
-            }
+        let seq = self.seq_lock.lock();
+        self.read_shared(seq);
+        Ok(())
+    }
 
-            // SAFETY: we ensure the underlying pointer is unused by ensuring the
-            // shared SequentialLock is on an EVEN value; which indicates it is
-            // under no exclusive access and safe to write to.
-            unsafe {
-                std::ptr::copy_nonoverlapping(self.inner.as_ptr(), &mut self.local, 1);
-            }
+    /// Same as [`Mirror::sync`], giving up once the shared state has been
+    /// under exclusive access for longer than `timeout`.
+    ///
+    /// # Returns
+    /// A [`SyncError::TimeoutExceeded`] if the timeout has passed before the
+    /// mirror could synchronise.
+    /// Otherwise, [`Ok`] is returned.
+    pub fn sync_timeout(&mut self, timeout: Duration) -> SyncResult {
+        let seq = self.seq_lock.get(Ordering::Acquire);
 
-            // ensure prior operations (copy from shared to local) is not
-            // reordered before the next version load.
-            std::sync::atomic::fence(Ordering::Acquire);
-            let seq_1 = self.seq_lock.get(Ordering::Acquire);
+        // up-to-date check
+        if self.version == seq {
+            return Ok(());
+        }
 
-            // value is guaranteed to not have changed since sync started.
-            if seq_0 == seq_1 {
-                self.version = seq_1;
-                break;
-            }
+        let start = Instant::now();
+        let seq = self
+            .seq_lock
+            .lock_until(start + timeout)
+            .ok_or_else(|| SyncError::timeout_exceeded(start, timeout))?;
+        self.read_shared(seq);
+        Ok(())
+    }
 
-            // value changed while syncing: re-sync
-            continue;
+    /// Park the thread until a new value has been published by another
+    /// mirror, then synchronise with it.
+    ///
+    /// If a value has been published since the last synchronisation, this
+    /// returns right away.
+    pub fn wait_for_change(&mut self) {
+        self.wait_for_change_until(None);
+    }
+
+    /// Same as [`Mirror::wait_for_change`], giving up after `timeout`.
+    ///
+    /// # Returns
+    /// A [`SyncError::TimeoutExceeded`] if nothing has been published before
+    /// the timeout has passed.
+    /// Otherwise, [`Ok`] is returned.
+    pub fn wait_for_change_timeout(&mut self, timeout: Duration) -> SyncResult {
+        let start = Instant::now();
+        if self.wait_for_change_until(Some(start + timeout)) {
+            Ok(())
+        } else {
+            Err(SyncError::timeout_exceeded(start, timeout))
         }
+    }
 
-        Ok(())
+    /// # Returns
+    /// Whether the mirror has synchronised with a new value, rather than the
+    /// `deadline` having passed.
+    fn wait_for_change_until(&mut self, deadline: Option<Instant>) -> bool {
+        loop {
+            let seq = self.seq_lock.get(Ordering::Acquire);
+            if seq.is_multiple_of(2) && seq != self.version {
+                // another mirror may lock in between, in which case this
+                // waits for it to unlock instead
+                if let Some(seq) = self.seq_lock.try_lock() {
+                    self.read_shared(seq);
+                    return true;
+                }
+            } else if !self.seq_lock.wait(seq, deadline) {
+                return false;
+            }
+        }
+    }
+
+    /// Clone the shared value into the local one, while holding exclusive
+    /// access locked at the sequence number `seq`.
+    fn read_shared(&mut self, seq: u64) {
+        // nothing is published, the other mirrors remain in sync once the
+        // sequence number is restored, even if cloning panics
+        let _guard = RestoreGuard {
+            seq_lock: &self.seq_lock,
+            seq,
+        };
+
+        if self.version != seq {
+            // SAFETY: the shared value is only ever accessed under exclusive
+            // access, which is held until the sequence number is restored.
+            self.inner
+                .with(|inner| self.local.clone_from(unsafe { &*inner }));
+            self.version = seq;
+        }
     }
 
     /// Returns the local variable.
@@ -285,3 +552,186 @@ impl<T: Clone + Send + Sync + std::fmt::Debug> Deref for Mirror<T> {
 
 unsafe impl<T: Clone + Sync + Send + std::fmt::Debug> Sync for Mirror<T> {}
 unsafe impl<T: Clone + Send + Sync + std::fmt::Debug> Send for Mirror<T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn mirrors_sync_published_values() {
+        let mut a = Mirror::new([0u64; 4]);
+        let mut b = a.clone();
+        assert!(b.check_sync_status());
+
+        a.publish([1; 4]);
+        assert!(a.check_sync_status());
+        assert!(!b.check_sync_status());
+        assert_eq!(*b, [0; 4]);
+
+        // syncing publishes nothing, the other mirrors stay in sync
+        let seq = b.seq_lock.lock();
+        assert_eq!(b.sync_noblock(), Err(SyncError::Locked));
+        b.seq_lock.restore(seq);
+        assert_eq!(b.sync_noblock(), Ok(()));
+        assert_eq!(*b, [1; 4]);
+        assert!(a.check_sync_status());
+
+        b.publish_with(|values| values[0] = 2);
+        a.sync().unwrap();
+        assert_eq!(*a, [2, 1, 1, 1]);
+    }
+
+    #[test]
+    fn heap_values_are_owned_by_each_mirror() {
+        let mut a = Mirror::new(vec![String::from("a")]);
+        let mut b = a.clone();
+
+        a.publish(vec![String::from("b"), String::from("c")]);
+        a.publish_with(|values| values[0].push('d'));
+        b.sync().unwrap();
+        assert_eq!(*b, ["bd", "c"]);
+
+        // the mirrors no longer share anything once synchronised
+        drop(a);
+        b.publish_with(|values| values.clear());
+        let mut c = b.clone();
+        c.publish(vec![String::from("e")]);
+        b.sync().unwrap();
+        assert_eq!(*b, ["e"]);
+    }
+
+    #[test]
+    fn concurrent_heap_publish_and_sync() {
+        const VERSIONS: usize = if cfg!(miri) { 50 } else { 5_000 };
+
+        let mut reader = Mirror::new(Vec::<usize>::new());
+        let mut writer = reader.clone();
+        let publisher = thread::spawn(move || {
+            for version in 1..=VERSIONS {
+                writer.publish(vec![version; version % 8 + 1]);
+            }
+        });
+
+        let mut last = 0;
+        while last < VERSIONS {
+            reader.sync().unwrap();
+            let Some(&version) = reader.first() else {
+                thread::yield_now();
+                continue;
+            };
+            assert_eq!(reader.len(), version % 8 + 1);
+            assert!(reader.iter().all(|&value| value == version));
+            assert!(version >= last);
+            last = version;
+        }
+        publisher.join().unwrap();
+    }
+
+    #[test]
+    fn sync_and_wait_timeouts() {
+        let timeout = Duration::from_millis(5);
+        let mut a = Mirror::new(0u32);
+        let mut b = a.clone();
+        assert!(matches!(
+            b.wait_for_change_timeout(timeout),
+            Err(SyncError::TimeoutExceeded { .. })
+        ));
+
+        a.publish(1);
+        let seq = a.seq_lock.lock();
+        assert!(matches!(
+            b.sync_timeout(timeout),
+            Err(SyncError::TimeoutExceeded { .. })
+        ));
+        a.seq_lock.restore(seq);
+        assert_eq!(b.sync_timeout(timeout), Ok(()));
+        assert_eq!(*b, 1);
+
+        // a value published before waiting counts as a change
+        a.publish(2);
+        assert_eq!(b.wait_for_change_timeout(timeout), Ok(()));
+        assert_eq!(*b, 2);
+    }
+
+    #[test]
+    fn wait_for_change_parks_until_published() {
+        let mut reader = Mirror::new(String::new());
+        let mut writer = reader.clone();
+
+        let publisher = thread::spawn(move || {
+            // hold exclusive access for a while, parking the reader on it
+            let seq = writer.seq_lock.lock();
+            thread::sleep(Duration::from_millis(5));
+            writer.seq_lock.restore(seq);
+            writer.publish(String::from("changed"));
+        });
+
+        reader.wait_for_change();
+        assert_eq!(*reader, "changed");
+        publisher.join().unwrap();
+        assert!(reader.check_sync_status());
+    }
+
+    #[test]
+    fn change_listeners() {
+        let a = Mirror::new(0u32);
+        let mut b = a.clone();
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let id = {
+            let seen = seen.clone();
+            a.on_change(move |&value| seen.lock().unwrap().push(value))
+        };
+
+        let publisher = thread::spawn(move || {
+            b.publish(1);
+            b.publish_with(|value| *value += 1);
+            b
+        });
+        let mut b = publisher.join().unwrap();
+        assert_eq!(*seen.lock().unwrap(), [1, 2]);
+
+        assert!(a.remove_listener(id));
+        assert!(!a.remove_listener(id));
+        b.publish(3);
+        assert_eq!(*seen.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn concurrent_publish_and_sync() {
+        const VERSIONS: u64 = if cfg!(miri) { 50 } else { 5_000 };
+
+        let mut reader = Mirror::new([0u64; 4]);
+        let mut writer = reader.clone();
+        let publisher = thread::spawn(move || {
+            for version in 1..=VERSIONS {
+                writer.publish([version; 4]);
+                if version % 8 == 0 {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut last = 0;
+        while last < VERSIONS {
+            let synced = if last % 2 == 0 {
+                reader.sync()
+            } else {
+                reader.sync_noblock()
+            };
+            if synced.is_err() {
+                thread::yield_now();
+                continue;
+            }
+
+            let values = *reader.get();
+            assert!(values.iter().all(|&value| value == values[0]));
+            assert!(values[0] >= last);
+            last = values[0];
+        }
+        publisher.join().unwrap();
+        assert!(reader.check_sync_status());
+    }
+}
@@ -1,5 +1,12 @@
 pub mod mirror;
+mod primitives;
+pub mod ring;
+pub mod stream;
 pub mod tricell;
+pub mod triple_buffer;
 
 pub use mirror::Mirror;
+pub use ring::RingBuffer;
+pub use stream::{EventStream, Packet};
 pub use tricell::TriCell;
+pub use triple_buffer::TripleBuffer;
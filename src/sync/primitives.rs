@@ -0,0 +1,44 @@
+//! The atomics and cells the lock-free primitives are built on, which are
+//! swapped for those of [`loom`](https://docs.rs/loom) when building with
+//! `--cfg loom`, so that the primitives can be model-checked.
+
+#[cfg(loom)]
+pub(crate) use loom::{
+    cell::UnsafeCell,
+    hint::spin_loop,
+    sync::{
+        Condvar, Mutex, MutexGuard,
+        atomic::{AtomicU8, AtomicU32, AtomicU64, AtomicUsize},
+    },
+};
+#[cfg(not(loom))]
+pub(crate) use std::{
+    cell::UnsafeCell,
+    hint::spin_loop,
+    sync::{
+        Condvar, Mutex, MutexGuard,
+        atomic::{AtomicU8, AtomicU32, AtomicU64, AtomicUsize},
+    },
+};
+
+/// The closure-based access of `loom`'s [`UnsafeCell`], through which it
+/// tracks every access.
+#[cfg(not(loom))]
+pub(crate) trait CellAccess<T> {
+    fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R;
+
+    fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R;
+}
+
+#[cfg(not(loom))]
+impl<T> CellAccess<T> for UnsafeCell<T> {
+    #[inline(always)]
+    fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.get())
+    }
+
+    #[inline(always)]
+    fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.get())
+    }
+}
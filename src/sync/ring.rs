@@ -0,0 +1,230 @@
+use std::sync::{Arc, atomic::Ordering};
+
+#[cfg(not(loom))]
+use crate::sync::primitives::CellAccess;
+use crate::sync::primitives::{AtomicUsize, UnsafeCell};
+
+/// A bounded queue of up to `N` values, split into a [`Writer`] and a
+/// [`Reader`].
+///
+/// Neither half ever blocks: pushing to a full queue hands the value back to
+/// the writer instead, so that it can account for it being dropped.
+///
+/// Unlike [`EventStream`](crate::sync::EventStream), the values are moved
+/// through the queue as they are and may be of any type.
+#[derive(Debug)]
+pub struct RingBuffer<T, const N: usize> {
+    slots: [UnsafeCell<Option<T>>; N],
+    /// The position of the next value to be read, only written by the
+    /// reader.
+    ///
+    /// Both positions wrap around at `2 * N`, so that a full queue can be
+    /// told apart from an empty one.
+    head: AtomicUsize,
+    /// The position of the next value to be written, only written by the
+    /// writer.
+    tail: AtomicUsize,
+}
+
+impl<T, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> RingBuffer<T, N> {
+    pub fn new() -> Self {
+        const { assert!(N > 0, "a ring buffer needs at least one slot") };
+        Self {
+            slots: std::array::from_fn(|_| UnsafeCell::new(None)),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn split(self) -> (Writer<T, N>, Reader<T, N>) {
+        let buffer = Arc::new(self);
+        let writer = Writer {
+            buffer: buffer.clone(),
+        };
+        let reader = Reader { buffer };
+        (writer, reader)
+    }
+
+    #[inline(always)]
+    fn next(position: usize) -> usize {
+        (position + 1) % (2 * N)
+    }
+
+    #[inline(always)]
+    fn len(head: usize, tail: usize) -> usize {
+        (tail + 2 * N - head) % (2 * N)
+    }
+}
+
+/// The writing half of a [`RingBuffer`].
+#[derive(Debug)]
+pub struct Writer<T, const N: usize> {
+    buffer: Arc<RingBuffer<T, N>>,
+}
+
+// SAFETY: a slot is only ever accessed by the writer while it is outside of
+// the readable range, and by the reader while it is inside of it, with the
+// range changing hands through the release and acquire of the positions.
+unsafe impl<T: Send, const N: usize> Send for Writer<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for Writer<T, N> {}
+
+impl<T, const N: usize> Writer<T, N> {
+    /// Push `value` to the back of the queue.
+    ///
+    /// # Returns
+    /// `value` back if the queue is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let buffer = &*self.buffer;
+        let tail = buffer.tail.load(Ordering::Relaxed);
+        // acquire the reader's last accesses to the slot about to be reused
+        let head = buffer.head.load(Ordering::Acquire);
+        if RingBuffer::<T, N>::len(head, tail) == N {
+            return Err(value);
+        }
+
+        // SAFETY: the slot is outside of the readable range, which only the
+        // writer extends
+        buffer.slots[tail % N].with_mut(|slot| unsafe { *slot = Some(value) });
+        // release the write to the slot along with it
+        buffer
+            .tail
+            .store(RingBuffer::<T, N>::next(tail), Ordering::Release);
+        Ok(())
+    }
+
+    /// Whether the reader has dropped its half, after which nothing pushed
+    /// is ever read.
+    pub fn is_orphaned(&self) -> bool {
+        Arc::strong_count(&self.buffer) == 1
+    }
+}
+
+/// The reading half of a [`RingBuffer`].
+#[derive(Debug)]
+pub struct Reader<T, const N: usize> {
+    buffer: Arc<RingBuffer<T, N>>,
+}
+
+// SAFETY: see `Writer`
+unsafe impl<T: Send, const N: usize> Send for Reader<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for Reader<T, N> {}
+
+impl<T, const N: usize> Default for Reader<T, N> {
+    /// A reader without a writer, which stays empty.
+    fn default() -> Self {
+        RingBuffer::new().split().1
+    }
+}
+
+impl<T, const N: usize> Reader<T, N> {
+    /// Pop the value at the front of the queue, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        let buffer = &*self.buffer;
+        let head = buffer.head.load(Ordering::Relaxed);
+        // acquire the write to the slot
+        let tail = buffer.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        // SAFETY: the slot is inside of the readable range, which only the
+        // reader shrinks
+        let value = buffer.slots[head % N].with_mut(|slot| unsafe { (*slot).take() });
+        // release the access to the slot back to the writer
+        buffer
+            .head
+            .store(RingBuffer::<T, N>::next(head), Ordering::Release);
+        value
+    }
+
+    /// The amount of values that can currently be popped.
+    pub fn len(&self) -> usize {
+        let head = self.buffer.head.load(Ordering::Relaxed);
+        let tail = self.buffer.tail.load(Ordering::Acquire);
+        RingBuffer::<T, N>::len(head, tail)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Pop every value currently in the queue.
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn push_until_full_and_wrap_around() {
+        let (mut writer, mut reader) = RingBuffer::<u32, 3>::new().split();
+        assert_eq!(reader.pop(), None);
+
+        for value in 0..3 {
+            assert_eq!(writer.push(value), Ok(()));
+        }
+        assert_eq!(writer.push(3), Err(3));
+        assert_eq!(reader.len(), 3);
+
+        assert_eq!(reader.pop(), Some(0));
+        assert_eq!(writer.push(4), Ok(()));
+        assert_eq!(writer.push(5), Err(5));
+        assert_eq!(reader.pop(), Some(1));
+        assert_eq!(reader.pop(), Some(2));
+        assert_eq!(reader.pop(), Some(4));
+        assert!(reader.is_empty());
+
+        // the positions wrap around several times over
+        for value in 0..10 {
+            assert_eq!(writer.push(value), Ok(()));
+            assert_eq!(writer.push(value + 100), Ok(()));
+            assert_eq!(reader.pop(), Some(value));
+            assert_eq!(reader.pop(), Some(value + 100));
+        }
+
+        writer.push(6).unwrap();
+        reader.clear();
+        assert!(reader.is_empty());
+        assert!(!writer.is_orphaned());
+        drop(reader);
+        assert!(writer.is_orphaned());
+    }
+
+    #[test]
+    fn concurrent_push_and_pop() {
+        const VALUES: usize = if cfg!(miri) { 100 } else { 20_000 };
+
+        let (mut writer, mut reader) = RingBuffer::<Vec<usize>, 8>::new().split();
+        let producer = thread::spawn(move || {
+            let mut value = 0;
+            while value < VALUES {
+                match writer.push(vec![value; value % 4 + 1]) {
+                    Ok(()) => value += 1,
+                    Err(_) => thread::yield_now(),
+                }
+            }
+        });
+
+        let mut next = 0;
+        while next < VALUES {
+            let Some(values) = reader.pop() else {
+                thread::yield_now();
+                continue;
+            };
+            assert_eq!(values, vec![next; next % 4 + 1]);
+            next += 1;
+        }
+        producer.join().unwrap();
+    }
+}
@@ -0,0 +1,630 @@
+use std::{marker::PhantomData, sync::atomic::Ordering};
+
+use crate::sync::primitives::{AtomicU32, AtomicU64};
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for u32 {}
+    impl Sealed for u64 {}
+}
+
+/// The integer an [`EventStream`] packet is packed into, either [`u32`] or
+/// [`u64`].
+pub trait PacketBits: Copy + Eq + sealed::Sealed {
+    /// How many packets fit in a single 64 bit fold.
+    const LANES: usize;
+
+    fn into_u64(self) -> u64;
+
+    /// Truncate `bits` to the packet's width.
+    fn from_u64(bits: u64) -> Self;
+}
+
+impl PacketBits for u32 {
+    const LANES: usize = 2;
+
+    #[inline(always)]
+    fn into_u64(self) -> u64 {
+        self as u64
+    }
+
+    #[inline(always)]
+    fn from_u64(bits: u64) -> Self {
+        bits as u32
+    }
+}
+
+impl PacketBits for u64 {
+    const LANES: usize = 1;
+
+    #[inline(always)]
+    fn into_u64(self) -> u64 {
+        self
+    }
+
+    #[inline(always)]
+    fn from_u64(bits: u64) -> Self {
+        bits
+    }
+}
+
+/// An event which can be sent through an [`EventStream`], packed into 32 or
+/// 64 bits.
+///
+/// A slot of zero bits is empty, so every packet must encode to non-zero
+/// bits, e.g. by reserving a non-zero type ID as [`DeltaPacket`] does.
+///
+/// [`DeltaPacket`]: crate::input::DeltaPacket
+pub trait Packet: Copy {
+    type Bits: PacketBits;
+
+    /// Pack the event, which must not be all zeros.
+    fn encode(self) -> Self::Bits;
+
+    /// Unpack the event from the non-zero `bits` it has been encoded to.
+    fn decode(bits: Self::Bits) -> Self;
+}
+
+/// A thread-safe, single producer and single consumer stream of packed
+/// events, sectioned by frame.
+///
+/// The producer writes the packets of each frame to a section of
+/// `FOLDS * P::Bits::LANES` slots, and moves on to the next one with
+/// [`frame_front`](EventStream::frame_front). The consumer reads the packets
+/// of a section with [`drain_back`](EventStream::drain_back) and moves on
+/// with [`frame_back`](EventStream::frame_back), never into the section being
+/// written, so that the sections are read in the order they were written.
+///
+/// Once a section is full, any further packet of the frame is dropped rather
+/// than overwriting the ones before it, and counted in the
+/// [`dropped`](EventStream::dropped) packets.
+#[repr(C, align(64))]
+#[derive(Debug)]
+pub struct EventStream<P: Packet, const FOLDS: usize, const SECTIONS: usize> {
+    stream: [[FoldBits<P>; FOLDS]; SECTIONS],
+
+    /// write to the front, read from the back
+    index: StreamIndex<P, FOLDS, SECTIONS>,
+
+    /// Packets dropped from each section since it has last been read.
+    overflow: [AtomicU32; SECTIONS],
+    /// Packets dropped over the whole lifetime of the stream.
+    dropped: AtomicU64,
+}
+
+impl<P: Packet, const FOLDS: usize, const SECTIONS: usize> Default
+    for EventStream<P, FOLDS, SECTIONS>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Packet, const FOLDS: usize, const SECTIONS: usize> EventStream<P, FOLDS, SECTIONS> {
+    pub fn new() -> Self {
+        // both the section-local index, which may be one past the last slot,
+        // and the section index must fit in a byte
+        const {
+            assert!(
+                StreamIndex::<P, FOLDS, SECTIONS>::SECTION_CAPACITY <= u8::MAX as usize
+                    && SECTIONS <= u8::MAX as usize + 1,
+                "event stream sections and their capacity must fit in a byte"
+            );
+            // one being written, one being read and one ready to be read
+            assert!(SECTIONS >= 3, "event streams need at least 3 sections");
+        };
+
+        let queue = { core::array::from_fn(|_| core::array::from_fn(|_| FoldBits::new(0))) };
+        Self {
+            stream: queue,
+            // the reader starts right behind the writer
+            index: StreamIndex::new(0, (SECTIONS - 1) as u8),
+            overflow: core::array::from_fn(|_| AtomicU32::new(0)),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Advance the writer to the next section.
+    ///
+    /// Any packet still left unread in that section is discarded and counted
+    /// as dropped.
+    ///
+    /// If the reader is so far behind that it is still reading the next
+    /// section, the writer stays in the current one instead, and the packets
+    /// of the next frame are added to those of this frame.
+    pub fn frame_front(&self) {
+        let Some(section) = self.index.advance_front() else {
+            return;
+        };
+
+        let unread = self.stream[section as usize]
+            .iter()
+            .map(FoldBits::clear)
+            .sum::<u32>();
+        if unread > 0 {
+            self.drop_packets(section, unread);
+        }
+    }
+
+    /// Advance the reader to the next section, unless it is still being
+    /// written.
+    ///
+    /// # Returns
+    /// `false` if the reader has caught up with the writer and has not
+    /// advanced.
+    pub fn frame_back(&self) -> bool {
+        self.index.advance_back()
+    }
+
+    /// # Returns
+    /// `false` if the current section is full and the packet has been
+    /// dropped.
+    pub fn push_front(&self, packet: P) -> bool {
+        let Some((local, section_i)) = self.index.advance_local(StreamEnd::Front) else {
+            self.drop_packets(self.index.section(StreamEnd::Front), 1);
+            return false;
+        };
+        let section = &self.stream[section_i as usize];
+
+        let lane = local as usize % P::Bits::LANES;
+        section[local as usize / P::Bits::LANES].write(lane, packet);
+        true
+    }
+
+    pub fn pop_back(&self) -> Option<P> {
+        let (local, section) = self.index.advance_local(StreamEnd::Back)?;
+        let section = &self.stream[section as usize];
+
+        let lane = local as usize % P::Bits::LANES;
+        section[local as usize / P::Bits::LANES].read(lane)
+    }
+
+    pub fn drain_back(&self) -> IterEventStream<'_, P, FOLDS, SECTIONS> {
+        let section = self.index.section(StreamEnd::Back);
+        IterEventStream {
+            index: &self.index,
+            stream: &self.stream[section as usize],
+        }
+    }
+
+    /// Take the count of packets dropped from the section being read.
+    ///
+    /// This should be called once the section has been
+    /// [`drained`](EventStream::drain_back), as the packets that were dropped
+    /// came after all of the ones that have been read.
+    pub fn take_overflow_back(&self) -> u32 {
+        self.overflow[self.index.section(StreamEnd::Back) as usize].swap(0, Ordering::AcqRel)
+    }
+
+    /// Count `count` packets as dropped from the section being written,
+    /// without them ever having been pushed, e.g. because what they refer to
+    /// could not be sent.
+    pub fn drop_front(&self, count: u32) {
+        self.drop_packets(self.index.section(StreamEnd::Front), count);
+    }
+
+    /// The total amount of packets dropped since the creation of the stream.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn drop_packets(&self, section: u8, count: u32) {
+        self.overflow[section as usize].fetch_add(count, Ordering::AcqRel);
+        self.dropped.fetch_add(count as u64, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IterEventStream<'stream, P: Packet, const FOLDS: usize, const SECTIONS: usize> {
+    index: &'stream StreamIndex<P, FOLDS, SECTIONS>,
+    stream: &'stream [FoldBits<P>; FOLDS],
+}
+
+impl<'stream, P: Packet, const FOLDS: usize, const SECTIONS: usize> Iterator
+    for IterEventStream<'stream, P, FOLDS, SECTIONS>
+{
+    type Item = P;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (local, _) = self.index.advance_local(StreamEnd::Back)?;
+
+        let lane = local as usize % P::Bits::LANES;
+        self.stream[local as usize / P::Bits::LANES].read(lane)
+    }
+}
+
+/// One of the two ends of an [`EventStream`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StreamEnd {
+    /// The end written by the producer.
+    Front,
+    /// The end read by the consumer.
+    Back,
+}
+
+impl StreamEnd {
+    const fn shift(self) -> u32 {
+        match self {
+            StreamEnd::Front => 0,
+            StreamEnd::Back => 16,
+        }
+    }
+}
+
+/// Packs a section-local index and section index for both ends of the
+/// stream.
+///
+/// Both ends are packed together so that neither may move to a section based
+/// on a stale position of the other, e.g. the consumer moving to the section
+/// the producer is about to clear.
+///
+/// The section-local index is not the fold index and it is independent of fold
+/// size and count.
+#[derive(Debug)]
+pub struct StreamIndex<P: Packet, const FOLDS: usize, const SECTIONS: usize>(
+    AtomicU32,
+    PhantomData<fn() -> P>,
+);
+
+impl<P: Packet, const FOLDS: usize, const SECTIONS: usize> StreamIndex<P, FOLDS, SECTIONS> {
+    const SECTION_CAPACITY: usize = FOLDS * P::Bits::LANES;
+
+    pub fn new(front_section: u8, back_section: u8) -> Self {
+        let encoded = encode(0, front_section) << StreamEnd::Front.shift()
+            | encode(0, back_section) << StreamEnd::Back.shift();
+        Self(AtomicU32::new(encoded), PhantomData)
+    }
+
+    /// Advance `end` to the next local slot in its current section.
+    ///
+    /// # Returns
+    /// The previous local index first with the current section unchanged, or
+    /// [`None`] if all of the section's slots have been exhausted.
+    pub fn advance_local(&self, end: StreamEnd) -> Option<(u8, u8)> {
+        let previous = self.update(|indices| {
+            let (section, local) = decode(indices >> end.shift());
+            if local as usize >= Self::SECTION_CAPACITY {
+                return None;
+            }
+            Some(with_end(indices, end, encode(local + 1, section)))
+        })?;
+
+        let (section, local) = decode(previous >> end.shift());
+        Some((local, section))
+    }
+
+    /// Advance the front to the beginning of the next section, unless it is
+    /// the section of the back.
+    ///
+    /// # Returns
+    /// The new section it has advanced to, or [`None`] if the back is still
+    /// in the next section and the front has not advanced.
+    pub fn advance_front(&self) -> Option<u8> {
+        let previous = self.update(|indices| {
+            let (front, _) = decode(indices >> StreamEnd::Front.shift());
+            let (back, _) = decode(indices >> StreamEnd::Back.shift());
+
+            // wrap around to first section after all have been exhausted
+            let section = ((front as usize + 1) % SECTIONS) as u8;
+            if section == back {
+                return None;
+            }
+            Some(with_end(indices, StreamEnd::Front, encode(0, section)))
+        })?;
+
+        let (front, _) = decode(previous >> StreamEnd::Front.shift());
+        Some(((front as usize + 1) % SECTIONS) as u8)
+    }
+
+    /// Advance the back to the beginning of the next section, unless it is
+    /// the section of the front.
+    ///
+    /// # Returns
+    /// `false` if the back has caught up with the front and has not advanced.
+    pub fn advance_back(&self) -> bool {
+        self.update(|indices| {
+            let (front, _) = decode(indices >> StreamEnd::Front.shift());
+            let (back, _) = decode(indices >> StreamEnd::Back.shift());
+
+            let section = ((back as usize + 1) % SECTIONS) as u8;
+            if section == front {
+                return None;
+            }
+            Some(with_end(indices, StreamEnd::Back, encode(0, section)))
+        })
+        .is_some()
+    }
+
+    fn update(&self, f: impl FnMut(u32) -> Option<u32>) -> Option<u32> {
+        self.0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, f)
+            .ok()
+    }
+
+    pub fn section(&self, end: StreamEnd) -> u8 {
+        self.extract(end).0
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Extract the section index and the local index of the slot of `end`.
+    pub fn extract(&self, end: StreamEnd) -> (u8, u8) {
+        decode(self.get() >> end.shift())
+    }
+}
+
+const fn encode(local: u8, section: u8) -> u32 {
+    (local as u32) << 8 | section as u32
+}
+
+/// Decode the section index and the local index of the lowest 16 bits.
+const fn decode(encoded: u32) -> (u8, u8) {
+    let local = (encoded >> 8 & 0x00FF) as u8;
+    let section = (encoded & 0x00FF) as u8;
+    (section, local)
+}
+
+const fn with_end(indices: u32, end: StreamEnd, encoded: u32) -> u32 {
+    indices & !(0xFFFF << end.shift()) | encoded << end.shift()
+}
+
+/// A 64 bit slot holding [`PacketBits::LANES`] packets, from the highest
+/// bits down.
+#[derive(Debug)]
+pub struct FoldBits<P: Packet>(AtomicU64, PhantomData<fn() -> P>);
+
+impl<P: Packet> FoldBits<P> {
+    const LANE_BITS: u32 = u64::BITS / P::Bits::LANES as u32;
+    const LANE_MASK: u64 = u64::MAX >> (u64::BITS - Self::LANE_BITS);
+
+    pub fn new(int: u64) -> Self {
+        Self(AtomicU64::new(int), PhantomData)
+    }
+
+    const fn shift(lane: usize) -> u32 {
+        u64::BITS - (lane as u32 + 1) * Self::LANE_BITS
+    }
+
+    /// Write `packet` to `lane`, replacing only that lane of the fold.
+    #[inline(always)]
+    pub fn write(&self, lane: usize, packet: P) {
+        self.write_bits(lane, packet.encode());
+    }
+
+    #[inline(always)]
+    pub fn write_bits(&self, lane: usize, bits: P::Bits) {
+        let shift = Self::shift(lane);
+        let bits = (bits.into_u64() & Self::LANE_MASK) << shift;
+        let _ = self
+            .0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |value| {
+                Some(value & !(Self::LANE_MASK << shift) | bits)
+            });
+    }
+
+    /// Take the packet of `lane`, clearing only that lane of the fold.
+    pub fn read(&self, lane: usize) -> Option<P> {
+        let shift = Self::shift(lane);
+        let value = self
+            .0
+            .fetch_and(!(Self::LANE_MASK << shift), Ordering::AcqRel);
+        let bits = value >> shift & Self::LANE_MASK;
+
+        if bits == 0 {
+            return None;
+        }
+        Some(P::decode(P::Bits::from_u64(bits)))
+    }
+
+    /// Clear every lane of the fold.
+    ///
+    /// # Returns
+    /// How many packets were in the fold.
+    pub fn clear(&self) -> u32 {
+        let value = self.0.swap(0, Ordering::AcqRel);
+        (0..P::Bits::LANES)
+            .filter(|&lane| value >> Self::shift(lane) & Self::LANE_MASK != 0)
+            .count() as u32
+    }
+}
+
+/// Folds of two 32 bit packets, left being the highest bits.
+impl<P: Packet<Bits = u32>> FoldBits<P> {
+    /// The bits of both packets, without taking them.
+    pub fn read_bits(&self) -> (u32, u32) {
+        let value = self.0.load(Ordering::Acquire);
+
+        let left = (value >> 32) as u32;
+        let right = (value & 0x00000000FFFFFFFF) as u32;
+
+        (left, right)
+    }
+
+    #[inline(always)]
+    pub fn write_left(&self, packet: P) {
+        self.write(0, packet);
+    }
+
+    #[inline(always)]
+    pub fn write_right(&self, packet: P) {
+        self.write(1, packet);
+    }
+
+    #[inline(always)]
+    pub fn write_left_bits(&self, bits: u32) {
+        self.write_bits(0, bits);
+    }
+
+    #[inline(always)]
+    pub fn write_right_bits(&self, bits: u32) {
+        self.write_bits(1, bits);
+    }
+
+    /// Take the left packet, clearing only the left half of the fold.
+    pub fn read_left(&self) -> Option<P> {
+        self.read(0)
+    }
+
+    /// Take the right packet, clearing only the right half of the fold.
+    pub fn read_right(&self) -> Option<P> {
+        self.read(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum GameEvent {
+        Damage { entity: u32, amount: u16 },
+        Spawn { kind: u8, x: i16, y: i16 },
+    }
+
+    impl Packet for GameEvent {
+        type Bits = u64;
+
+        fn encode(self) -> u64 {
+            match self {
+                GameEvent::Damage { entity, amount } => {
+                    1 << 56 | (amount as u64) << 32 | entity as u64
+                }
+                GameEvent::Spawn { kind, x, y } => {
+                    2 << 56 | (kind as u64) << 32 | (x as u16 as u64) << 16 | y as u16 as u64
+                }
+            }
+        }
+
+        fn decode(bits: u64) -> Self {
+            match bits >> 56 {
+                1 => GameEvent::Damage {
+                    entity: bits as u32,
+                    amount: (bits >> 32) as u16,
+                },
+                _ => GameEvent::Spawn {
+                    kind: (bits >> 32) as u8,
+                    x: (bits >> 16) as u16 as i16,
+                    y: bits as u16 as i16,
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn wide_packets_by_frame() {
+        let stream = EventStream::<GameEvent, 2, 3>::new();
+        let spawn = GameEvent::Spawn {
+            kind: 3,
+            x: -40,
+            y: 12,
+        };
+        let damage = GameEvent::Damage {
+            entity: u32::MAX,
+            amount: 250,
+        };
+
+        // the reader starts right behind the first section written
+        assert!(!stream.frame_back());
+        stream.frame_front();
+        assert!(stream.push_front(spawn));
+        assert!(stream.push_front(damage));
+        assert!(!stream.push_front(damage));
+        assert!(stream.frame_back());
+        assert_eq!(stream.drain_back().count(), 0);
+
+        stream.frame_front();
+        assert!(stream.push_front(damage));
+        assert!(stream.frame_back());
+        assert_eq!(stream.drain_back().collect::<Vec<_>>(), [spawn, damage]);
+        assert_eq!(stream.take_overflow_back(), 1);
+
+        assert!(!stream.frame_back());
+        assert_eq!(stream.drain_back().count(), 0);
+        stream.frame_front();
+        assert!(stream.frame_back());
+        assert_eq!(stream.pop_back(), Some(damage));
+        assert_eq!(stream.pop_back(), None);
+        assert_eq!(stream.dropped(), 1);
+
+        // the writer does not move into the section still being read
+        stream.frame_front();
+        assert!(stream.push_front(spawn));
+        stream.frame_front();
+        assert!(stream.push_front(spawn));
+        assert!(stream.frame_back());
+        assert!(!stream.frame_back());
+        stream.frame_front();
+        assert!(stream.frame_back());
+        assert_eq!(stream.drain_back().collect::<Vec<_>>(), [spawn, spawn]);
+    }
+
+    #[test]
+    fn reader_never_enters_the_written_section() {
+        const FRAMES: u32 = if cfg!(miri) { 100 } else { 20_000 };
+        let stream = std::sync::Arc::new(EventStream::<GameEvent, 2, 3>::new());
+
+        let writer = {
+            let stream = stream.clone();
+            std::thread::spawn(move || {
+                let mut pushed = 0;
+                for frame in 1..=FRAMES {
+                    // overflow every few frames
+                    for amount in 0..(frame % 6) as u16 {
+                        stream.push_front(GameEvent::Damage {
+                            entity: frame,
+                            amount,
+                        });
+                        pushed += 1;
+                    }
+                    stream.frame_front();
+                }
+                pushed
+            })
+        };
+
+        let mut read = 0;
+        let mut dropped = 0;
+        let mut last = (0, 0);
+        let mut check = |stream: &EventStream<GameEvent, 2, 3>| {
+            for packet in stream.drain_back() {
+                let GameEvent::Damage { entity, amount } = packet else {
+                    panic!("unexpected packet {packet:?}");
+                };
+                // in order, a frame's packets are only ever dropped at its end
+                if entity == last.0 {
+                    assert_eq!(amount, last.1 + 1);
+                } else {
+                    assert!(entity > last.0);
+                    assert_eq!(amount, 0);
+                }
+                last = (entity, amount);
+                read += 1;
+            }
+            dropped += stream.take_overflow_back() as u64;
+        };
+
+        while !writer.is_finished() {
+            check(&stream);
+            stream.frame_back();
+        }
+        let pushed = writer.join().unwrap();
+
+        // the last frame may still be in the section being written
+        for _ in 0..2 {
+            loop {
+                check(&stream);
+                if !stream.frame_back() {
+                    break;
+                }
+            }
+            stream.frame_front();
+        }
+
+        // the last section cleared by the writer is never read
+        assert!(dropped <= stream.dropped());
+        assert_eq!(read + stream.dropped(), pushed);
+    }
+}
@@ -1,17 +1,24 @@
-use std::{
-    cell::UnsafeCell,
-    ops::Deref,
-    sync::atomic::{AtomicUsize, Ordering},
-};
+use std::sync::atomic::Ordering;
 
+#[cfg(not(loom))]
+use crate::sync::primitives::CellAccess;
+use crate::sync::primitives::{AtomicUsize, UnsafeCell};
+
+/// A triple buffer advanced by its writer, once per frame.
+///
+/// The reader may keep reading the slot it has last seen until the writer has
+/// advanced twice past it, i.e. it must be at most a frame behind.
+///
+/// For types which are not `Copy`, see [`TripleBuffer`](crate::sync::TripleBuffer).
 #[derive(Debug)]
 pub struct TriCell<T>
 where
     T: Default + Clone + Copy + Send + Sync,
 {
     pointers: [UnsafeCell<T>; 3],
-    write_i: AtomicUsize,
-    read_i: AtomicUsize,
+    /// The write and read indices, packed so that they are always advanced
+    /// together.
+    indices: AtomicUsize,
 }
 
 unsafe impl<T: Default + Clone + Copy + Sync + Send> Send for TriCell<T> {}
@@ -27,8 +34,7 @@ where
     fn default() -> Self {
         Self {
             pointers: Default::default(),
-            write_i: AtomicUsize::new(1),
-            read_i: AtomicUsize::new(0),
+            indices: AtomicUsize::new(pack(1, 0)),
         }
     }
 }
@@ -44,27 +50,39 @@ where
                 UnsafeCell::default(),
                 UnsafeCell::default(),
             ],
-            write_i: AtomicUsize::new(1),
-            read_i: AtomicUsize::new(0),
+            indices: AtomicUsize::new(pack(1, 0)),
         }
     }
 
-    pub unsafe fn read_raw(&self) -> &UnsafeCell<T> {
-        let read_i = self.read_i.load(Ordering::Relaxed);
+    /// The slot last published to the reader.
+    ///
+    /// # Safety
+    /// The slot must only be read, and the reader must stay at most one
+    /// advance behind the writer for as long as it reads it.
+    pub(crate) unsafe fn read_raw(&self) -> &UnsafeCell<T> {
+        // pairs with the release of `advance`, so that the writes to the slot
+        // are visible
+        let (_, read_i) = unpack(self.indices.load(Ordering::Acquire));
         &self.pointers[read_i]
     }
 
-    pub unsafe fn write_raw(&self) -> &UnsafeCell<T> {
-        let write_i = self.write_i.load(Ordering::Relaxed);
+    /// The slot being written.
+    ///
+    /// # Safety
+    /// Only the single writer, which is also the only one to advance the
+    /// indices, may access the slot.
+    pub(crate) unsafe fn write_raw(&self) -> &UnsafeCell<T> {
+        // only the writer changes the indices
+        let (write_i, _) = unpack(self.indices.load(Ordering::Relaxed));
         &self.pointers[write_i]
     }
 
-    /// Advances the internal read and write indices of the triple buffer.
+    /// Advances the internal read and write indices of the triple buffer,
+    /// publishing the current `write` section to the reader.
     ///
-    /// This will also reset the value stored in the previous `read` index,
-    /// allowing for a fresh `Default` value in its place once that section is
-    /// used again in a future frame.
-    /// This is only done if the advance operation succeeds.
+    /// This will also reset the value stored in the new `write` section,
+    /// last read two advances ago, allowing for a fresh `Default` value in
+    /// its place.
     ///
     /// # Returns
     /// The previous (before advancing) write and read index, respectively.
@@ -73,18 +91,26 @@ where
     /// has advanced, `Err` if it failed to advance. These contain the
     /// (previous, if `Ok`; current if `Err`) index value to use.
     pub fn advance(&self) -> (AdvanceResult, AdvanceResult) {
-        let write = self
-            .write_i
-            .fetch_update(Ordering::Release, Ordering::Acquire, |i| Some((i + 1) % 3));
-        let read = self
-            .read_i
-            .fetch_update(Ordering::Release, Ordering::Acquire, |i| Some((i + 1) % 3));
-
-        if let Ok(ri) = read {
-            unsafe { *self.pointers[ri].get() = T::default() };
-        }
+        // both indices move in a single step, the reader never sees the
+        // write index catch up with the read index
+        let previous = self
+            .indices
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |indices| {
+                let (write_i, _) = unpack(indices);
+                Some(pack((write_i + 1) % 3, write_i))
+            });
 
-        (write, read)
+        match previous {
+            Ok(indices) => {
+                let (write_i, read_i) = unpack(indices);
+                self.pointers[(write_i + 1) % 3].with_mut(|slot| unsafe { *slot = T::default() });
+                (Ok(write_i), Ok(read_i))
+            }
+            Err(indices) => {
+                let (write_i, read_i) = unpack(indices);
+                (Err(write_i), Err(read_i))
+            }
+        }
     }
 
     /// Update the value stored in the current section, first fetching the
@@ -94,9 +120,9 @@ where
     /// operations in one frame.
     pub fn set_with<F: FnOnce(T) -> T>(&self, update_op: F) {
         let raw = unsafe { self.write_raw() };
-        let t = unsafe { *raw.get() };
+        let t = raw.with(|slot| unsafe { *slot });
         let new = update_op(t);
-        unsafe { (*raw.get()) = new }
+        raw.with_mut(|slot| unsafe { *slot = new });
     }
 
     /// Update the `value` stored in the current 'write' section and advance
@@ -116,17 +142,16 @@ where
     /// `Ok` with the previously stored value or `Err` if the index could not
     /// be updated.
     pub fn set_and_advance(&self, value: T) -> Result<T, ()> {
-        if let (Ok(index), _) = self.advance() {
-            let raw = self.pointers[index].get();
-            let prev = unsafe { *raw };
+        // the value must be written before the section is published
+        let raw = unsafe { self.write_raw() };
+        let prev = raw.with_mut(|slot| unsafe { std::ptr::replace(slot, value) });
 
-            unsafe {
-                *raw = value;
+        match self.advance() {
+            (Ok(_), _) => Ok(prev),
+            (Err(_), _) => {
+                raw.with_mut(|slot| unsafe { *slot = prev });
+                Err(())
             }
-
-            Ok(prev)
-        } else {
-            Err(())
         }
     }
 
@@ -136,30 +161,105 @@ where
     /// manually per-frame with [`TriCell::advance`], or consider using
     /// [`TriCell::set_and_advance`].
     pub fn set(&self, value: T) {
-        unsafe { *self.write_raw().get() = value }
+        unsafe { self.write_raw() }.with_mut(|slot| unsafe { *slot = value });
     }
 
     /// Get the stored value in the current section and advance the index.
     ///
     /// The stored value is returned even if the index fails to advance.
     pub fn get(&self) -> T {
-        unsafe { *self.read_raw().get() }
+        unsafe { self.read_raw() }.with(|slot| unsafe { *slot })
     }
 
-    pub fn get_ref(&self) -> &T {
-        unsafe {
-            self.read_raw()
-                .get()
-                .as_ref()
-                .expect("pointer is never null")
-        }
+    /// Borrow the stored value in the current section, without copying it.
+    ///
+    /// # Safety
+    /// The reference must be dropped before the writer has advanced twice
+    /// past the current section, after which it is reset and written again.
+    /// Prefer [`TriCell::get`] unless the reader is known to be at most a
+    /// frame behind for as long as the reference is held.
+    pub unsafe fn get_ref(&self) -> &T {
+        unsafe { self.read_raw() }
+            .with(|slot| unsafe { slot.as_ref().expect("pointer is never null") })
     }
 }
 
-impl<T: Default + Clone + Copy + Send + Sync> Deref for TriCell<T> {
-    type Target = T;
+const fn pack(write_i: usize, read_i: usize) -> usize {
+    write_i | read_i << 2
+}
+
+const fn unpack(indices: usize) -> (usize, usize) {
+    (indices & 0b11, indices >> 2 & 0b11)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        thread,
+    };
+
+    use super::*;
+
+    const FRAMES: usize = if cfg!(miri) { 50 } else { 10_000 };
+
+    #[test]
+    fn frames_are_published_whole() {
+        let cell = Arc::new(TriCell::<[usize; 4]>::default());
+        let advanced = Arc::new(AtomicUsize::new(0));
+        let read = Arc::new(AtomicUsize::new(0));
+
+        let reader = {
+            let (cell, advanced, read) = (cell.clone(), advanced.clone(), read.clone());
+            thread::spawn(move || {
+                let mut last = 0;
+                while last < FRAMES {
+                    if advanced.load(Ordering::Acquire) <= last {
+                        thread::yield_now();
+                        continue;
+                    }
+
+                    // the writer may be a frame ahead by now
+                    let frame = cell.get();
+                    assert!(frame.iter().all(|&value| value == frame[0]));
+                    assert!(frame[0] > last);
+                    last = frame[0];
+                    read.store(last, Ordering::Release);
+                }
+            })
+        };
+
+        for frame in 1..=FRAMES {
+            cell.set_with(|values| {
+                // every section starts fresh
+                assert_eq!(values, [0; 4]);
+                [frame; 4]
+            });
+
+            // the reader must be at most a frame behind
+            while read.load(Ordering::Acquire) + 2 < frame {
+                thread::yield_now();
+            }
+            assert_eq!(cell.advance(), (Ok(frame % 3), Ok((frame + 2) % 3)));
+            advanced.store(frame, Ordering::Release);
+        }
+        reader.join().unwrap();
+    }
+
+    #[test]
+    fn set_and_advance_publishes_the_value() {
+        let cell = TriCell::new(1);
+        assert_eq!(cell.get(), 1);
+        assert_eq!(cell.set_and_advance(2), Ok(0));
+        assert_eq!(cell.get(), 2);
 
-    fn deref(&self) -> &Self::Target {
-        self.get_ref()
+        cell.set(3);
+        let _ = cell.advance();
+        assert_eq!(cell.get(), 3);
+        assert_eq!(cell.set_and_advance(4), Ok(0));
+        assert_eq!(cell.get(), 4);
     }
 }
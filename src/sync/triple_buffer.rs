@@ -0,0 +1,246 @@
+use std::sync::{Arc, atomic::Ordering};
+
+#[cfg(not(loom))]
+use crate::sync::primitives::CellAccess;
+use crate::sync::primitives::{AtomicU8, UnsafeCell};
+
+/// Set on the shared slot index once a new value has been published to it,
+/// and cleared once the reader has taken it.
+const FRESH_BIT: u8 = 0b100;
+const INDEX_MASK: u8 = 0b011;
+
+/// A triple buffer for any type, split into a [`Writer`] and a [`Reader`].
+///
+/// Each half owns one of the three slots, and the third is shared. Publishing
+/// exchanges the writer's slot with the shared one, and reading exchanges
+/// the shared slot with the reader's if it holds a value it has not seen,
+/// so that values are never copied nor dropped when they change hands.
+///
+/// Unlike [`TriCell`](crate::sync::TriCell), the writer may publish any amount
+/// of times between two reads, with the reader only ever seeing the latest
+/// value.
+#[derive(Debug)]
+pub struct TripleBuffer<T> {
+    slots: [UnsafeCell<T>; 3],
+    /// The index of the shared slot, along with the [`FRESH_BIT`].
+    shared: AtomicU8,
+}
+
+impl<T: Clone> TripleBuffer<T> {
+    /// Create a triple buffer with every slot holding a clone of `value`.
+    pub fn new(value: T) -> Self {
+        Self::from_slots([value.clone(), value.clone(), value])
+    }
+}
+
+impl<T: Default> Default for TripleBuffer<T> {
+    fn default() -> Self {
+        Self::from_slots(Default::default())
+    }
+}
+
+impl<T> TripleBuffer<T> {
+    /// Create a triple buffer from its three `slots`.
+    ///
+    /// The first is initially read, the second written and the third shared.
+    pub fn from_slots(slots: [T; 3]) -> Self {
+        Self {
+            slots: slots.map(UnsafeCell::new),
+            shared: AtomicU8::new(2),
+        }
+    }
+
+    pub fn split(self) -> (Writer<T>, Reader<T>) {
+        let buffer = Arc::new(self);
+        let writer = Writer {
+            buffer: buffer.clone(),
+            slot: 1,
+        };
+        let reader = Reader { buffer, slot: 0 };
+        (writer, reader)
+    }
+}
+
+/// The writing half of a [`TripleBuffer`].
+#[derive(Debug)]
+pub struct Writer<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    slot: u8,
+}
+
+// SAFETY: the slot owned by the writer is only ever accessed through it, and
+// the shared slot changes hands through the atomic exchange.
+unsafe impl<T: Send> Send for Writer<T> {}
+unsafe impl<T: Sync> Sync for Writer<T> {}
+
+impl<T> Writer<T> {
+    /// The back buffer, to be written to and then
+    /// [`published`](Writer::publish).
+    ///
+    /// It holds a value published some time ago, or the initial value, which
+    /// may be cleared and reused in place, e.g. to keep the capacity of a
+    /// `Vec`.
+    pub fn back(&self) -> &T {
+        // SAFETY: only the writer ever accesses its slot
+        self.buffer.slots[self.slot as usize].with(|slot| unsafe { &*slot })
+    }
+
+    /// See [`Writer::back`].
+    pub fn back_mut(&mut self) -> &mut T {
+        // SAFETY: only the writer ever accesses its slot
+        self.buffer.slots[self.slot as usize].with_mut(|slot| unsafe { &mut *slot })
+    }
+
+    /// Publish the back buffer to the reader, replacing any value it has not
+    /// read yet, and take the shared slot as the new back buffer.
+    pub fn publish(&mut self) {
+        // release the writes to the back buffer, and acquire the reader's
+        // last accesses to the slot it has given back
+        let shared = self
+            .buffer
+            .shared
+            .swap(self.slot | FRESH_BIT, Ordering::AcqRel);
+        self.slot = shared & INDEX_MASK;
+    }
+
+    /// Replace the back buffer with `value` and [`publish`](Writer::publish)
+    /// it.
+    ///
+    /// # Returns
+    /// The value that was replaced.
+    pub fn write(&mut self, value: T) -> T {
+        let previous = std::mem::replace(self.back_mut(), value);
+        self.publish();
+        previous
+    }
+
+    /// Whether the last published value has been read.
+    pub fn consumed(&self) -> bool {
+        self.buffer.shared.load(Ordering::Relaxed) & FRESH_BIT == 0
+    }
+}
+
+/// The reading half of a [`TripleBuffer`].
+#[derive(Debug)]
+pub struct Reader<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    slot: u8,
+}
+
+// SAFETY: the slot owned by the reader is only ever accessed through it, and
+// the shared slot changes hands through the atomic exchange.
+unsafe impl<T: Send> Send for Reader<T> {}
+unsafe impl<T: Sync> Sync for Reader<T> {}
+
+impl<T> Reader<T> {
+    /// Whether a value has been published since the last
+    /// [`update`](Reader::update).
+    pub fn has_update(&self) -> bool {
+        self.buffer.shared.load(Ordering::Relaxed) & FRESH_BIT != 0
+    }
+
+    /// Take the latest published value, if there is one the reader has not
+    /// seen.
+    ///
+    /// # Returns
+    /// Whether the value has changed.
+    pub fn update(&mut self) -> bool {
+        if !self.has_update() {
+            return false;
+        }
+
+        // acquire the writes to the published slot, and release the accesses
+        // to the slot given back to the writer
+        let shared = self.buffer.shared.swap(self.slot, Ordering::AcqRel);
+        self.slot = shared & INDEX_MASK;
+        true
+    }
+
+    /// [`Update`](Reader::update) and get the latest published value.
+    pub fn read(&mut self) -> &T {
+        self.update();
+        self.current()
+    }
+
+    /// The value taken by the last [`update`](Reader::update), without
+    /// checking for a newer one.
+    pub fn current(&self) -> &T {
+        // SAFETY: only the reader ever accesses its slot
+        self.buffer.slots[self.slot as usize].with(|slot| unsafe { &*slot })
+    }
+
+    /// See [`Reader::current`].
+    ///
+    /// The value may be modified freely, it is handed back to the writer to
+    /// be reused once a newer value has been taken.
+    pub fn current_mut(&mut self) -> &mut T {
+        // SAFETY: only the reader ever accesses its slot
+        self.buffer.slots[self.slot as usize].with_mut(|slot| unsafe { &mut *slot })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn reader_sees_the_latest_value() {
+        let (mut writer, mut reader) = TripleBuffer::new(Vec::<u32>::new()).split();
+        assert!(!reader.update());
+        assert!(reader.read().is_empty());
+
+        writer.back_mut().extend([1, 2, 3]);
+        writer.publish();
+        assert!(!writer.consumed());
+        assert!(writer.write(vec![4]).is_empty());
+        assert!(reader.has_update());
+        assert_eq!(reader.read(), &[4]);
+        assert!(writer.consumed());
+        assert!(!reader.update());
+        assert_eq!(reader.current(), &[4]);
+
+        // the overwritten value comes back around to be reused
+        let back = writer.back_mut();
+        assert_eq!(back, &[1, 2, 3]);
+        let capacity = back.capacity();
+        back.clear();
+        back.push(5);
+        writer.publish();
+        assert!(reader.update());
+        assert_eq!(reader.current(), &[5]);
+        assert_eq!(reader.current().capacity(), capacity);
+    }
+
+    #[test]
+    fn concurrent_write_and_read() {
+        const VALUES: usize = if cfg!(miri) { 100 } else { 20_000 };
+
+        let (mut writer, mut reader) = TripleBuffer::<Vec<usize>>::default().split();
+        let producer = thread::spawn(move || {
+            for value in 1..=VALUES {
+                let back = writer.back_mut();
+                back.clear();
+                back.resize(value % 16 + 1, value);
+                writer.publish();
+            }
+        });
+
+        let mut last = 0;
+        while last < VALUES {
+            if !reader.update() {
+                thread::yield_now();
+                continue;
+            }
+
+            let values = reader.current();
+            let value = values[0];
+            assert!(value > last);
+            assert_eq!(values.len(), value % 16 + 1);
+            assert!(values.iter().all(|&v| v == value));
+            last = value;
+        }
+        producer.join().unwrap();
+    }
+}
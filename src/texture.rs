@@ -81,7 +81,7 @@ pub fn unbind(target: TextureKind, unit: u32) {
 }
 
 #[inline(always)]
-fn load_image<P: AsRef<Path>>(path: P) -> Result<DynamicImage, ImageError> {
+pub(crate) fn load_image<P: AsRef<Path>>(path: P) -> Result<DynamicImage, ImageError> {
     ImageReader::open(path)?.with_guessed_format()?.decode()
 }
 
@@ -17,7 +17,7 @@ use winit::{
 };
 
 use crate::{
-    context::{Context, Draw, Setup, StateHandle, Update},
+    context::{Clock, Context, Draw, Setup, StateHandle, Update},
     gl::{self, get_gl_string},
 };
 
@@ -71,11 +71,13 @@ impl DisplayParameters {
     }
 }
 
-impl<Init, State, Render> ApplicationHandler for Context<Init, State, Render>
+impl<Init, State, Render, C, const SLOTS: usize, const SECTIONS: usize> ApplicationHandler
+    for Context<Init, State, Render, C, SLOTS, SECTIONS>
 where
     Init: Setup<State, Render>,
     State: Update + Default + Sync + Send + 'static,
     Render: Draw + Default,
+    C: Clock,
 {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         let (window, config) = match &self.gl_display {
@@ -198,13 +200,15 @@ where
     #[cfg(feature = "input")]
     fn new_events(
         &mut self,
-        _event_loop: &winit::event_loop::ActiveEventLoop,
+        event_loop: &winit::event_loop::ActiveEventLoop,
         cause: winit::event::StartCause,
     ) {
         if cause == StartCause::Poll {
             #[cfg(feature = "input")]
-            self.sync_cursor_options();
+            self.sync_cursor_options(event_loop);
+            self.sync_ime_options();
 
+            self.input_dispatcher.poll_gamepads();
             self.input_dispatcher.sync();
         }
     }
@@ -219,12 +223,22 @@ where
             WindowEvent::RedrawRequested => {
                 if let Some(DisplayHandle { gl_surface, window }) = self.display.as_ref() {
                     let ctx = self.gl_ctx.as_ref().unwrap();
+                    let stats = &self.frame_stats;
+
+                    let _frame_span = stats.tracing_enabled().then(|| {
+                        let frame = stats.render().frames();
+                        tracing::trace_span!("context.render.frame", frame).entered()
+                    });
 
                     let delta = &mut self.render_delta;
                     self.renderer.draw(delta.delta());
                     delta.sync();
 
+                    let swap_start = self.clock.now();
                     gl_surface.swap_buffers(ctx).unwrap();
+                    let swap_time = self.clock.now().duration_since(swap_start);
+                    stats.render().record_frame(delta.delta_time(), swap_time);
+
                     window.request_redraw();
                 }
             }
@@ -248,6 +262,12 @@ where
             window_ev => {
                 self.input_dispatcher.handle_mouse_events(&window_ev);
                 self.input_dispatcher.handle_key_event(&window_ev);
+                self.input_dispatcher.handle_text_event(&window_ev);
+                self.input_dispatcher.handle_focus_events(&window_ev);
+                self.input_dispatcher.handle_touch_events(&window_ev);
+                if let Some(event) = self.input_dispatcher.handle_file_events(&window_ev) {
+                    self.renderer.file_event(&event);
+                }
             }
 
             #[cfg(not(feature = "input"))]
@@ -0,0 +1,287 @@
+//! Model-checked tests of the lock-free primitives in `janus::sync`, run with:
+//!
+//! ```sh
+//! RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//! ```
+
+#![cfg(loom)]
+
+use std::sync::Arc;
+
+use janus::sync::{EventStream, Mirror, Packet, RingBuffer, TriCell, TripleBuffer};
+use loom::thread;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Event(u32);
+
+impl Packet for Event {
+    type Bits = u32;
+
+    fn encode(self) -> u32 {
+        self.0
+    }
+
+    fn decode(bits: u32) -> Self {
+        Self(bits)
+    }
+}
+
+#[test]
+fn tricell_publishes_after_writing() {
+    loom::model(|| {
+        let cell = Arc::new(TriCell::new([0u32; 2]));
+
+        let writer = {
+            let cell = cell.clone();
+            thread::spawn(move || {
+                cell.set([1; 2]);
+                let _ = cell.advance();
+                // the next frame is written while the reader may still read
+                // the last one
+                cell.set([2; 2]);
+            })
+        };
+
+        let values = cell.get();
+        assert!(values == [0; 2] || values == [1; 2]);
+
+        writer.join().unwrap();
+        assert_eq!(cell.get(), [1; 2]);
+        let _ = cell.advance();
+        assert_eq!(cell.get(), [2; 2]);
+    });
+}
+
+#[test]
+fn tricell_set_and_advance() {
+    loom::model(|| {
+        let cell = Arc::new(TriCell::new(0u32));
+
+        let writer = {
+            let cell = cell.clone();
+            thread::spawn(move || {
+                let _ = cell.set_and_advance(1);
+            })
+        };
+
+        let value = cell.get();
+        assert!(value == 0 || value == 1);
+        writer.join().unwrap();
+        assert_eq!(cell.get(), 1);
+    });
+}
+
+#[test]
+fn triple_buffer_hands_over_whole_values() {
+    loom::model(|| {
+        let (mut writer, mut reader) = TripleBuffer::new(vec![0u32]).split();
+
+        let writer = thread::spawn(move || {
+            for value in 1..=2 {
+                let back = writer.back_mut();
+                back.clear();
+                back.extend([value; 2]);
+                writer.publish();
+            }
+        });
+
+        let mut last = 0;
+        for _ in 0..2 {
+            let values = reader.read();
+            assert!(values == &[last] || values.len() == 2);
+            assert!(values[0] >= last);
+            assert!(values.iter().all(|&v| v == values[0]));
+            last = values[0];
+        }
+
+        writer.join().unwrap();
+        assert_eq!(reader.read(), &[2, 2]);
+    });
+}
+
+#[test]
+fn ring_buffer_moves_values_in_order() {
+    loom::model(|| {
+        let (mut writer, mut reader) = RingBuffer::<Vec<u32>, 2>::new().split();
+
+        let writer = thread::spawn(move || {
+            let mut pushed = Vec::new();
+            for value in 1..=3 {
+                if writer.push(vec![value; 2]).is_ok() {
+                    pushed.push(value);
+                }
+            }
+            pushed
+        });
+
+        let mut popped = Vec::new();
+        for _ in 0..2 {
+            if let Some(values) = reader.pop() {
+                assert_eq!(values[0], values[1]);
+                popped.push(values[0]);
+            }
+        }
+
+        let pushed = writer.join().unwrap();
+        while let Some(values) = reader.pop() {
+            popped.push(values[0]);
+        }
+        assert!(pushed.len() >= 2);
+        assert_eq!(popped, pushed);
+    });
+}
+
+#[test]
+fn stream_sections_in_order() {
+    loom::model(|| {
+        let stream = Arc::new(EventStream::<Event, 1, 3>::new());
+
+        let writer = {
+            let stream = stream.clone();
+            thread::spawn(move || {
+                for frame in 1..=3 {
+                    stream.push_front(Event(frame));
+                    stream.frame_front();
+                }
+            })
+        };
+
+        let mut read = Vec::new();
+        for _ in 0..2 {
+            read.extend(stream.drain_back());
+            stream.frame_back();
+        }
+        writer.join().unwrap();
+
+        // the last frames may still be in the section being written
+        for _ in 0..2 {
+            loop {
+                read.extend(stream.drain_back());
+                if !stream.frame_back() {
+                    break;
+                }
+            }
+            stream.frame_front();
+        }
+
+        // frames are never read twice, out of order or lost without being
+        // counted
+        assert!(read.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(read.len() as u64 + stream.dropped(), 3);
+    });
+}
+
+#[test]
+fn stream_fold_lanes_are_independent() {
+    loom::model(|| {
+        let stream = Arc::new(EventStream::<Event, 1, 3>::new());
+        // the reader is right behind the section the writer moves to
+        stream.frame_front();
+        stream.frame_back();
+
+        let writer = {
+            let stream = stream.clone();
+            thread::spawn(move || {
+                stream.push_front(Event(1));
+                stream.push_front(Event(2));
+                stream.frame_front();
+            })
+        };
+
+        let mut read = Vec::new();
+        stream.frame_back();
+        read.extend(stream.drain_back());
+        writer.join().unwrap();
+        stream.frame_back();
+        read.extend(stream.drain_back());
+
+        assert_eq!(read, [Event(1), Event(2)]);
+    });
+}
+
+#[test]
+fn mirror_sync_is_consistent() {
+    loom::model(|| {
+        let mut reader = Mirror::new((0u32, 0u32));
+        let mut writer = reader.clone();
+
+        let publisher = thread::spawn(move || {
+            writer.publish((1, 1));
+            writer.publish_with(|values| *values = (2, 2));
+        });
+
+        let _ = reader.sync_noblock();
+        let first = *reader.get();
+        reader.sync().unwrap();
+        let second = *reader.get();
+        assert_eq!(first.0, first.1);
+        assert_eq!(second.0, second.1);
+        assert!(first.0 <= second.0);
+
+        publisher.join().unwrap();
+        reader.sync().unwrap();
+        assert_eq!(*reader.get(), (2, 2));
+        assert!(reader.check_sync_status());
+    });
+}
+
+#[test]
+fn mirror_listeners_see_publish_order() {
+    loom::model(|| {
+        let mut a = Mirror::new(0u32);
+        let mut b = a.clone();
+
+        let seen = Arc::new(loom::sync::Mutex::new(Vec::new()));
+        {
+            let seen = seen.clone();
+            a.on_change(move |&value| seen.lock().unwrap().push(value));
+        }
+
+        let publisher = thread::spawn(move || {
+            b.publish(2);
+        });
+        a.publish(1);
+        publisher.join().unwrap();
+
+        // the last value seen is the one left shared
+        a.sync().unwrap();
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.last(), Some(a.get()));
+        assert!(seen.len() <= 2);
+    });
+}
+
+#[test]
+fn mirror_heap_values() {
+    loom::model(|| {
+        let mut reader = Mirror::new(vec![0u32]);
+        let mut writer = reader.clone();
+
+        let publisher = thread::spawn(move || {
+            writer.publish(vec![1, 1]);
+        });
+
+        reader.sync().unwrap();
+        assert!(*reader == [0] || *reader == [1, 1]);
+
+        publisher.join().unwrap();
+        reader.sync().unwrap();
+        assert_eq!(*reader, [1, 1]);
+    });
+}
+
+#[test]
+fn mirror_wait_for_change_is_woken() {
+    loom::model(|| {
+        let mut reader = Mirror::new(0u32);
+        let mut writer = reader.clone();
+
+        let publisher = thread::spawn(move || {
+            writer.publish(1);
+        });
+
+        reader.wait_for_change();
+        assert_eq!(*reader.get(), 1);
+        publisher.join().unwrap();
+    });
+}